@@ -0,0 +1,279 @@
+use chrono::{DateTime, Duration, Utc};
+use std::time::Duration as StdDuration;
+use serde::Serialize;
+use crate::error::VolatilityError;
+use crate::volatility::VolatilityCalculator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolatilityZone {
+    Low,
+    Normal,
+    High,
+}
+
+// Both alert kinds go to the same webhook, so each carries a `type` field
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename = "volatility_threshold")]
+pub struct VolatilityAlert {
+    pub timestamp: DateTime<Utc>,
+    pub volatility: f64,
+    pub from: VolatilityZone,
+    pub to: VolatilityZone,
+}
+
+// Band around each level, in percent of it, that volatility has to clear on
+// the way back before the zone changes again
+pub const DEFAULT_ALERT_HYSTERESIS_PCT: f64 = 5.0;
+
+// How long a webhook POST may take before it's abandoned
+pub const DEFAULT_WEBHOOK_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+// Tracks which side of the configured levels volatility is on and reports
+// only the transitions, so a reading that stays above the line alerts once.
+// Leaving a zone takes a move past the level by the hysteresis band, so a
+// reading hovering on the line doesn't alert every tick.
+pub struct ThresholdMonitor {
+    high: Option<f64>,
+    low: Option<f64>,
+    hysteresis_pct: f64,
+    zone: VolatilityZone,
+}
+
+impl ThresholdMonitor {
+    pub fn new(high: Option<f64>, low: Option<f64>) -> Self {
+        Self {
+            high,
+            low,
+            hysteresis_pct: DEFAULT_ALERT_HYSTERESIS_PCT,
+            zone: VolatilityZone::Normal,
+        }
+    }
+
+    pub fn with_hysteresis_pct(mut self, pct: f64) -> Self {
+        self.hysteresis_pct = pct;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.high.is_some() || self.low.is_some()
+    }
+
+    pub fn zone(&self) -> VolatilityZone {
+        self.zone
+    }
+
+    fn classify(&self, volatility: f64) -> VolatilityZone {
+        let band = self.hysteresis_pct / 100.0;
+        match self.zone {
+            VolatilityZone::High if self.high.is_some_and(|high| volatility > high * (1.0 - band)) => {
+                return VolatilityZone::High;
+            }
+            VolatilityZone::Low if self.low.is_some_and(|low| volatility < low * (1.0 + band)) => {
+                return VolatilityZone::Low;
+            }
+            _ => {}
+        }
+        if self.high.is_some_and(|high| volatility > high) {
+            VolatilityZone::High
+        } else if self.low.is_some_and(|low| volatility < low) {
+            VolatilityZone::Low
+        } else {
+            VolatilityZone::Normal
+        }
+    }
+
+    pub fn update(&mut self, timestamp: DateTime<Utc>, volatility: f64) -> Option<VolatilityAlert> {
+        let zone = self.classify(volatility);
+        if zone == self.zone {
+            return None;
+        }
+        let alert = VolatilityAlert {
+            timestamp,
+            volatility,
+            from: self.zone,
+            to: zone,
+        };
+        self.zone = zone;
+        Some(alert)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename = "volatility_regime")]
+pub struct RegimeAlert {
+    pub timestamp: DateTime<Utc>,
+    pub short_volatility: f64,
+    pub long_volatility: f64,
+    pub ratio: f64,
+    // True when the ratio rose above the threshold, false when it fell back
+    pub elevated: bool,
+}
+
+// Compares a short window's volatility with a long one's and reports when
+// their ratio crosses `threshold`, catching a sudden expansion the long
+// window smooths over. Like ThresholdMonitor only transitions alert: once
+// on the way up and once on the way back down.
+pub struct VolatilityRegimeMonitor {
+    short_window: Duration,
+    long_window: Duration,
+    threshold: f64,
+    elevated: bool,
+}
+
+impl VolatilityRegimeMonitor {
+    pub fn new(short_window: Duration, long_window: Duration, threshold: f64) -> Self {
+        Self {
+            short_window,
+            long_window,
+            threshold,
+            elevated: false,
+        }
+    }
+
+    pub fn is_elevated(&self) -> bool {
+        self.elevated
+    }
+
+    // Both windows sliced from the calculator's history, which has to span
+    // the long one
+    pub fn check(&mut self, timestamp: DateTime<Utc>, calculator: &VolatilityCalculator) -> Option<RegimeAlert> {
+        let estimates = calculator.calculate_windows(&[self.short_window, self.long_window]);
+        let short = estimates[0].1.filter(|v| v.is_finite())?;
+        let long = estimates[1].1.filter(|v| v.is_finite())?;
+        self.update(timestamp, short, long)
+    }
+
+    pub fn update(&mut self, timestamp: DateTime<Utc>, short_volatility: f64, long_volatility: f64) -> Option<RegimeAlert> {
+        if long_volatility <= 0.0 {
+            return None;
+        }
+        let ratio = short_volatility / long_volatility;
+        let elevated = ratio > self.threshold;
+        if elevated == self.elevated {
+            return None;
+        }
+        self.elevated = elevated;
+        Some(RegimeAlert {
+            timestamp,
+            short_volatility,
+            long_volatility,
+            ratio,
+            elevated,
+        })
+    }
+}
+
+// Posts alerts as JSON to a webhook
+pub struct AlertSink {
+    webhook_url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl AlertSink {
+    pub fn new(webhook_url: Option<String>) -> Self {
+        Self {
+            webhook_url,
+            client: reqwest::Client::builder()
+                .timeout(DEFAULT_WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn emit(&self, alert: &impl Serialize) -> Result<(), VolatilityError> {
+        if let Some(url) = &self.webhook_url {
+            self.client.post(url)
+                .json(alert)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn threshold_needs_to_clear_the_band_to_leave_a_zone() {
+        // High at 0.8 with a 5% band: back to normal only below 0.76
+        let mut monitor = ThresholdMonitor::new(Some(0.8), Some(0.2));
+
+        let alert = monitor.update(at(0), 0.81).unwrap();
+        assert_eq!((alert.from, alert.to), (VolatilityZone::Normal, VolatilityZone::High));
+        // Dipping under the line but not the band is still high
+        assert!(monitor.update(at(60), 0.79).is_none());
+        assert!(monitor.update(at(120), 0.77).is_none());
+        assert!(monitor.update(at(180), 0.81).is_none());
+        let alert = monitor.update(at(240), 0.75).unwrap();
+        assert_eq!((alert.from, alert.to), (VolatilityZone::High, VolatilityZone::Normal));
+
+        // Low at 0.2: back to normal only above 0.21
+        assert_eq!(monitor.update(at(300), 0.19).unwrap().to, VolatilityZone::Low);
+        assert!(monitor.update(at(360), 0.205).is_none());
+        assert_eq!(monitor.update(at(420), 0.22).unwrap().to, VolatilityZone::Normal);
+    }
+
+    #[test]
+    fn threshold_without_hysteresis_flips_on_the_line() {
+        let mut monitor = ThresholdMonitor::new(Some(0.8), None).with_hysteresis_pct(0.0);
+        assert_eq!(monitor.update(at(0), 0.81).unwrap().to, VolatilityZone::High);
+        assert_eq!(monitor.update(at(60), 0.79).unwrap().to, VolatilityZone::Normal);
+    }
+
+    #[test]
+    fn regime_alerts_once_each_way() {
+        let mut monitor = VolatilityRegimeMonitor::new(Duration::minutes(15), Duration::hours(24), 2.0);
+
+        // 0.9 / 0.5 = 1.8x: below the threshold, nothing to report
+        assert!(monitor.update(at(0), 0.9, 0.5).is_none());
+        // 1.2 / 0.5 = 2.4x crosses it
+        let alert = monitor.update(at(60), 1.2, 0.5).unwrap();
+        assert!(alert.elevated);
+        assert!((alert.ratio - 2.4).abs() < 1e-12);
+        // Still above, so no repeat
+        assert!(monitor.update(at(120), 1.5, 0.5).is_none());
+        // 0.8 / 0.5 = 1.6x is back below
+        let alert = monitor.update(at(180), 0.8, 0.5).unwrap();
+        assert!(!alert.elevated);
+        assert!(!monitor.is_elevated());
+    }
+
+    #[test]
+    fn regime_ignores_a_zero_long_window() {
+        let mut monitor = VolatilityRegimeMonitor::new(Duration::minutes(15), Duration::hours(24), 2.0);
+        assert!(monitor.update(at(0), 1.0, 0.0).is_none());
+        assert!(!monitor.is_elevated());
+    }
+
+    #[test]
+    fn alert_payloads_name_their_type() {
+        let regime = RegimeAlert {
+            timestamp: at(0),
+            short_volatility: 1.2,
+            long_volatility: 0.5,
+            ratio: 2.4,
+            elevated: true,
+        };
+        let json = serde_json::to_value(&regime).unwrap();
+        assert_eq!(json["type"], "volatility_regime");
+        assert_eq!(json["ratio"], 2.4);
+
+        let threshold = VolatilityAlert {
+            timestamp: at(0),
+            volatility: 1.1,
+            from: VolatilityZone::Normal,
+            to: VolatilityZone::High,
+        };
+        let json = serde_json::to_value(&threshold).unwrap();
+        assert_eq!(json["type"], "volatility_threshold");
+        assert_eq!(json["to"], "high");
+    }
+}
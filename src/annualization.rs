@@ -0,0 +1,228 @@
+use std::str::FromStr;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+// Scales a per-sample standard deviation to an annual figure
+pub trait Annualizer: Send + Sync {
+    // `interval_seconds` is the observed average spacing between samples
+    fn annualize(&self, period_stddev: f64, interval_seconds: f64) -> f64;
+
+    // When true the calculator passes the square root of realized variance
+    // per second, with an interval of one second, instead of the
+    // per-sample standard deviation
+    fn time_weighted(&self) -> bool {
+        false
+    }
+
+    // Days in the year the annual figure covers, for quoting it per day.
+    // Derived from how a one-day interval is scaled; None when the figure
+    // isn't annual.
+    fn days_per_year(&self) -> Option<f64> {
+        let scale = self.annualize(1.0, SECONDS_PER_DAY);
+        (scale.is_finite() && scale > 0.0).then_some(scale * scale)
+    }
+}
+
+fn scale_to_year(period_stddev: f64, interval_seconds: f64, seconds_per_year: f64) -> f64 {
+    let samples_per_year = seconds_per_year / interval_seconds;
+    period_stddev * samples_per_year.sqrt()
+}
+
+// 365 calendar days per year
+pub struct Calendar365;
+
+impl Annualizer for Calendar365 {
+    fn annualize(&self, period_stddev: f64, interval_seconds: f64) -> f64 {
+        scale_to_year(period_stddev, interval_seconds, 365.0 * SECONDS_PER_DAY)
+    }
+}
+
+// 252 trading days per year, as quoted for TradFi assets
+pub struct Trading252;
+
+impl Annualizer for Trading252 {
+    fn annualize(&self, period_stddev: f64, interval_seconds: f64) -> f64 {
+        scale_to_year(period_stddev, interval_seconds, 252.0 * SECONDS_PER_DAY)
+    }
+}
+
+// Continuous 24/7 trading over an average year including leap days
+pub struct Continuous;
+
+impl Annualizer for Continuous {
+    fn annualize(&self, period_stddev: f64, interval_seconds: f64) -> f64 {
+        scale_to_year(period_stddev, interval_seconds, 365.25 * SECONDS_PER_DAY)
+    }
+}
+
+// Config-selectable annualization convention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnualizationConvention {
+    Calendar365,
+    Trading252,
+    Continuous,
+}
+
+impl AnnualizationConvention {
+    pub fn annualizer(&self) -> Box<dyn Annualizer> {
+        match self {
+            Self::Calendar365 => Box::new(Calendar365),
+            Self::Trading252 => Box::new(Trading252),
+            Self::Continuous => Box::new(Continuous),
+        }
+    }
+}
+
+impl FromStr for AnnualizationConvention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "calendar" | "calendar365" | "365" => Ok(Self::Calendar365),
+            "trading" | "trading252" | "252" => Ok(Self::Trading252),
+            "continuous" | "24/7" => Ok(Self::Continuous),
+            other => Err(format!("unknown annualization convention: {}", other)),
+        }
+    }
+}
+
+// How per-sample volatility is scaled, when more control is needed than the
+// named conventions give. ObservedInterval matches Calendar365, deriving the
+// sampling frequency from the average spacing of the window's ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnnualizationMode {
+    #[default]
+    ObservedInterval,
+    // Scale by sqrt(n) regardless of spacing; steadier with irregular ticks
+    FixedPeriodsPerYear(f64),
+    // Observed spacing against a year of this many days
+    CalendarDays(f64),
+    // Per-interval volatility, not annualized at all
+    Raw,
+    // Squared returns summed over the time they actually span, so one long
+    // gap counts for its length rather than as one more average interval.
+    // Estimators without per-return gaps fall back to ObservedInterval.
+    TimeWeighted,
+}
+
+impl Annualizer for AnnualizationMode {
+    fn annualize(&self, period_stddev: f64, interval_seconds: f64) -> f64 {
+        match self {
+            Self::ObservedInterval | Self::TimeWeighted => scale_to_year(period_stddev, interval_seconds, 365.0 * SECONDS_PER_DAY),
+            Self::FixedPeriodsPerYear(periods) => period_stddev * periods.sqrt(),
+            Self::CalendarDays(days) => scale_to_year(period_stddev, interval_seconds, days * SECONDS_PER_DAY),
+            Self::Raw => period_stddev,
+        }
+    }
+
+    fn time_weighted(&self) -> bool {
+        matches!(self, Self::TimeWeighted)
+    }
+
+    fn days_per_year(&self) -> Option<f64> {
+        match self {
+            Self::FixedPeriodsPerYear(_) => Some(365.0),
+            Self::Raw => None,
+            _ => Some(self.annualize(1.0, SECONDS_PER_DAY).powi(2)),
+        }
+    }
+}
+
+// "observed", "time_weighted", "raw", "periods:8760" or "days:365"
+impl FromStr for AnnualizationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let (kind, value) = match lower.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (lower.as_str(), None),
+        };
+        let parse_value = |value: Option<&str>| -> Result<f64, String> {
+            let value = value.ok_or_else(|| format!("annualization mode `{}` needs a value, e.g. `{}:365`", kind, kind))?;
+            match value.trim().parse::<f64>() {
+                Ok(v) if v > 0.0 && v.is_finite() => Ok(v),
+                _ => Err(format!("`{}` is not a positive number", value)),
+            }
+        };
+        match kind {
+            "observed" | "observed_interval" => Ok(Self::ObservedInterval),
+            "time_weighted" | "time-weighted" | "weighted" => Ok(Self::TimeWeighted),
+            "raw" | "none" => Ok(Self::Raw),
+            "periods" | "fixed" => Ok(Self::FixedPeriodsPerYear(parse_value(value)?)),
+            "days" | "calendar_days" => Ok(Self::CalendarDays(parse_value(value)?)),
+            other => Err(format!("unknown annualization mode: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use crate::price_collector::PricePoint;
+    use crate::volatility::VolatilityCalculator;
+
+    const PRICES: [f64; 5] = [2500.0, 2512.5, 2495.0, 2505.0, 2490.0];
+    const YEAR: f64 = 365.0 * SECONDS_PER_DAY;
+
+    // The estimate over PRICES one minute apart
+    fn volatility(mode: AnnualizationMode) -> f64 {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_min_samples(2)
+            .with_annualizer(Box::new(mode));
+        for (minute, price) in PRICES.iter().enumerate() {
+            calculator.add_price(PricePoint {
+                timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 0, minute as u32, 0).unwrap(),
+                price: *price,
+                source: "test".to_string(),
+                spread: None,
+                volume: None,
+            });
+        }
+        calculator.calculate_volatility().unwrap()
+    }
+
+    fn returns() -> Vec<f64> {
+        PRICES.windows(2).map(|w| (w[1] / w[0]).ln()).collect()
+    }
+
+    // Sample standard deviation of the returns
+    fn stddev() -> f64 {
+        let returns = returns();
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9 * expected, "{} vs {}", actual, expected);
+    }
+
+    #[test]
+    fn observed_interval_scales_by_minutes_per_calendar_year() {
+        assert_close(volatility(AnnualizationMode::ObservedInterval), stddev() * (YEAR / 60.0).sqrt());
+    }
+
+    #[test]
+    fn fixed_periods_scale_by_their_count_whatever_the_spacing() {
+        assert_close(volatility(AnnualizationMode::FixedPeriodsPerYear(8760.0)), stddev() * 8760f64.sqrt());
+    }
+
+    #[test]
+    fn calendar_days_scale_by_minutes_in_that_many_days() {
+        assert_close(volatility(AnnualizationMode::CalendarDays(252.0)), stddev() * (252.0 * SECONDS_PER_DAY / 60.0).sqrt());
+    }
+
+    #[test]
+    fn raw_is_the_per_interval_stddev() {
+        assert_close(volatility(AnnualizationMode::Raw), stddev());
+    }
+
+    #[test]
+    fn time_weighted_spreads_the_squared_returns_over_their_span() {
+        let returns = returns();
+        let per_second = returns.iter().map(|r| r * r).sum::<f64>() / (60.0 * returns.len() as f64);
+        assert_close(volatility(AnnualizationMode::TimeWeighted), (per_second * YEAR).sqrt());
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::error::VolatilityError;
+
+// Where the historical baseline comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum BaselineSource {
+    // A single reference volatility, e.g. a 30-day average
+    Value(f64),
+    // A file of historical volatility readings, one per line
+    File(PathBuf),
+}
+
+// Reference volatility readings to put the live estimate in context
+pub struct VolatilityBaseline {
+    // Sorted ascending
+    readings: Vec<f64>,
+    mean: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BaselineComparison {
+    // Current volatility divided by the baseline mean
+    pub ratio: f64,
+    // Share of baseline readings at or below the current value (0-100);
+    // only meaningful with more than one reading
+    pub percentile: Option<f64>,
+}
+
+impl VolatilityBaseline {
+    pub fn load(source: &BaselineSource) -> Result<Self, VolatilityError> {
+        match source {
+            BaselineSource::Value(value) => Self::from_readings(vec![*value]),
+            BaselineSource::File(path) => Self::from_file(path),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, VolatilityError> {
+        let contents = fs::read_to_string(path)?;
+        let readings = contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.parse::<f64>()
+                .map_err(|e| VolatilityError::Parse(format!("invalid baseline reading {:?} in {}: {}", line, path.display(), e))))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_readings(readings)
+    }
+
+    fn from_readings(mut readings: Vec<f64>) -> Result<Self, VolatilityError> {
+        readings.retain(|v| v.is_finite());
+        if readings.is_empty() {
+            return Err(VolatilityError::Parse("baseline contains no volatility readings".to_string()));
+        }
+        readings.sort_by(|a, b| a.total_cmp(b));
+        let mean = readings.iter().sum::<f64>() / readings.len() as f64;
+        if mean <= 0.0 {
+            return Err(VolatilityError::Parse("baseline volatility must be positive".to_string()));
+        }
+        Ok(Self { readings, mean })
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn compare(&self, volatility: f64) -> BaselineComparison {
+        let percentile = (self.readings.len() > 1).then(|| {
+            let at_or_below = self.readings.partition_point(|v| *v <= volatility);
+            at_or_below as f64 / self.readings.len() as f64 * 100.0
+        });
+        BaselineComparison {
+            ratio: volatility / self.mean,
+            percentile,
+        }
+    }
+}
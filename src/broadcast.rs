@@ -0,0 +1,95 @@
+use tokio::sync::broadcast::{self, error::RecvError};
+use crate::price_collector::PricePoint;
+
+pub const DEFAULT_BROADCAST_CAPACITY: usize = 1024;
+
+// Fans every fetched price out to any number of async consumers (a CSV
+// writer, a database, ...) without each reading the sockets. The producer
+// never waits: a subscriber more than `capacity` prices behind gets
+// `RecvError::Lagged` and skips ahead.
+#[derive(Clone)]
+pub struct PriceBroadcast {
+    sender: broadcast::Sender<PricePoint>,
+}
+
+impl PriceBroadcast {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    // Receives every price published after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<PricePoint> {
+        self.sender.subscribe()
+    }
+
+    // Returns how many subscribers will see the price; none is not an error
+    pub fn publish(&self, price: &PricePoint) -> usize {
+        self.sender.send(price.clone()).unwrap_or(0)
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+// Feed each price from `receiver` to `handle` until every sender is gone,
+// logging (rather than failing on) prices skipped because it fell behind
+pub async fn consume<F>(mut receiver: broadcast::Receiver<PricePoint>, name: &str, mut handle: F)
+where
+    F: FnMut(PricePoint),
+{
+    loop {
+        match receiver.recv().await {
+            Ok(price) => handle(price),
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("{} subscriber fell behind and skipped {} prices", name, skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn point(price: f64) -> PricePoint {
+        PricePoint {
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap(),
+            price,
+            source: "test".to_string(),
+            spread: None,
+            volume: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn every_subscriber_sees_the_same_sequence() {
+        let broadcast = PriceBroadcast::new(16);
+        // Published before anyone subscribed, so nobody sees it
+        assert_eq!(broadcast.publish(&point(2499.0)), 0);
+
+        let consumers: Vec<_> = (0..2)
+            .map(|i| {
+                let receiver = broadcast.subscribe();
+                tokio::spawn(async move {
+                    let mut seen = Vec::new();
+                    consume(receiver, &format!("test{}", i), |p| seen.push(p.price)).await;
+                    seen
+                })
+            })
+            .collect();
+        assert_eq!(broadcast.subscriber_count(), 2);
+        for price in [2500.0, 2501.0, 2502.0] {
+            assert_eq!(broadcast.publish(&point(price)), 2);
+        }
+        // Dropping the only sender ends both consumers
+        drop(broadcast);
+
+        for consumer in consumers {
+            assert_eq!(consumer.await.unwrap(), [2500.0, 2501.0, 2502.0]);
+        }
+    }
+}
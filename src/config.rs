@@ -1,31 +1,1338 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
-use chrono::Duration;
+use std::error::Error;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{Duration, Weekday};
+use crate::volatility::{EvictionReference, FlatWindowPolicy, IntervalEstimate, NonFinitePolicy, ReturnType, SpreadAdjustment, VolatilityPreset, DEFAULT_MIN_SAMPLES};
+use crate::annualization::{AnnualizationConvention, AnnualizationMode, Annualizer};
+use crate::alerts::DEFAULT_ALERT_HYSTERESIS_PCT;
+use crate::baseline::BaselineSource;
+use crate::price_collector::{AggregationStrategy, AggregationTimestamp, BinanceStream, CircuitBreaker, DivergenceAction, DEFAULT_BINANCE_REST_BASE, DEFAULT_BREAKER_COOLDOWN, DEFAULT_MAX_CLOCK_SKEW_SECONDS, DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_LOG_EVERY, DEFAULT_RECONNECT_MAX};
+use crate::format::NumberFormat;
+use crate::logging::LogFormat;
+use crate::ndjson_export::NdjsonTarget;
+use crate::persistence::PersistenceFormat;
+use crate::broadcast::DEFAULT_BROADCAST_CAPACITY;
+use crate::proxy::WsProxy;
+use crate::synthetic::CollectorKind;
 
 pub struct Config {
     pub binance_ws_url: String,
+    pub binance_rest_base: String,
+    pub trading_symbol: String,
+    pub binance_stream: BinanceStream,
+    pub trading_symbols: Vec<String>,
+    // Collector names for the registry, e.g. ["binance", "coinbase"]
+    pub collectors: Vec<String>,
+    pub ohlc_interval: Option<String>,
+    // Build OHLC bars from the fetched prices at this interval
+    pub bar_interval: Option<Duration>,
+    pub gemini_ws_url: Option<String>,
+    pub okx_ws_url: Option<String>,
+    pub htx_ws_url: Option<String>,
+    #[cfg(feature = "coinbase")]
+    pub coinbase_ws_url: Option<String>,
+    #[cfg(feature = "coinbase")]
+    pub coinbase_product_id: String,
+    #[cfg(feature = "kraken")]
+    pub kraken_ws_url: Option<String>,
+    #[cfg(feature = "kraken")]
+    pub kraken_pair: String,
+    #[cfg(feature = "uniswap")]
+    pub ethereum_rpc_url: Option<String>,
+    #[cfg(feature = "uniswap")]
+    pub uniswap_pool_address: Option<String>,
+    #[cfg(feature = "uniswap")]
+    pub uniswap_token0_decimals: u8,
+    #[cfg(feature = "uniswap")]
+    pub uniswap_token1_decimals: u8,
+    #[cfg(feature = "uniswap")]
+    pub uniswap_invert: bool,
+    // Fail at startup instead of silently running without Uniswap
+    #[cfg(feature = "uniswap")]
+    pub require_uniswap: bool,
     pub update_interval: Duration,
+    // Random spread around the update interval, in percent of it
+    pub update_jitter_pct: f64,
     pub volatility_window: Duration,
+    pub flat_window_policy: FlatWindowPolicy,
+    pub non_finite_policy: NonFinitePolicy,
+    pub annualization: AnnualizationConvention,
+    pub annualization_mode: Option<AnnualizationMode>,
+    pub report_variance: bool,
+    pub seasonality_adjustment: bool,
+    pub per_source_volatility: bool,
+    pub zscore_alert_threshold: Option<f64>,
+    // Reject the ticks ZSCORE_ALERT_THRESHOLD would flag instead of storing them
+    pub zscore_reject: bool,
+    pub vol_alert_high: Option<f64>,
+    pub vol_alert_low: Option<f64>,
+    pub vol_alert_hysteresis_pct: f64,
+    pub regime_alert_ratio: Option<f64>,
+    pub regime_short_window: Duration,
+    pub regime_long_window: Duration,
+    pub alert_webhook_url: Option<String>,
+    pub ewma_lambda: Option<f64>,
+    pub bollinger_k: Option<f64>,
+    pub vol_smoothing_alpha: Option<f64>,
+    pub max_points: Option<usize>,
+    pub min_samples: usize,
+    pub sample_interval: Option<Duration>,
+    pub eviction_reference: EvictionReference,
+    pub broadcast_capacity: usize,
+    pub confidence_level: Option<f64>,
+    pub spread_adjustment: SpreadAdjustment,
+    pub return_type: ReturnType,
+    pub interval_estimate: IntervalEstimate,
+    pub demean_returns: bool,
+    pub excluded_weekdays: Vec<Weekday>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+    pub max_price_jump_pct: Option<f64>,
+    pub outlier_threshold_pct: Option<f64>,
+    pub min_sources: usize,
+    pub aggregation_strategy: AggregationStrategy,
+    pub aggregation_timestamp: AggregationTimestamp,
+    pub max_source_divergence_pct: Option<f64>,
+    pub source_divergence_action: DivergenceAction,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub liquidity_tiers: HashMap<String, u8>,
+    pub time_offsets: HashMap<String, Duration>,
+    pub volatility_preset: Option<VolatilityPreset>,
+    pub volatility_windows: Vec<Duration>,
+    pub term_structure_flat_threshold: f64,
+    pub daily_summary_path: Option<PathBuf>,
+    pub daily_summary_webhook_url: Option<String>,
+    pub batch_mode: bool,
+    // Replay prices from this CSV instead of connecting to any venue
+    pub replay_path: Option<PathBuf>,
+    pub replay_speedup: f64,
+    pub collector: CollectorKind,
+    pub synthetic_start_price: f64,
+    pub synthetic_drift: f64,
+    pub synthetic_volatility: f64,
+    pub synthetic_seed: u64,
+    pub backfill_on_start: bool,
+    pub inactivity_timeout: Option<Duration>,
+    pub volatility_series_len: usize,
+    pub series_interval: Option<Duration>,
+    pub http_addr: Option<SocketAddr>,
+    pub http_control_token: Option<String>,
+    pub health_addr: Option<SocketAddr>,
+    pub ready_max_staleness: Duration,
+    #[cfg(feature = "metrics")]
+    pub metrics_addr: Option<SocketAddr>,
+    pub json_output: bool,
+    pub log_format: LogFormat,
+    pub number_format: NumberFormat,
+    pub gap_threshold: Duration,
+    pub baseline: Option<BaselineSource>,
+    pub reconnect_log_every: u32,
+    // Exit after this many failed fetches in a row; None retries forever
+    pub max_consecutive_errors: Option<u32>,
+    pub reconnect_base: std::time::Duration,
+    pub reconnect_max: std::time::Duration,
+    pub min_reconnect_interval: std::time::Duration,
+    pub ping_interval: std::time::Duration,
+    pub stale_timeout: Option<std::time::Duration>,
+    pub read_timeout: Option<std::time::Duration>,
+    pub read_timeout_reconnect: bool,
+    pub fetch_timeout: Option<std::time::Duration>,
+    pub max_clock_skew: Duration,
+    pub ws_proxy: Option<WsProxy>,
+    pub ws_compression: bool,
+    pub warmup_ticks: u32,
+    pub history_path: Option<PathBuf>,
+    pub csv_output_path: Option<PathBuf>,
+    pub ndjson_output: Option<NdjsonTarget>,
+    pub vol_series_output: Option<PathBuf>,
+    pub history_format: PersistenceFormat,
+    pub volatility_trend_flat_threshold: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const BINANCE_KLINE_INTERVALS: &[&str] = &[
+    "1s", "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w", "1M",
+];
+
+// Values given on the command line; each one set takes precedence over the
+// corresponding environment variable
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub binance_ws_url: Option<String>,
+    pub update_interval_seconds: Option<i64>,
+    pub volatility_window_hours: Option<i64>,
+    pub trading_symbol: Option<String>,
+    // TOML file to read settings from; CONFIG_FILE is used when not given
+    pub config_file: Option<PathBuf>,
+}
+
+// Why the environment could not be turned into a Config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Missing(&'static str),
+    Invalid { var: &'static str, value: String, reason: String },
+    NotPositive { var: &'static str, value: String },
+    File { path: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(var) => write!(f, "{} must be set", var),
+            Self::Invalid { var, value, reason } => write!(f, "{}={:?} is invalid: {}", var, value, reason),
+            Self::NotPositive { var, value } => write!(f, "{}={:?} must be greater than zero", var, value),
+            Self::File { path, reason } => write!(f, "config file {}: {}", path, reason),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+// Read a TOML file whose keys are the lowercase environment variable names
+// (`update_interval_seconds = 10`, `symbol` for TRADING_SYMBOL) into
+// variables. Arrays become comma-separated lists.
+fn read_file(path: &Path) -> Result<BTreeMap<String, String>, ConfigError> {
+    let file_error = |reason: String| ConfigError::File { path: path.display().to_string(), reason };
+    let contents = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+    let table: toml::Table = toml::from_str(&contents).map_err(|e| file_error(e.to_string()))?;
+
+    let mut vars = BTreeMap::new();
+    for (key, value) in table {
+        let var = match key.as_str() {
+            "symbol" => "TRADING_SYMBOL".to_string(),
+            other => other.to_uppercase(),
+        };
+        let value = match value {
+            toml::Value::String(s) => s,
+            toml::Value::Array(items) => items.iter()
+                .map(|item| match item {
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            toml::Value::Table(_) => return Err(file_error(format!("`{}` must be a value, not a table", key))),
+            other => other.to_string(),
+        };
+        vars.insert(var, value);
+    }
+    Ok(vars)
+}
+
+// `KEY=value` lines of a .env file, skipping blanks and comments
+fn read_dotenv(path: &Path) -> BTreeMap<String, String> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        .collect()
+}
+
+// Where settings not given to the builder come from, first match wins:
+// variables set explicitly, the process environment (unless building from
+// defaults only), then a config file. Names looked up are recorded so that
+// file keys no setting reads can be reported.
+#[derive(Debug, Clone, Default)]
+struct Vars {
+    set: BTreeMap<String, String>,
+    env: bool,
+    file: BTreeMap<String, String>,
+    read: RefCell<BTreeSet<String>>,
+}
+
+impl Vars {
+    fn get(&self, var: &str) -> Result<String, env::VarError> {
+        self.read.borrow_mut().insert(var.to_string());
+        if let Some(value) = self.set.get(var) {
+            return Ok(value.clone());
+        }
+        if self.env {
+            match env::var(var) {
+                Err(env::VarError::NotPresent) => {}
+                other => return other,
+            }
+        }
+        self.file.get(var).cloned().ok_or(env::VarError::NotPresent)
+    }
+
+    // Config file keys that nothing has looked up
+    fn unread_file_keys(&self) -> Vec<&str> {
+        let read = self.read.borrow();
+        self.file.keys().filter(|key| !read.contains(*key)).map(String::as_str).collect()
+    }
+}
+
+// Parse a variable if set; an unset variable is None, a malformed one an error
+fn parse_var<T: FromStr>(vars: &Vars, var: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    match vars.get(var) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|e: T::Err| ConfigError::Invalid {
+            var,
+            reason: e.to_string(),
+            value,
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+// A comma-separated list of `source:value` entries, sources lowercased. Any
+// entry that isn't one is an error naming it; unset is an empty list.
+fn parse_source_pairs<T: FromStr>(vars: &Vars, var: &'static str, example: &str) -> Result<Vec<(String, T)>, ConfigError> {
+    let Ok(value) = vars.get(var) else {
+        return Ok(Vec::new());
+    };
+    value.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let pair = entry.split_once(':')
+                .filter(|(source, _)| !source.trim().is_empty())
+                .and_then(|(source, parsed)| Some((source.trim().to_lowercase(), parsed.trim().parse().ok()?)));
+            pair.ok_or_else(|| ConfigError::Invalid {
+                var,
+                value: value.clone(),
+                reason: format!("`{}` is not a source:value pair, e.g. {}", entry, example),
+            })
+        })
+        .collect()
+}
+
+// A WebSocket endpoint must be a ws:// or wss:// URL with a host
+fn validate_ws_url(var: &'static str, value: &str) -> Result<(), ConfigError> {
+    validate_url(var, value, &["ws", "wss"])
+}
+
+fn validate_http_url(var: &'static str, value: &str) -> Result<(), ConfigError> {
+    validate_url(var, value, &["http", "https"])
+}
+
+fn validate_url(var: &'static str, value: &str, schemes: &[&str]) -> Result<(), ConfigError> {
+    let invalid = |reason: String| ConfigError::Invalid { var, value: value.to_string(), reason };
+    if value.trim().is_empty() {
+        return Err(invalid("must not be empty".to_string()));
+    }
+    let url = url::Url::parse(value).map_err(|e| invalid(e.to_string()))?;
+    if !schemes.contains(&url.scheme()) {
+        return Err(invalid(format!("scheme must be {}, not {}", schemes.join(" or "), url.scheme())));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host".to_string()));
+    }
+    Ok(())
+}
+
+// Builds a Config from explicit settings, with everything not set falling
+// back to the environment (`from_env`) or straight to the defaults (`new`),
+// so code that needs a Config can get one without touching process env
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    vars: Vars,
+    binance_ws_url: Option<String>,
+    update_interval: Option<Duration>,
+    volatility_window: Option<Duration>,
+    trading_symbol: Option<String>,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigBuilder {
+    // Defaults only; the environment is never read
+    pub fn new() -> Self {
+        Self {
+            vars: Vars::default(),
+            binance_ws_url: None,
+            update_interval: None,
+            volatility_window: None,
+            trading_symbol: None,
+        }
+    }
+
+    // Environment variables fill in anything not set on the builder
+    pub fn from_env() -> Self {
+        let mut builder = Self::new();
+        builder.vars.env = true;
+        builder
+    }
+
+    // Any setting by its environment variable name, taking precedence over
+    // the environment and the config file
+    pub fn var(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.vars.set.insert(name.to_string(), value.into());
+        self
+    }
+
+    // Settings from a TOML config file, used for anything the environment
+    // and the builder leave unset
+    pub fn config_file(mut self, path: &Path) -> Result<Self, ConfigError> {
+        self.vars.file = read_file(path)?;
+        Ok(self)
+    }
+
+    pub fn binance_ws_url(mut self, url: impl Into<String>) -> Self {
+        self.binance_ws_url = Some(url.into());
+        self
+    }
+
+    pub fn update_interval(mut self, interval: Duration) -> Self {
+        self.update_interval = Some(interval);
+        self
+    }
+
+    pub fn volatility_window(mut self, window: Duration) -> Self {
+        self.volatility_window = Some(window);
+        self
+    }
+
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.trading_symbol = Some(symbol.into());
+        self
+    }
+
+    // Fails on a missing or non-WebSocket Binance URL and on non-positive
+    // intervals, as well as on anything malformed in the environment
+    pub fn build(&self) -> Result<Config, ConfigError> {
+        let config = Config::build(self)?;
+        // Most likely a typo, which would otherwise silently keep the default
+        for key in self.vars.unread_file_keys() {
+            log::warn!("Ignoring config file key `{}`: no setting in this build reads {}", key.to_lowercase(), key);
+        }
+        Ok(config)
+    }
 }
 
 impl Config {
+    // Panicking convenience wrapper around `from_env`
     pub fn new() -> Self {
-        // Get update interval in seconds from env or use default (5 seconds)
-        let update_seconds = env::var("UPDATE_INTERVAL_SECONDS")
-            .unwrap_or_else(|_| "5".to_string())
-            .parse()
-            .unwrap_or(5);
-
-        // Get volatility window in hours from env or use default (6 hours)
-        let window_hours = env::var("VOLATILITY_WINDOW_HOURS")
-            .unwrap_or_else(|_| "6".to_string())
-            .parse()
-            .unwrap_or(6);
+        Self::from_env().unwrap_or_else(|e| panic!("invalid configuration: {}", e))
+    }
 
-        Self {
-            binance_ws_url: env::var("BINANCE_WS_URL")
-                .expect("BINANCE_WS_URL must be set"),
-            update_interval: Duration::seconds(update_seconds),
-            volatility_window: Duration::hours(window_hours),
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_args_and_env(&ConfigOverrides::default())
+    }
+
+    // Settings from a TOML file, with any environment variable that is set
+    // taking precedence over the file
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        ConfigBuilder::from_env().config_file(path)?.build()
+    }
+
+    // Command-line overrides, then environment variables, then the config
+    // file if one is given, then defaults
+    pub fn from_args_and_env(args: &ConfigOverrides) -> Result<Self, ConfigError> {
+        Self::from_args_and_builder(args, ConfigBuilder::from_env())
+    }
+
+    fn from_args_and_builder(args: &ConfigOverrides, mut builder: ConfigBuilder) -> Result<Self, ConfigError> {
+        let config_file = args.config_file.clone()
+            .or_else(|| builder.vars.get("CONFIG_FILE").ok().map(PathBuf::from));
+        if let Some(path) = config_file {
+            builder = builder.config_file(&path)?;
+        }
+
+        if let Some(url) = &args.binance_ws_url {
+            builder = builder.binance_ws_url(url.clone());
+        }
+        if let Some(seconds) = args.update_interval_seconds {
+            builder = builder.update_interval(Duration::seconds(seconds));
         }
+        if let Some(hours) = args.volatility_window_hours {
+            builder = builder.volatility_window(Duration::hours(hours));
+        }
+        if let Some(symbol) = &args.trading_symbol {
+            builder = builder.symbol(symbol.clone());
+        }
+        builder.build()
     }
-} 
\ No newline at end of file
+
+    fn build(args: &ConfigBuilder) -> Result<Self, ConfigError> {
+        // Update interval from the builder, the environment or the default (5 seconds)
+        let update_interval = match args.update_interval {
+            Some(interval) => interval,
+            None => Duration::seconds(parse_var(&args.vars, "UPDATE_INTERVAL_SECONDS")?.unwrap_or(5)),
+        };
+        if update_interval <= Duration::zero() {
+            return Err(ConfigError::NotPositive { var: "UPDATE_INTERVAL_SECONDS", value: update_interval.num_seconds().to_string() });
+        }
+
+        // Below 100 so the shortest jittered interval stays positive
+        let update_jitter_pct: f64 = parse_var(&args.vars, "UPDATE_JITTER_PCT")?.unwrap_or(0.0);
+        if !(0.0..100.0).contains(&update_jitter_pct) {
+            return Err(ConfigError::Invalid {
+                var: "UPDATE_JITTER_PCT",
+                value: update_jitter_pct.to_string(),
+                reason: "must be at least 0 and below 100".to_string(),
+            });
+        }
+
+        // Volatility window from the builder, the environment or the default (6 hours)
+        let base_window = match args.volatility_window {
+            Some(window) => window,
+            None => Duration::hours(parse_var(&args.vars, "VOLATILITY_WINDOW_HOURS")?.unwrap_or(6)),
+        };
+        if base_window <= Duration::zero() {
+            return Err(ConfigError::NotPositive { var: "VOLATILITY_WINDOW_HOURS", value: base_window.num_hours().to_string() });
+        }
+
+        // Looked up even when the builder has a URL, so a config file entry
+        // for it isn't reported as unknown
+        let binance_ws_url = match (&args.binance_ws_url, args.vars.get("BINANCE_WS_URL")) {
+            (Some(url), _) => url.clone(),
+            (None, var) => var.map_err(|_| ConfigError::Missing("BINANCE_WS_URL"))?,
+        };
+        validate_ws_url("BINANCE_WS_URL", &binance_ws_url)?;
+        // REST host for klines backfill, e.g. https://testnet.binance.vision
+        let binance_rest_base = args.vars.get("BINANCE_REST_BASE")
+            .map(|base| base.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| DEFAULT_BINANCE_REST_BASE.to_string());
+        validate_http_url("BINANCE_REST_BASE", &binance_rest_base)?;
+
+        // How to report a window with a single distinct price (report, warn or suppress)
+        let flat_window_policy = parse_var(&args.vars, "FLAT_WINDOW_POLICY")?.unwrap_or(FlatWindowPolicy::Report);
+
+        // What to output if volatility comes out NaN/Inf (suppress or error)
+        let non_finite_policy = parse_var(&args.vars, "NON_FINITE_OUTPUT")?.unwrap_or(NonFinitePolicy::Suppress);
+
+        // Annualization convention: calendar (365 days), trading (252) or continuous
+        let annualization = parse_var(&args.vars, "ANNUALIZATION")?.unwrap_or(AnnualizationConvention::Calendar365);
+
+        // Also report annualized realized variance alongside volatility
+        let report_variance = args.vars.get("REPORT_VARIANCE")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        // How bid/ask spread data is folded into returns (none or widen)
+        let spread_adjustment = parse_var(&args.vars, "SPREAD_ADJUSTMENT")?.unwrap_or(SpreadAdjustment::None);
+
+        // Log (default) or simple returns
+        let return_type = parse_var(&args.vars, "RETURN_TYPE")?.unwrap_or(ReturnType::Log);
+
+        // Weekdays whose returns are left out, e.g. "sat,sun" (default: none)
+        let excluded_weekdays: Vec<Weekday> = match args.vars.get("EXCLUDED_WEEKDAYS") {
+            Ok(v) => v.split(',')
+                .map(str::trim)
+                .filter(|day| !day.is_empty())
+                .map(|day| day.parse().map_err(|_| ConfigError::Invalid {
+                    var: "EXCLUDED_WEEKDAYS",
+                    value: v.clone(),
+                    reason: format!("`{}` is not a weekday", day),
+                }))
+                .collect::<Result<_, _>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        // Optional sanity bounds on incoming prices
+        let price_min = parse_var(&args.vars, "PRICE_MIN")?;
+        let price_max = parse_var(&args.vars, "PRICE_MAX")?;
+
+        // Reject ticks that move more than this percent from the previous price
+        let max_price_jump_pct = parse_var(&args.vars, "MAX_PRICE_JUMP_PCT")?;
+
+        // Reject prices more than this percent from the window median
+        let outlier_threshold_pct = parse_var(&args.vars, "OUTLIER_THRESHOLD_PCT")?;
+
+        // Minimum number of sources that must report for an aggregated price
+        let min_sources = parse_var(&args.vars, "MIN_SOURCES")?.unwrap_or(1);
+
+        // Skip a source for a cooldown after this many bad fetches in a row
+        let breaker_cooldown = parse_var::<u64>(&args.vars, "CIRCUIT_BREAKER_COOLDOWN_SECONDS")?;
+        let breaker_max_latency = parse_var(&args.vars, "CIRCUIT_BREAKER_MAX_LATENCY_MS")?.map(std::time::Duration::from_millis);
+        let circuit_breaker = match parse_var::<u32>(&args.vars, "CIRCUIT_BREAKER_FAILURES")? {
+            None => None,
+            Some(0) => return Err(ConfigError::NotPositive { var: "CIRCUIT_BREAKER_FAILURES", value: "0".to_string() }),
+            Some(failures) => Some(CircuitBreaker {
+                failures,
+                cooldown: match breaker_cooldown {
+                    Some(0) => return Err(ConfigError::NotPositive { var: "CIRCUIT_BREAKER_COOLDOWN_SECONDS", value: "0".to_string() }),
+                    Some(seconds) => std::time::Duration::from_secs(seconds),
+                    None => DEFAULT_BREAKER_COOLDOWN,
+                },
+                max_latency: breaker_max_latency,
+            }),
+        };
+
+        // How source prices are blended: mean, median or weighted (by liquidity tier)
+        let aggregation_strategy = parse_var(&args.vars, "AGGREGATION_STRATEGY")?.unwrap_or(AggregationStrategy::WeightedMean);
+
+        // Liquidity tiers keyed by source, e.g. "binance:1,okx:1,gemini:2"
+        let liquidity_tiers = parse_source_pairs(&args.vars, "LIQUIDITY_TIERS", "okx:1")?.into_iter().collect();
+
+        // Per-source timestamp corrections in milliseconds, e.g. "okx:-250,htx:28800000"
+        let time_offsets = parse_source_pairs(&args.vars, "SOURCE_TIME_OFFSETS_MS", "okx:-250")?
+            .into_iter()
+            .map(|(source, offset)| (source, Duration::milliseconds(offset)))
+            .collect();
+
+        // Optional preset reporting several windows at once (e.g. "bloomberg")
+        let volatility_preset: Option<VolatilityPreset> = parse_var(&args.vars, "VOLATILITY_PRESET")?;
+
+        let zscore_alert_threshold = parse_var(&args.vars, "ZSCORE_ALERT_THRESHOLD")?;
+        let zscore_reject = args.vars.get("ZSCORE_REJECT").map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(false);
+        if zscore_reject && zscore_alert_threshold.is_none() {
+            return Err(ConfigError::Missing("ZSCORE_ALERT_THRESHOLD"));
+        }
+
+        // Below 100 so the band under VOL_ALERT_HIGH stays above zero
+        let vol_alert_hysteresis_pct: f64 = parse_var(&args.vars, "VOL_ALERT_HYSTERESIS_PCT")?.unwrap_or(DEFAULT_ALERT_HYSTERESIS_PCT);
+        if !(0.0..100.0).contains(&vol_alert_hysteresis_pct) {
+            return Err(ConfigError::Invalid {
+                var: "VOL_ALERT_HYSTERESIS_PCT",
+                value: vol_alert_hysteresis_pct.to_string(),
+                reason: "must be at least 0 and below 100".to_string(),
+            });
+        }
+
+        // Explicit collector list; unset keeps the URL-driven defaults. A name
+        // listed twice would connect to that venue twice and weigh it double.
+        let collectors: Vec<String> = args.vars.get("COLLECTORS")
+            .map(|v| v.split(',')
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect())
+            .unwrap_or_default();
+        if let Some(duplicate) = collectors.iter().enumerate().find(|(i, name)| collectors[..*i].contains(name)) {
+            return Err(ConfigError::Invalid {
+                var: "COLLECTORS",
+                value: args.vars.get("COLLECTORS").unwrap_or_default(),
+                reason: format!("`{}` is listed more than once", duplicate.1),
+            });
+        }
+
+        // Extra windows reported alongside the main one, in hours, e.g. "1,6,24"
+        let volatility_windows: Vec<Duration> = match args.vars.get("VOLATILITY_WINDOWS_HOURS") {
+            Ok(v) => v.split(',')
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+                .map(|h| match h.parse::<i64>() {
+                    Ok(hours) if hours > 0 => Ok(Duration::hours(hours)),
+                    _ => Err(ConfigError::Invalid {
+                        var: "VOLATILITY_WINDOWS_HOURS",
+                        value: v.clone(),
+                        reason: format!("`{}` is not a positive number of hours", h),
+                    }),
+                })
+                .collect::<Result<_, _>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        // Retain enough history for the longest preset or extra window
+        let mut volatility_window = base_window;
+        if let Some(preset) = volatility_preset {
+            volatility_window = volatility_window.max(preset.longest_window());
+        }
+        if let Some(longest) = volatility_windows.iter().max() {
+            volatility_window = volatility_window.max(*longest);
+        }
+
+        // Alert when short-window volatility exceeds this multiple of the
+        // long window's; the windows default to 30 minutes and the main window
+        let regime_alert_ratio = match parse_var::<f64>(&args.vars, "REGIME_ALERT_RATIO")? {
+            Some(ratio) if ratio.is_nan() || ratio <= 0.0 => {
+                return Err(ConfigError::NotPositive { var: "REGIME_ALERT_RATIO", value: ratio.to_string() })
+            }
+            ratio => ratio,
+        };
+        let regime_short_window = match parse_var::<i64>(&args.vars, "REGIME_SHORT_WINDOW_MINUTES")? {
+            Some(minutes) if minutes <= 0 => {
+                return Err(ConfigError::NotPositive { var: "REGIME_SHORT_WINDOW_MINUTES", value: minutes.to_string() })
+            }
+            minutes => Duration::minutes(minutes.unwrap_or(30)),
+        };
+        let regime_long_window = match parse_var::<i64>(&args.vars, "REGIME_LONG_WINDOW_HOURS")? {
+            Some(hours) if hours <= 0 => {
+                return Err(ConfigError::NotPositive { var: "REGIME_LONG_WINDOW_HOURS", value: hours.to_string() })
+            }
+            hours => hours.map_or(base_window, Duration::hours),
+        };
+        if regime_alert_ratio.is_some() {
+            if regime_short_window >= regime_long_window {
+                return Err(ConfigError::Invalid {
+                    var: "REGIME_SHORT_WINDOW_MINUTES",
+                    value: regime_short_window.num_minutes().to_string(),
+                    reason: format!("must be shorter than the {}h long window", regime_long_window.num_hours()),
+                });
+            }
+            volatility_window = volatility_window.max(regime_long_window);
+        }
+
+        // One-shot/batch runs exit once the feed has been silent this long
+        let batch_mode = args.vars.get("BATCH_MODE")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        let inactivity_timeout = parse_var(&args.vars, "INACTIVITY_TIMEOUT_SECONDS")?.map(Duration::seconds);
+
+        // Number of volatility readings retained for trend analysis
+        let volatility_series_len = parse_var(&args.vars, "VOLATILITY_SERIES_LEN")?.unwrap_or(720);
+
+        // Commit at most one reading per interval to the volatility series
+        let series_interval = parse_var(&args.vars, "SERIES_INTERVAL_SECONDS")?.map(Duration::seconds);
+
+        // Trend slopes below this (annualized vol per hour) are reported as flat
+        let volatility_trend_flat_threshold = parse_var(&args.vars, "VOLATILITY_TREND_FLAT_THRESHOLD")?.unwrap_or(0.001);
+
+        // Emit each volatility result as a JSON line on stdout
+        let json_output = args.vars.get("OUTPUT_FORMAT")
+            .map(|v| v.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+
+        // Price lines on stdout would interleave with the JSON results there
+        let ndjson_output = parse_var(&args.vars, "NDJSON_OUTPUT")?;
+        if json_output && ndjson_output == Some(NdjsonTarget::Stdout) {
+            return Err(ConfigError::Invalid {
+                var: "NDJSON_OUTPUT",
+                value: "stdout".to_string(),
+                reason: "OUTPUT_FORMAT=json already writes to stdout; use a file path".to_string(),
+            });
+        }
+
+        // Silence longer than this counts as a gap (default three update intervals)
+        let gap_threshold = parse_var(&args.vars, "GAP_THRESHOLD_SECONDS")?
+            .map(Duration::seconds)
+            .unwrap_or(update_interval * 3);
+
+        // Historical baseline: a fixed value takes precedence over a file of readings
+        let baseline = parse_var(&args.vars, "VOLATILITY_BASELINE")?
+            .map(BaselineSource::Value)
+            .or_else(|| args.vars.get("VOLATILITY_BASELINE_FILE").ok().map(|p| BaselineSource::File(PathBuf::from(p))));
+
+        // Binance kline interval for OHLC collection, e.g. "1m" (default: off)
+        let ohlc_interval = match args.vars.get("OHLC_INTERVAL") {
+            Ok(v) if BINANCE_KLINE_INTERVALS.contains(&v.as_str()) => Some(v),
+            Ok(v) => return Err(ConfigError::Invalid {
+                var: "OHLC_INTERVAL",
+                value: v,
+                reason: format!("expected one of {}", BINANCE_KLINE_INTERVALS.join(", ")),
+            }),
+            Err(_) => None,
+        };
+
+        // Bars built from ticks; the kline stream would feed the same buffer
+        let bar_interval = match parse_var::<i64>(&args.vars, "BAR_INTERVAL_SECONDS")? {
+            Some(seconds) if seconds <= 0 => {
+                return Err(ConfigError::NotPositive { var: "BAR_INTERVAL_SECONDS", value: seconds.to_string() })
+            }
+            Some(seconds) if ohlc_interval.is_some() => {
+                return Err(ConfigError::Invalid {
+                    var: "BAR_INTERVAL_SECONDS",
+                    value: seconds.to_string(),
+                    reason: "can't be combined with OHLC_INTERVAL".to_string(),
+                })
+            }
+            seconds => seconds.map(Duration::seconds),
+        };
+
+        // Persisted price history; an unknown format is a startup error
+        let history_format = parse_var(&args.vars, "HISTORY_FORMAT")?.unwrap_or(PersistenceFormat::Json);
+
+        Ok(Self {
+            binance_ws_url,
+            binance_rest_base,
+            // Binance pair to subscribe to, e.g. "btcusdt"
+            trading_symbol: args.trading_symbol.clone()
+                .or_else(|| args.vars.get("TRADING_SYMBOL").ok())
+                .unwrap_or_else(|| "ethusdc".to_string()),
+            // Several Binance pairs tracked side by side, e.g. "ethusdc,btcusdc"
+            trading_symbols: args.vars.get("TRADING_SYMBOLS")
+                .map(|v| v.split(',')
+                    .map(|symbol| symbol.trim().to_lowercase())
+                    .filter(|symbol| !symbol.is_empty())
+                    .collect())
+                .unwrap_or_default(),
+            collectors,
+            // Binance trade stream: trade (default) or aggTrade
+            binance_stream: parse_var(&args.vars, "BINANCE_STREAM")?.unwrap_or_default(),
+            ohlc_interval,
+            bar_interval,
+            gemini_ws_url: args.vars.get("GEMINI_WS_URL").ok(),
+            okx_ws_url: args.vars.get("OKX_WS_URL").ok(),
+            htx_ws_url: args.vars.get("HTX_WS_URL").ok(),
+            #[cfg(feature = "coinbase")]
+            coinbase_ws_url: args.vars.get("COINBASE_WS_URL").ok(),
+            #[cfg(feature = "coinbase")]
+            coinbase_product_id: args.vars.get("COINBASE_PRODUCT_ID").unwrap_or_else(|_| "ETH-USD".to_string()),
+            #[cfg(feature = "kraken")]
+            kraken_ws_url: args.vars.get("KRAKEN_WS_URL").ok(),
+            #[cfg(feature = "kraken")]
+            kraken_pair: args.vars.get("KRAKEN_PAIR").unwrap_or_else(|_| "ETH/USD".to_string()),
+            #[cfg(feature = "uniswap")]
+            ethereum_rpc_url: args.vars.get("ETHEREUM_RPC_URL").ok(),
+            #[cfg(feature = "uniswap")]
+            uniswap_pool_address: args.vars.get("UNISWAP_V3_POOL_ADDRESS").ok(),
+            // Defaults suit the USDC/WETH pool: token0 is USDC (6 decimals),
+            // token1 WETH (18), inverted to quote ETH in USDC
+            #[cfg(feature = "uniswap")]
+            uniswap_token0_decimals: parse_var(&args.vars, "UNISWAP_TOKEN0_DECIMALS")?.unwrap_or(6),
+            #[cfg(feature = "uniswap")]
+            uniswap_token1_decimals: parse_var(&args.vars, "UNISWAP_TOKEN1_DECIMALS")?.unwrap_or(18),
+            #[cfg(feature = "uniswap")]
+            uniswap_invert: args.vars.get("UNISWAP_INVERT")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            #[cfg(feature = "uniswap")]
+            require_uniswap: args.vars.get("REQUIRE_UNISWAP")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            update_interval,
+            update_jitter_pct,
+            volatility_window,
+            flat_window_policy,
+            non_finite_policy,
+            annualization,
+            // Takes precedence over ANNUALIZATION when set
+            annualization_mode: parse_var(&args.vars, "ANNUALIZATION_MODE")?,
+            report_variance,
+            // Also report volatility adjusted for its usual hour-of-day level
+            seasonality_adjustment: args.vars.get("SEASONALITY_ADJUSTMENT").map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(false),
+            // Warn when the latest return's z-score exceeds this magnitude
+            zscore_alert_threshold,
+            zscore_reject,
+            // Alert when annualized volatility crosses these levels (fractions, e.g. 0.8)
+            vol_alert_high: parse_var(&args.vars, "VOL_ALERT_HIGH")?,
+            vol_alert_low: parse_var(&args.vars, "VOL_ALERT_LOW")?,
+            vol_alert_hysteresis_pct,
+            regime_alert_ratio,
+            regime_short_window,
+            regime_long_window,
+            alert_webhook_url: args.vars.get("ALERT_WEBHOOK_URL").ok(),
+            // Upper bound on buffered price points, whatever their timestamps
+            max_points: parse_var(&args.vars, "MAX_POINTS")?,
+            // Bucket ticks into one close per this many seconds
+            sample_interval: match parse_var::<i64>(&args.vars, "SAMPLE_INTERVAL_SECONDS")? {
+                Some(seconds) if seconds <= 0 => {
+                    return Err(ConfigError::NotPositive { var: "SAMPLE_INTERVAL_SECONDS", value: seconds.to_string() })
+                }
+                seconds => seconds.map(Duration::seconds),
+            },
+            // Returns required before volatility is reported
+            min_samples: match parse_var(&args.vars, "MIN_SAMPLES")? {
+                Some(0) => return Err(ConfigError::NotPositive { var: "MIN_SAMPLES", value: "0".to_string() }),
+                Some(samples) => samples,
+                None => DEFAULT_MIN_SAMPLES,
+            },
+            // Measure the window back from the newest point (default) or the clock
+            eviction_reference: parse_var(&args.vars, "EVICTION_REFERENCE")?.unwrap_or_default(),
+            // Prices a broadcast subscriber may fall behind before skipping ahead
+            broadcast_capacity: parse_var(&args.vars, "PRICE_BROADCAST_CAPACITY")?.unwrap_or(DEFAULT_BROADCAST_CAPACITY),
+            // Also report EWMA volatility with this decay (e.g. 0.94)
+            ewma_lambda: parse_var(&args.vars, "EWMA_LAMBDA")?,
+            // Also log price bands this many standard deviations wide (e.g. 2)
+            bollinger_k: parse_var(&args.vars, "BOLLINGER_K")?,
+            // Also track each source's volatility on its own
+            per_source_volatility: args.vars.get("PER_SOURCE_VOLATILITY")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            // Smooth the logged volatility series with this EMA weight
+            vol_smoothing_alpha: match parse_var::<f64>(&args.vars, "VOL_SMOOTHING_ALPHA")? {
+                Some(alpha) if !(alpha > 0.0 && alpha <= 1.0) => {
+                    return Err(ConfigError::Invalid {
+                        var: "VOL_SMOOTHING_ALPHA",
+                        value: alpha.to_string(),
+                        reason: "must be in (0, 1]".to_string(),
+                    })
+                }
+                alpha => alpha,
+            },
+            // Also log a confidence interval at this level (e.g. 0.95)
+            confidence_level: parse_var(&args.vars, "VOLATILITY_CONFIDENCE")?,
+            spread_adjustment,
+            return_type,
+            interval_estimate: parse_var(&args.vars, "ANNUALIZATION_INTERVAL")?.unwrap_or_default(),
+            demean_returns: args.vars.get("DEMEAN_RETURNS")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            excluded_weekdays,
+            price_min,
+            price_max,
+            max_price_jump_pct,
+            outlier_threshold_pct,
+            min_sources,
+            aggregation_strategy,
+            aggregation_timestamp: parse_var(&args.vars, "AGG_TIMESTAMP_MODE")?.unwrap_or_default(),
+            // Flag a source this far from the median of all sources
+            max_source_divergence_pct: match parse_var::<f64>(&args.vars, "MAX_SOURCE_DIVERGENCE_PCT")? {
+                Some(pct) if pct.is_nan() || pct <= 0.0 => {
+                    return Err(ConfigError::NotPositive { var: "MAX_SOURCE_DIVERGENCE_PCT", value: pct.to_string() })
+                }
+                pct => pct,
+            },
+            source_divergence_action: parse_var(&args.vars, "SOURCE_DIVERGENCE_ACTION")?.unwrap_or_default(),
+            circuit_breaker,
+            liquidity_tiers,
+            time_offsets,
+            volatility_preset,
+            volatility_windows,
+            // Term structure slopes below this (vol per day of tenor) are flat
+            term_structure_flat_threshold: parse_var(&args.vars, "TERM_STRUCTURE_FLAT_THRESHOLD")?.unwrap_or(0.0001),
+            daily_summary_path: args.vars.get("DAILY_SUMMARY_PATH").ok().map(PathBuf::from),
+            daily_summary_webhook_url: args.vars.get("DAILY_SUMMARY_WEBHOOK_URL").ok(),
+            batch_mode,
+            replay_path: args.vars.get("REPLAY_PATH").ok().map(PathBuf::from),
+            // 0 replays as fast as possible
+            replay_speedup: parse_var(&args.vars, "REPLAY_SPEEDUP")?.unwrap_or(0.0),
+            // "synthetic" generates prices offline instead of connecting anywhere
+            collector: parse_var(&args.vars, "COLLECTOR")?.unwrap_or_default(),
+            // Geometric Brownian motion parameters for the synthetic collector,
+            // drift and volatility annualized
+            synthetic_start_price: parse_var(&args.vars, "SYNTHETIC_START_PRICE")?.unwrap_or(3000.0),
+            synthetic_drift: parse_var(&args.vars, "SYNTHETIC_DRIFT")?.unwrap_or(0.0),
+            synthetic_volatility: parse_var(&args.vars, "SYNTHETIC_VOLATILITY")?.unwrap_or(0.8),
+            synthetic_seed: parse_var(&args.vars, "SYNTHETIC_SEED")?.unwrap_or(42),
+            // Seed the window from Binance REST klines before going live
+            backfill_on_start: args.vars.get("BACKFILL_ON_START")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            inactivity_timeout,
+            volatility_series_len,
+            series_interval,
+            // Address for the optional HTTP server (e.g. 127.0.0.1:8080)
+            http_addr: parse_var(&args.vars, "HTTP_ADDR")?,
+            // Bearer token enabling POST /pause and /resume
+            http_control_token: args.vars.get("HTTP_CONTROL_TOKEN").ok().filter(|token| !token.is_empty()),
+            // Liveness/readiness probes (e.g. 0.0.0.0:8081)
+            health_addr: parse_var(&args.vars, "HEALTH_ADDR")?,
+            // Not ready once the last successful fetch is older than this
+            ready_max_staleness: parse_var(&args.vars, "READY_MAX_STALENESS_SECONDS")?
+                .map(Duration::seconds)
+                .unwrap_or(update_interval * 3),
+            // Address for the Prometheus /metrics endpoint (e.g. 0.0.0.0:9100)
+            #[cfg(feature = "metrics")]
+            metrics_addr: parse_var(&args.vars, "METRICS_ADDR")?,
+            json_output,
+            // Decimal places for logged prices and volatility percentages
+            number_format: NumberFormat {
+                price_decimals: parse_var(&args.vars, "PRICE_DECIMALS")?.unwrap_or(NumberFormat::default().price_decimals),
+                vol_decimals: parse_var(&args.vars, "VOL_DECIMALS")?.unwrap_or(NumberFormat::default().vol_decimals),
+            },
+            gap_threshold,
+            baseline,
+            // Log failed reconnects on the first attempt and every Nth after that
+            reconnect_log_every: parse_var(&args.vars, "RECONNECT_LOG_EVERY")?.unwrap_or(DEFAULT_RECONNECT_LOG_EVERY),
+            // Exponential backoff bounds for Binance reconnects
+            reconnect_base: parse_var(&args.vars, "RECONNECT_BASE_MS")?
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(DEFAULT_RECONNECT_BASE),
+            reconnect_max: parse_var(&args.vars, "RECONNECT_MAX_MS")?
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(DEFAULT_RECONNECT_MAX),
+            // 0 means the same as unset: keep retrying
+            max_consecutive_errors: parse_var(&args.vars, "MAX_CONSECUTIVE_ERRORS")?.filter(|&max| max > 0),
+            // Minimum spacing between Binance connection attempts
+            min_reconnect_interval: parse_var(&args.vars, "MIN_RECONNECT_INTERVAL_MS")?
+                .map(std::time::Duration::from_millis)
+                .unwrap_or_default(),
+            // Keepalive ping after this much silence on the Binance stream
+            ping_interval: match parse_var(&args.vars, "PING_INTERVAL_SECONDS")? {
+                Some(0) => return Err(ConfigError::NotPositive { var: "PING_INTERVAL_SECONDS", value: "0".to_string() }),
+                Some(seconds) => std::time::Duration::from_secs(seconds),
+                None => DEFAULT_PING_INTERVAL,
+            },
+            // HTTP CONNECT or SOCKS5 proxy for the Binance WebSocket
+            ws_proxy: parse_var(&args.vars, "WS_PROXY_URL")?,
+            // Ask for permessage-deflate on the Binance WebSocket
+            ws_compression: args.vars.get("WS_COMPRESSION").map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(false),
+            // Reconnect when the Binance stream goes this long without a trade
+            stale_timeout: match parse_var(&args.vars, "STALE_TIMEOUT_SECONDS")? {
+                Some(0) => return Err(ConfigError::NotPositive { var: "STALE_TIMEOUT_SECONDS", value: "0".to_string() }),
+                seconds => seconds.map(std::time::Duration::from_secs),
+            },
+            // Fail a single Binance fetch that waits this long for a trade
+            read_timeout: match parse_var(&args.vars, "READ_TIMEOUT_SECONDS")? {
+                Some(0) => return Err(ConfigError::NotPositive { var: "READ_TIMEOUT_SECONDS", value: "0".to_string() }),
+                seconds => seconds.map(std::time::Duration::from_secs),
+            },
+            read_timeout_reconnect: args.vars.get("READ_TIMEOUT_RECONNECT").map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(false),
+            // Abandon a whole aggregated fetch, across every source, after this long
+            fetch_timeout: match parse_var(&args.vars, "FETCH_TIMEOUT_SECONDS")? {
+                Some(0) => return Err(ConfigError::NotPositive { var: "FETCH_TIMEOUT_SECONDS", value: "0".to_string() }),
+                seconds => seconds.map(std::time::Duration::from_secs),
+            },
+            // Warn when exchange timestamps drift this far from local time
+            max_clock_skew: match parse_var::<i64>(&args.vars, "MAX_CLOCK_SKEW_SECONDS")? {
+                Some(seconds) if seconds <= 0 => {
+                    return Err(ConfigError::NotPositive { var: "MAX_CLOCK_SKEW_SECONDS", value: seconds.to_string() })
+                }
+                seconds => Duration::seconds(seconds.unwrap_or(DEFAULT_MAX_CLOCK_SKEW_SECONDS)),
+            },
+            // Points discarded after each (re)connection
+            warmup_ticks: parse_var(&args.vars, "WARMUP_TICKS")?.unwrap_or(0),
+            history_path: args.vars.get("HISTORY_PATH").ok().map(PathBuf::from),
+            // Append every fetched price to this CSV file
+            csv_output_path: args.vars.get("CSV_OUTPUT_PATH").ok().map(PathBuf::from),
+            // Stream every fetched price as JSON lines to stdout or a file
+            ndjson_output,
+            // The logger is installed from the environment before this loads;
+            // parsing it here still rejects a malformed value
+            log_format: parse_var(&args.vars, "LOG_FORMAT")?.unwrap_or_default(),
+            // Append every volatility estimate to this CSV or NDJSON file
+            vol_series_output: args.vars.get("VOL_SERIES_OUTPUT").ok().map(PathBuf::from),
+            history_format,
+            volatility_trend_flat_threshold,
+        })
+    }
+
+    // Re-read the .env file and rebuild. dotenv copied the startup values
+    // into the environment and never overrides them, so the file's current
+    // values are layered over the environment here instead.
+    // Command-line overrides from startup still apply.
+    pub fn reload(args: &ConfigOverrides) -> Result<Self, ConfigError> {
+        let mut builder = ConfigBuilder::from_env();
+        builder.vars.set = read_dotenv(Path::new(".env"));
+        Self::from_args_and_builder(args, builder)
+    }
+
+    // Settings that changed but are only read at startup, by variable name;
+    // a reload keeps running with the old values until a restart
+    pub fn restart_required(&self, other: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        let mut check = |differs: bool, var: &'static str| {
+            if differs {
+                changed.push(var);
+            }
+        };
+        check(self.ohlc_interval != other.ohlc_interval, "OHLC_INTERVAL");
+        check(self.bar_interval != other.bar_interval, "BAR_INTERVAL_SECONDS");
+        check(self.http_addr != other.http_addr, "HTTP_ADDR");
+        check(self.http_control_token != other.http_control_token, "HTTP_CONTROL_TOKEN");
+        check(self.health_addr != other.health_addr, "HEALTH_ADDR");
+        check(self.ready_max_staleness != other.ready_max_staleness, "READY_MAX_STALENESS_SECONDS");
+        #[cfg(feature = "metrics")]
+        check(self.metrics_addr != other.metrics_addr, "METRICS_ADDR");
+        check(self.history_path != other.history_path, "HISTORY_PATH");
+        check(self.history_format != other.history_format, "HISTORY_FORMAT");
+        check(self.csv_output_path != other.csv_output_path, "CSV_OUTPUT_PATH");
+        check(self.ndjson_output != other.ndjson_output, "NDJSON_OUTPUT");
+        check(self.log_format != other.log_format, "LOG_FORMAT");
+        check(self.vol_series_output != other.vol_series_output, "VOL_SERIES_OUTPUT");
+        check(self.broadcast_capacity != other.broadcast_capacity, "PRICE_BROADCAST_CAPACITY");
+        check(self.max_points != other.max_points, "MAX_POINTS");
+        check(self.seasonality_adjustment != other.seasonality_adjustment, "SEASONALITY_ADJUSTMENT");
+        check(self.eviction_reference != other.eviction_reference, "EVICTION_REFERENCE");
+        check(self.spread_adjustment != other.spread_adjustment, "SPREAD_ADJUSTMENT");
+        check(self.excluded_weekdays != other.excluded_weekdays, "EXCLUDED_WEEKDAYS");
+        check(self.price_min != other.price_min || self.price_max != other.price_max, "PRICE_MIN/PRICE_MAX");
+        check(self.max_price_jump_pct != other.max_price_jump_pct, "MAX_PRICE_JUMP_PCT");
+        check(self.outlier_threshold_pct != other.outlier_threshold_pct, "OUTLIER_THRESHOLD_PCT");
+        check(
+            self.zscore_reject != other.zscore_reject
+                || (self.zscore_reject && self.zscore_alert_threshold != other.zscore_alert_threshold),
+            "ZSCORE_REJECT",
+        );
+        check(self.volatility_series_len != other.volatility_series_len, "VOLATILITY_SERIES_LEN");
+        check(self.series_interval != other.series_interval, "SERIES_INTERVAL_SECONDS");
+        // Only read by the startup backfill and the startup warning
+        check(self.binance_rest_base != other.binance_rest_base, "BINANCE_REST_BASE");
+        check(self.ws_compression != other.ws_compression, "WS_COMPRESSION");
+        changed
+    }
+
+    // ANNUALIZATION_MODE if given, otherwise the named convention
+    pub fn annualizer(&self) -> Box<dyn Annualizer> {
+        match self.annualization_mode {
+            Some(mode) => Box::new(mode),
+            None => self.annualization.annualizer(),
+        }
+    }
+
+    // Whether switching to `other` requires reconnecting the collectors
+    pub fn sources_differ(&self, other: &Config) -> bool {
+        #[cfg(feature = "uniswap")]
+        if self.ethereum_rpc_url != other.ethereum_rpc_url
+            || self.uniswap_pool_address != other.uniswap_pool_address
+            || self.uniswap_token0_decimals != other.uniswap_token0_decimals
+            || self.uniswap_token1_decimals != other.uniswap_token1_decimals
+            || self.uniswap_invert != other.uniswap_invert
+        {
+            return true;
+        }
+
+        #[cfg(feature = "coinbase")]
+        if self.coinbase_ws_url != other.coinbase_ws_url || self.coinbase_product_id != other.coinbase_product_id {
+            return true;
+        }
+
+        #[cfg(feature = "kraken")]
+        if self.kraken_ws_url != other.kraken_ws_url || self.kraken_pair != other.kraken_pair {
+            return true;
+        }
+
+        self.replay_path != other.replay_path
+            || self.collector != other.collector
+            || self.collectors != other.collectors
+            || self.synthetic_start_price != other.synthetic_start_price
+            || self.synthetic_drift != other.synthetic_drift
+            || self.synthetic_volatility != other.synthetic_volatility
+            || self.synthetic_seed != other.synthetic_seed
+            || self.binance_ws_url != other.binance_ws_url
+            || self.ws_proxy != other.ws_proxy
+            || self.trading_symbol != other.trading_symbol
+            || self.binance_stream != other.binance_stream
+            || self.gemini_ws_url != other.gemini_ws_url
+            || self.okx_ws_url != other.okx_ws_url
+            || self.htx_ws_url != other.htx_ws_url
+            // Connection settings are baked into each collector when it's built
+            || self.reconnect_log_every != other.reconnect_log_every
+            || self.reconnect_base != other.reconnect_base
+            || self.reconnect_max != other.reconnect_max
+            || self.min_reconnect_interval != other.min_reconnect_interval
+            || self.ping_interval != other.ping_interval
+            || self.stale_timeout != other.stale_timeout
+            || self.read_timeout != other.read_timeout
+            || self.read_timeout_reconnect != other.read_timeout_reconnect
+            || self.max_clock_skew != other.max_clock_skew
+            || self.warmup_ticks != other.warmup_ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("eth-volatility-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn config_file_fills_in_unset_variables_without_touching_the_environment() {
+        let path = temp_file("file-vars.toml", concat!(
+            "binance_ws_url = \"wss://stream.example.com/ws\"\n",
+            "symbol = \"btcusdt\"\n",
+            "update_interval_seconds = 10\n",
+        ));
+        let builder = ConfigBuilder::new().var("UPDATE_INTERVAL_SECONDS", "20").config_file(&path).unwrap();
+        let config = builder.build().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(config.binance_ws_url, "wss://stream.example.com/ws");
+        assert_eq!(config.trading_symbol, "btcusdt");
+        // Explicit variables win over the file
+        assert_eq!(config.update_interval, Duration::seconds(20));
+        assert!(env::var_os("TRADING_SYMBOL").is_none_or(|symbol| symbol != "btcusdt"));
+    }
+
+    #[test]
+    fn unknown_config_file_keys_are_reported() {
+        let path = temp_file("unknown-keys.toml", concat!(
+            "binance_ws_url = \"wss://stream.example.com/ws\"\n",
+            "update_intervall_seconds = 10\n",
+        ));
+        let builder = ConfigBuilder::new().config_file(&path).unwrap();
+        builder.build().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(builder.vars.unread_file_keys(), ["UPDATE_INTERVALL_SECONDS"]);
+    }
+
+    fn builder() -> ConfigBuilder {
+        ConfigBuilder::new().binance_ws_url("wss://stream.example.com/ws")
+    }
+
+    fn build_error(builder: ConfigBuilder) -> ConfigError {
+        match builder.build() {
+            Ok(_) => panic!("expected a configuration error"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn ndjson_on_stdout_conflicts_with_json_results() {
+        let error = build_error(builder().var("OUTPUT_FORMAT", "json").var("NDJSON_OUTPUT", "stdout"));
+        assert!(matches!(error, ConfigError::Invalid { var: "NDJSON_OUTPUT", .. }), "{}", error);
+
+        // Either on its own, or NDJSON to a file, is fine
+        assert!(builder().var("NDJSON_OUTPUT", "stdout").build().is_ok());
+        assert!(builder().var("OUTPUT_FORMAT", "json").var("NDJSON_OUTPUT", "prices.ndjson").build().is_ok());
+    }
+
+    #[test]
+    fn malformed_aggregation_strategy_is_an_error() {
+        let error = build_error(builder().var("AGGREGATION_STRATEGY", "midian"));
+        assert!(matches!(error, ConfigError::Invalid { var: "AGGREGATION_STRATEGY", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_return_type_is_an_error() {
+        let error = build_error(builder().var("RETURN_TYPE", "logarithmic"));
+        assert!(matches!(error, ConfigError::Invalid { var: "RETURN_TYPE", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_eviction_reference_is_an_error() {
+        let error = build_error(builder().var("EVICTION_REFERENCE", "oldest"));
+        assert!(matches!(error, ConfigError::Invalid { var: "EVICTION_REFERENCE", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_collector_is_an_error() {
+        let error = build_error(builder().var("COLLECTOR", "synthtic"));
+        assert!(matches!(error, ConfigError::Invalid { var: "COLLECTOR", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_binance_stream_is_an_error() {
+        let error = build_error(builder().var("BINANCE_STREAM", "aggtrades"));
+        assert!(matches!(error, ConfigError::Invalid { var: "BINANCE_STREAM", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_log_format_is_an_error() {
+        let error = build_error(builder().var("LOG_FORMAT", "yaml"));
+        assert!(matches!(error, ConfigError::Invalid { var: "LOG_FORMAT", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_flat_window_policy_is_an_error() {
+        let error = build_error(builder().var("FLAT_WINDOW_POLICY", "supress"));
+        assert!(matches!(error, ConfigError::Invalid { var: "FLAT_WINDOW_POLICY", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_non_finite_output_is_an_error() {
+        let error = build_error(builder().var("NON_FINITE_OUTPUT", "eror"));
+        assert!(matches!(error, ConfigError::Invalid { var: "NON_FINITE_OUTPUT", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_annualization_is_an_error() {
+        let error = build_error(builder().var("ANNUALIZATION", "trading-days"));
+        assert!(matches!(error, ConfigError::Invalid { var: "ANNUALIZATION", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_spread_adjustment_is_an_error() {
+        let error = build_error(builder().var("SPREAD_ADJUSTMENT", "widen-half"));
+        assert!(matches!(error, ConfigError::Invalid { var: "SPREAD_ADJUSTMENT", .. }), "{}", error);
+    }
+
+    #[test]
+    fn malformed_excluded_weekday_is_an_error() {
+        let error = build_error(builder().var("EXCLUDED_WEEKDAYS", "sat,sunday,hols"));
+        assert!(matches!(error, ConfigError::Invalid { var: "EXCLUDED_WEEKDAYS", .. }), "{}", error);
+        assert!(error.to_string().contains("`hols`"), "{}", error);
+
+        let config = builder().var("EXCLUDED_WEEKDAYS", "sat, sunday").build().ok().unwrap();
+        assert_eq!(config.excluded_weekdays, [Weekday::Sat, Weekday::Sun]);
+    }
+
+    #[test]
+    fn malformed_liquidity_tier_is_an_error() {
+        for tiers in ["binance:1,okx", "binance:1,okx:top", ":2"] {
+            let error = build_error(builder().var("LIQUIDITY_TIERS", tiers));
+            assert!(matches!(error, ConfigError::Invalid { var: "LIQUIDITY_TIERS", .. }), "{}: {}", tiers, error);
+        }
+
+        let config = builder().var("LIQUIDITY_TIERS", "Binance:1, gemini:2").build().ok().unwrap();
+        assert_eq!(config.liquidity_tiers, HashMap::from([("binance".to_string(), 1), ("gemini".to_string(), 2)]));
+    }
+
+    #[test]
+    fn malformed_source_time_offset_is_an_error() {
+        for offsets in ["okx:-250,htx", "okx:250ms", "okx:-250,:100"] {
+            let error = build_error(builder().var("SOURCE_TIME_OFFSETS_MS", offsets));
+            assert!(matches!(error, ConfigError::Invalid { var: "SOURCE_TIME_OFFSETS_MS", .. }), "{}: {}", offsets, error);
+        }
+
+        let config = builder().var("SOURCE_TIME_OFFSETS_MS", "OKX:-250, htx:28800000").build().ok().unwrap();
+        assert_eq!(config.time_offsets["okx"], Duration::milliseconds(-250));
+        assert_eq!(config.time_offsets["htx"], Duration::hours(8));
+    }
+
+    #[test]
+    fn malformed_volatility_preset_is_an_error() {
+        let error = build_error(builder().var("VOLATILITY_PRESET", "blomberg"));
+        assert!(matches!(error, ConfigError::Invalid { var: "VOLATILITY_PRESET", .. }), "{}", error);
+    }
+
+    #[test]
+    fn duplicate_collectors_are_rejected() {
+        let error = build_error(builder().var("COLLECTORS", "binance, okx, Binance"));
+        assert!(matches!(error, ConfigError::Invalid { var: "COLLECTORS", .. }), "{}", error);
+        assert!(error.to_string().contains("`binance`"), "{}", error);
+
+        let config = builder().var("COLLECTORS", "binance,okx").build().ok().unwrap();
+        assert_eq!(config.collectors, ["binance", "okx"]);
+    }
+
+    #[test]
+    fn command_line_overrides_beat_the_environment() {
+        let overrides = ConfigOverrides {
+            update_interval_seconds: Some(10),
+            trading_symbol: Some("btcusdt".to_string()),
+            ..ConfigOverrides::default()
+        };
+        let env = builder().var("UPDATE_INTERVAL_SECONDS", "30").var("TRADING_SYMBOL", "ethusdt").var("VOLATILITY_WINDOW_HOURS", "3");
+        let config = Config::from_args_and_builder(&overrides, env).ok().unwrap();
+        assert_eq!(config.update_interval, Duration::seconds(10));
+        assert_eq!(config.trading_symbol, "btcusdt");
+        // Not overridden, so the environment's value stands
+        assert_eq!(config.volatility_window, Duration::hours(3));
+    }
+
+    #[test]
+    fn builder_setters_reach_the_config() {
+        let config = builder()
+            .update_interval(Duration::seconds(2))
+            .volatility_window(Duration::hours(3))
+            .symbol("btcusdt")
+            .build()
+            .ok()
+            .unwrap();
+        assert_eq!(config.binance_ws_url, "wss://stream.example.com/ws");
+        assert_eq!(config.update_interval, Duration::seconds(2));
+        assert_eq!(config.volatility_window, Duration::hours(3));
+        assert_eq!(config.trading_symbol, "btcusdt");
+    }
+
+    #[test]
+    fn builder_rejects_each_broken_invariant() {
+        assert!(matches!(build_error(ConfigBuilder::new()), ConfigError::Missing("BINANCE_WS_URL")));
+        for url in ["", "stream.example.com/ws", "https://stream.example.com/ws", "wss://"] {
+            let error = build_error(ConfigBuilder::new().binance_ws_url(url));
+            assert!(matches!(error, ConfigError::Invalid { var: "BINANCE_WS_URL", .. }), "{}: {}", url, error);
+        }
+        let error = build_error(builder().update_interval(Duration::zero()));
+        assert!(matches!(error, ConfigError::NotPositive { var: "UPDATE_INTERVAL_SECONDS", .. }), "{}", error);
+        let error = build_error(builder().volatility_window(Duration::hours(-1)));
+        assert!(matches!(error, ConfigError::NotPositive { var: "VOLATILITY_WINDOW_HOURS", .. }), "{}", error);
+    }
+
+    #[test]
+    fn rest_base_defaults_to_production_and_must_be_http() {
+        assert_eq!(builder().build().ok().unwrap().binance_rest_base, DEFAULT_BINANCE_REST_BASE);
+        let testnet = builder().var("BINANCE_REST_BASE", "https://testnet.binance.vision/").build().ok().unwrap();
+        assert_eq!(testnet.binance_rest_base, "https://testnet.binance.vision");
+
+        let error = build_error(builder().var("BINANCE_REST_BASE", "wss://testnet.binance.vision"));
+        assert!(matches!(error, ConfigError::Invalid { var: "BINANCE_REST_BASE", .. }), "{}", error);
+    }
+
+    #[test]
+    fn annualization_interval_selects_the_median_gap() {
+        assert_eq!(builder().build().ok().unwrap().interval_estimate, IntervalEstimate::Mean);
+        let config = builder().var("ANNUALIZATION_INTERVAL", "median").build().ok().unwrap();
+        assert_eq!(config.interval_estimate, IntervalEstimate::Median);
+
+        let error = build_error(builder().var("ANNUALIZATION_INTERVAL", "mode"));
+        assert!(matches!(error, ConfigError::Invalid { var: "ANNUALIZATION_INTERVAL", .. }), "{}", error);
+    }
+}
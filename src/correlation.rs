@@ -0,0 +1,125 @@
+use chrono::Duration;
+use crate::price_collector::PricePoint;
+use crate::volatility::VolatilityCalculator;
+
+// Pearson correlation of log returns between two assets, e.g. ETH and BTC.
+// Points are paired by nearest timestamp and a point with no partner within
+// `tolerance` is dropped; returns are taken between consecutive pairs.
+pub struct CorrelationCalculator {
+    tolerance: Duration,
+}
+
+impl CorrelationCalculator {
+    pub fn new(tolerance: Duration) -> Self {
+        Self { tolerance }
+    }
+
+    // Correlation over whatever both calculators' windows hold
+    pub fn correlate(&self, a: &VolatilityCalculator, b: &VolatilityCalculator) -> Option<f64> {
+        let a: Vec<_> = a.prices().collect();
+        let b: Vec<_> = b.prices().collect();
+        self.correlate_points(&a, &b)
+    }
+
+    // Both series oldest first. None with fewer than two paired returns or
+    // when either side doesn't move.
+    pub fn correlate_points(&self, a: &[&PricePoint], b: &[&PricePoint]) -> Option<f64> {
+        let pairs = self.align(a, b);
+        let (returns_a, returns_b): (Vec<f64>, Vec<f64>) = pairs.windows(2)
+            .map(|w| ((w[1].0 / w[0].0).ln(), (w[1].1 / w[0].1).ln()))
+            .unzip();
+        pearson(&returns_a, &returns_b)
+    }
+
+    // Prices paired by nearest timestamp, each point used at most once
+    fn align(&self, a: &[&PricePoint], b: &[&PricePoint]) -> Vec<(f64, f64)> {
+        let mut pairs = Vec::new();
+        let mut j = 0;
+        for point in a {
+            // Skip b points too old to pair with this or any later a point
+            while j < b.len() && b[j].timestamp < point.timestamp - self.tolerance {
+                j += 1;
+            }
+            let nearest = (j..b.len())
+                .take_while(|&k| b[k].timestamp <= point.timestamp + self.tolerance)
+                .min_by_key(|&k| (b[k].timestamp - point.timestamp).abs());
+            if let Some(k) = nearest {
+                pairs.push((point.price, b[k].price));
+                j = k + 1;
+            }
+        }
+        pairs
+    }
+}
+
+fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    if x.len() < 2 {
+        return None;
+    }
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (xi, yi) in x.iter().zip(y) {
+        cov += (xi - mean_x) * (yi - mean_y);
+        var_x += (xi - mean_x).powi(2);
+        var_y += (yi - mean_y).powi(2);
+    }
+    let denominator = (var_x * var_y).sqrt();
+    (denominator > 0.0 && denominator.is_finite()).then(|| cov / denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn series(points: &[(i64, f64)]) -> Vec<PricePoint> {
+        points.iter()
+            .map(|&(seconds, price)| PricePoint {
+                timestamp: start() + Duration::seconds(seconds),
+                price,
+                source: "test".to_string(),
+                spread: None,
+                volume: None,
+            })
+            .collect()
+    }
+
+    fn correlate(a: &[PricePoint], b: &[PricePoint]) -> Option<f64> {
+        let (a, b): (Vec<_>, Vec<_>) = (a.iter().collect(), b.iter().collect());
+        CorrelationCalculator::new(Duration::seconds(5)).correlate_points(&a, &b)
+    }
+
+    const ETH: [(i64, f64); 5] = [(0, 100.0), (60, 101.0), (120, 99.5), (180, 102.0), (240, 101.0)];
+
+    #[test]
+    fn squared_prices_are_perfectly_correlated() {
+        // Within tolerance of ETH's times, and one stray point with no partner
+        let mut btc: Vec<_> = ETH.iter().map(|&(t, p)| (t + 2, p * p / 10.0)).collect();
+        btc.insert(2, (90, 50_000.0));
+        let correlation = correlate(&series(&ETH), &series(&btc)).unwrap();
+        assert!((correlation - 1.0).abs() < 1e-12, "{}", correlation);
+    }
+
+    #[test]
+    fn reciprocal_prices_are_perfectly_anti_correlated() {
+        let inverse: Vec<_> = ETH.iter().map(|&(t, p)| (t - 3, 1.0 / p)).collect();
+        let correlation = correlate(&series(&ETH), &series(&inverse)).unwrap();
+        assert!((correlation + 1.0).abs() < 1e-12, "{}", correlation);
+    }
+
+    #[test]
+    fn too_few_paired_returns_is_none() {
+        // Only the first two points pair up; the rest are 30s off
+        let b = series(&[(0, 10.0), (60, 11.0), (150, 12.0), (210, 11.0)]);
+        assert!(correlate(&series(&ETH), &b).is_none());
+        // A flat side has no variance to correlate
+        let flat: Vec<_> = ETH.iter().map(|&(t, _)| (t, 10.0)).collect();
+        assert!(correlate(&series(&ETH), &series(&flat)).is_none());
+    }
+}
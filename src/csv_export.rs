@@ -0,0 +1,77 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+use serde::Serialize;
+use crate::price_collector::PricePoint;
+use crate::error::VolatilityError;
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    timestamp: String,
+    price: f64,
+    source: &'a str,
+}
+
+// Appends every fetched price to a CSV file for offline analysis
+pub struct CsvSink {
+    writer: csv::Writer<std::fs::File>,
+}
+
+impl CsvSink {
+    // Appends to an existing file; the header is only written to a new or empty one
+    pub fn open(path: &Path) -> Result<Self, VolatilityError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let needs_header = file.metadata()?.len() == 0;
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(file);
+        if needs_header {
+            writer.write_record(["timestamp", "price", "source"])?;
+            writer.flush()?;
+        }
+        Ok(Self { writer })
+    }
+
+    // Flushed per row so a crash loses at most the row being written
+    pub fn write(&mut self, point: &PricePoint) -> Result<(), VolatilityError> {
+        self.writer.serialize(CsvRow {
+            timestamp: point.timestamp.to_rfc3339(),
+            price: point.price,
+            source: &point.source,
+        })?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn point(minute: u32, price: f64) -> PricePoint {
+        PricePoint {
+            timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 0, minute, 0).unwrap(),
+            price,
+            source: "Binance:ETHUSDT".to_string(),
+            spread: None,
+            volume: None,
+        }
+    }
+
+    #[test]
+    fn rows_are_appended_under_a_single_header() {
+        let path = std::env::temp_dir().join(format!("eth-volatility-{}-export.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        CsvSink::open(&path).unwrap().write(&point(0, 2500.25)).unwrap();
+        // Reopening appends without repeating the header
+        CsvSink::open(&path).unwrap().write(&point(1, 2501.0)).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, concat!(
+            "timestamp,price,source\n",
+            "2026-01-01T00:00:00+00:00,2500.25,Binance:ETHUSDT\n",
+            "2026-01-01T00:01:00+00:00,2501.0,Binance:ETHUSDT\n",
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use crate::error::VolatilityError;
+
+// End-of-day rollup of the live volatility series
+#[derive(Debug, Clone, Serialize)]
+pub struct DailySummary {
+    pub date: NaiveDate,
+    pub average_volatility: f64,
+    pub min_volatility: f64,
+    pub max_volatility: f64,
+    pub close_price: f64,
+    pub samples: usize,
+}
+
+// Retains the intraday volatility series and closes it out when a price
+// arrives on a new UTC day
+#[derive(Default)]
+pub struct DailySummaryTracker {
+    current_day: Option<NaiveDate>,
+    volatilities: Vec<f64>,
+    close_price: Option<f64>,
+}
+
+impl DailySummaryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Record one tick, returning the previous day's summary on a day boundary
+    pub fn record(&mut self, timestamp: DateTime<Utc>, price: f64, volatility: Option<f64>) -> Option<DailySummary> {
+        let day = timestamp.date_naive();
+        let mut completed = None;
+
+        if let Some(current_day) = self.current_day {
+            if day > current_day {
+                completed = self.summarize(current_day);
+                self.volatilities.clear();
+            }
+        }
+
+        self.current_day = Some(day);
+        self.close_price = Some(price);
+        if let Some(vol) = volatility {
+            self.volatilities.push(vol);
+        }
+        completed
+    }
+
+    fn summarize(&self, date: NaiveDate) -> Option<DailySummary> {
+        let close_price = self.close_price?;
+        if self.volatilities.is_empty() {
+            return None;
+        }
+
+        let samples = self.volatilities.len();
+        Some(DailySummary {
+            date,
+            average_volatility: self.volatilities.iter().sum::<f64>() / samples as f64,
+            min_volatility: self.volatilities.iter().cloned().fold(f64::INFINITY, f64::min),
+            max_volatility: self.volatilities.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            close_price,
+            samples,
+        })
+    }
+}
+
+// Where daily summaries are delivered: appended as JSON lines to a file,
+// POSTed to a webhook, or both
+pub struct DailySummarySink {
+    path: Option<PathBuf>,
+    webhook_url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl DailySummarySink {
+    pub fn new(path: Option<PathBuf>, webhook_url: Option<String>) -> Self {
+        Self {
+            path,
+            webhook_url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some() || self.webhook_url.is_some()
+    }
+
+    pub async fn emit(&self, summary: &DailySummary) -> Result<(), VolatilityError> {
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(summary)?)?;
+        }
+
+        if let Some(url) = &self.webhook_url {
+            self.client.post(url)
+                .json(summary)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use chrono::{DateTime, Duration, Utc};
+use crate::price_collector::{OhlcPoint, PricePoint};
+
+// Empty buckets a Downsampler fills by default before treating the hole as a gap
+pub const DEFAULT_MAX_FILL_BUCKETS: i64 = 10;
+
+// Buckets irregular ticks into fixed intervals, one close per bucket with
+// the last price winning. A bucket is only emitted once a tick lands in a
+// later one. A short run of buckets with no trade repeats the previous
+// close, so the output is evenly spaced; a longer one is left empty, so the
+// next close sits across the gap and the calculator's gap check sees it
+// rather than a run of zero returns.
+pub struct Downsampler {
+    interval_ms: i64,
+    // Most empty buckets filled in a row
+    max_fill: i64,
+    // Bucket index and the close so far
+    pending: Option<(i64, PricePoint)>,
+    late_ticks: u64,
+}
+
+impl Downsampler {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval_ms: interval.num_milliseconds().max(1),
+            max_fill: DEFAULT_MAX_FILL_BUCKETS,
+            pending: None,
+            late_ticks: 0,
+        }
+    }
+
+    // Fill only holes whose closes would end up at most `max_gap` apart,
+    // typically the calculator's gap threshold
+    pub fn with_max_gap(mut self, max_gap: Duration) -> Self {
+        self.set_max_gap(max_gap);
+        self
+    }
+
+    pub fn set_max_gap(&mut self, max_gap: Duration) {
+        self.max_fill = (max_gap.num_milliseconds() / self.interval_ms - 1).max(0);
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::milliseconds(self.interval_ms)
+    }
+
+    // Ticks dropped for belonging to a bucket that was already emitted
+    pub fn late_ticks(&self) -> u64 {
+        self.late_ticks
+    }
+
+    // The close of the bucket still being filled
+    pub fn pending(&self) -> Option<&PricePoint> {
+        self.pending.as_ref().map(|(_, close)| close)
+    }
+
+    // Closes of every bucket completed by this tick, oldest first, each
+    // stamped with its bucket's end time
+    pub fn push(&mut self, price: PricePoint) -> Vec<PricePoint> {
+        let bucket = price.timestamp.timestamp_millis().div_euclid(self.interval_ms);
+        let Some((current, close)) = self.pending.take() else {
+            self.pending = Some((bucket, price));
+            return Vec::new();
+        };
+
+        // Its bucket was emitted already; rewriting it now would change a
+        // close the window has seen
+        if bucket < current {
+            log::debug!("Dropping tick at {} for an already emitted bucket", price.timestamp);
+            self.late_ticks += 1;
+            self.pending = Some((current, close));
+            return Vec::new();
+        }
+
+        // Same bucket: last price wins
+        if bucket == current {
+            let volume = match (close.volume, price.volume) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+            self.pending = Some((current, PricePoint { volume, ..price }));
+            return Vec::new();
+        }
+
+        let empty = bucket - current - 1;
+        let mut closed = vec![self.stamp(current, close.clone())];
+        if empty <= self.max_fill {
+            for filler in current + 1..bucket {
+                closed.push(self.stamp(filler, PricePoint { volume: None, ..close.clone() }));
+            }
+        } else {
+            log::debug!("{} empty buckets since the last tick, leaving a gap", empty);
+        }
+        self.pending = Some((bucket, price));
+        closed
+    }
+
+    fn stamp(&self, bucket: i64, close: PricePoint) -> PricePoint {
+        let end = DateTime::from_timestamp_millis((bucket + 1) * self.interval_ms).unwrap_or_else(Utc::now);
+        PricePoint { timestamp: end, ..close }
+    }
+}
+
+// Builds OHLC bars from ticks over fixed intervals: open is the first
+// price, close the last, with the high and low tracked in between. A bar is
+// emitted once a tick lands in a later interval. Intervals with no ticks
+// produce no bar, since a flat filler bar would drag range estimators down.
+pub struct BarAggregator {
+    interval_ms: i64,
+    // Bucket index and the bar so far
+    pending: Option<(i64, OhlcPoint)>,
+    late_ticks: u64,
+}
+
+impl BarAggregator {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval_ms: interval.num_milliseconds().max(1),
+            pending: None,
+            late_ticks: 0,
+        }
+    }
+
+    // Ticks dropped for belonging to a bar that was already emitted
+    pub fn late_ticks(&self) -> u64 {
+        self.late_ticks
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::milliseconds(self.interval_ms)
+    }
+
+    // The bar still being filled
+    pub fn pending(&self) -> Option<&OhlcPoint> {
+        self.pending.as_ref().map(|(_, bar)| bar)
+    }
+
+    // The bar completed by this tick, if any, stamped with its interval's
+    // end time. A late tick for an interval already emitted is dropped:
+    // folding it into the current bar would give that bar a range it never
+    // traded in.
+    pub fn push(&mut self, price: &PricePoint) -> Option<OhlcPoint> {
+        let bucket = price.timestamp.timestamp_millis().div_euclid(self.interval_ms);
+        if let Some((current, bar)) = self.pending.as_mut() {
+            if bucket < *current {
+                log::debug!("Dropping tick at {} for an already emitted bar", price.timestamp);
+                self.late_ticks += 1;
+                return None;
+            }
+            if bucket == *current {
+                bar.high = bar.high.max(price.price);
+                bar.low = bar.low.min(price.price);
+                bar.close = price.price;
+                return None;
+            }
+        }
+
+        let opened = OhlcPoint {
+            timestamp: price.timestamp,
+            open: price.price,
+            high: price.price,
+            low: price.price,
+            close: price.price,
+            source: price.source.clone(),
+        };
+        let (current, bar) = self.pending.replace((bucket, opened))?;
+        let end = DateTime::from_timestamp_millis((current + 1) * self.interval_ms).unwrap_or_else(Utc::now);
+        Some(OhlcPoint { timestamp: end, ..bar })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(ms: i64, price: f64) -> PricePoint {
+        PricePoint {
+            timestamp: DateTime::from_timestamp_millis(ms).unwrap(),
+            price,
+            source: "test".to_string(),
+            spread: None,
+            volume: Some(1.0),
+        }
+    }
+
+    fn closes(points: &[PricePoint]) -> Vec<(i64, f64)> {
+        points.iter().map(|p| (p.timestamp.timestamp_millis(), p.price)).collect()
+    }
+
+    #[test]
+    fn last_price_in_a_bucket_wins() {
+        let mut sampler = Downsampler::new(Duration::seconds(1));
+        assert!(sampler.push(tick(100, 1.0)).is_empty());
+        assert!(sampler.push(tick(900, 2.0)).is_empty());
+        let pending = sampler.pending().unwrap();
+        assert_eq!(pending.price, 2.0);
+        assert_eq!(pending.volume, Some(2.0));
+        assert_eq!(closes(&sampler.push(tick(1_100, 3.0))), vec![(1_000, 2.0)]);
+    }
+
+    #[test]
+    fn bucket_edges_belong_to_the_later_bucket() {
+        let mut sampler = Downsampler::new(Duration::seconds(1));
+        sampler.push(tick(999, 1.0));
+        // Exactly on the boundary starts bucket 1 and closes bucket 0
+        assert_eq!(closes(&sampler.push(tick(1_000, 2.0))), vec![(1_000, 1.0)]);
+        assert_eq!(closes(&sampler.push(tick(2_000, 3.0))), vec![(2_000, 2.0)]);
+    }
+
+    #[test]
+    fn short_holes_repeat_the_previous_close() {
+        let mut sampler = Downsampler::new(Duration::seconds(1)).with_max_gap(Duration::seconds(5));
+        sampler.push(tick(500, 1.0));
+        let emitted = sampler.push(tick(3_500, 2.0));
+        assert_eq!(closes(&emitted), vec![(1_000, 1.0), (2_000, 1.0), (3_000, 1.0)]);
+        assert_eq!(emitted[1].volume, None);
+    }
+
+    #[test]
+    fn long_holes_are_left_as_gaps() {
+        let mut sampler = Downsampler::new(Duration::seconds(1)).with_max_gap(Duration::seconds(5));
+        sampler.push(tick(500, 1.0));
+        // Closes 6s apart: more than the gap, so nothing is filled
+        assert_eq!(closes(&sampler.push(tick(6_500, 2.0))), vec![(1_000, 1.0)]);
+        assert_eq!(closes(&sampler.push(tick(7_500, 3.0))), vec![(7_000, 2.0)]);
+    }
+
+    #[test]
+    fn hole_exactly_at_the_gap_is_filled() {
+        let mut sampler = Downsampler::new(Duration::seconds(1)).with_max_gap(Duration::seconds(5));
+        sampler.push(tick(500, 1.0));
+        assert_eq!(sampler.push(tick(5_500, 2.0)).len(), 5);
+    }
+
+    #[test]
+    fn far_future_tick_does_not_fill() {
+        let mut sampler = Downsampler::new(Duration::milliseconds(1));
+        sampler.push(tick(0, 1.0));
+        let year_9000 = 221_845_392_000_000;
+        assert_eq!(closes(&sampler.push(tick(year_9000, 2.0))), vec![(1, 1.0)]);
+    }
+
+    #[test]
+    fn late_ticks_do_not_widen_the_current_bar() {
+        let mut bars = BarAggregator::new(Duration::seconds(1));
+        bars.push(&tick(100, 10.0));
+        bars.push(&tick(1_100, 20.0));
+        // Belongs to the bar already emitted for 0..1s
+        assert!(bars.push(&tick(900, 5.0)).is_none());
+        assert_eq!(bars.late_ticks(), 1);
+        let pending = bars.pending().unwrap();
+        assert_eq!((pending.low, pending.close), (20.0, 20.0));
+
+        let bar = bars.push(&tick(2_100, 21.0)).unwrap();
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (20.0, 20.0, 20.0, 20.0));
+        assert_eq!(bar.timestamp.timestamp_millis(), 2_000);
+    }
+
+    #[test]
+    fn late_ticks_are_dropped() {
+        let mut sampler = Downsampler::new(Duration::seconds(1));
+        sampler.push(tick(500, 1.0));
+        sampler.push(tick(1_500, 2.0));
+        // Bucket 0 was emitted with close 1.0; this must not rewrite it or the pending bucket
+        assert!(sampler.push(tick(800, 9.0)).is_empty());
+        assert_eq!(sampler.late_ticks(), 1);
+        assert_eq!(sampler.pending().unwrap().price, 2.0);
+        assert_eq!(closes(&sampler.push(tick(2_500, 3.0))), vec![(2_000, 2.0)]);
+    }
+}
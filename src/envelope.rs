@@ -0,0 +1,56 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::error::VolatilityError;
+
+// Schema written by this build
+pub const SCHEMA_VERSION: u32 = 1;
+
+// A persisted or exported point tagged with the schema it was written in,
+// {"v": 1, "point": {...}}, so a format change fails loudly instead of
+// silently misreading older or newer files
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub v: u32,
+    pub point: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(point: T) -> Self {
+        Self { v: SCHEMA_VERSION, point }
+    }
+
+    // The point, if this build can read its version
+    pub fn open(self) -> Result<T, VolatilityError> {
+        check_version(self.v as u64)?;
+        Ok(self.point)
+    }
+}
+
+// Records written before the envelope existed. They carry no version, and
+// their point is the whole record, laid out as v1's.
+pub const UNVERSIONED: u32 = 0;
+
+// v1 is the only enveloped schema; when it changes, older versions are
+// converted to the current point here rather than rejected
+pub fn check_version(version: u64) -> Result<(), VolatilityError> {
+    match version {
+        1 => Ok(()),
+        other => Err(VolatilityError::Parse(format!(
+            "unsupported schema version {}, this build reads version {}",
+            other, SCHEMA_VERSION
+        ))),
+    }
+}
+
+// Read the version before the point, so a record from a newer schema is
+// reported as such rather than as whatever field fails to decode first. A
+// record with no `v` at all is an UNVERSIONED bare point.
+pub fn from_json<T: DeserializeOwned>(mut value: serde_json::Value) -> Result<T, VolatilityError> {
+    let Some(version) = value.get("v") else {
+        return Ok(serde_json::from_value(value)?);
+    };
+    let version = version.as_u64()
+        .ok_or_else(|| VolatilityError::Parse(format!("schema version {} is not a number", version)))?;
+    check_version(version)?;
+    Ok(serde_json::from_value(value["point"].take())?)
+}
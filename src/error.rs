@@ -0,0 +1,150 @@
+use thiserror::Error;
+use crate::config::ConfigError;
+use crate::price_collector::CollectorError;
+use crate::replay::ReplayFinished;
+
+// Every failure the library reports, so callers can match on the kind
+// instead of downcasting a boxed error
+#[derive(Debug, Error)]
+pub enum VolatilityError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Collector(#[from] CollectorError),
+    // Boxed, as tungstenite's error is large enough to bloat every Result
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    // Malformed data from a venue, a file or a setting
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error(transparent)]
+    ReplayFinished(#[from] ReplayFinished),
+    // A read was attempted before the venue connection was opened
+    #[error("WebSocket connection not established")]
+    NotConnected,
+    // Fewer sources reported than MIN_SOURCES asks for
+    #[error("only {got} price source(s) reported, {need} required")]
+    InsufficientSources { got: usize, need: usize },
+    // The estimate came out NaN or infinite, under NonFinitePolicy::Error
+    #[error("non-finite volatility ({0})")]
+    NonFiniteVolatility(f64),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl VolatilityError {
+    // Whether trying again later could succeed, e.g. after a reconnect
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Collector(e) => e.is_transient(),
+            Self::WebSocket(_) | Self::Http(_) | Self::Io(_) => true,
+            Self::NotConnected | Self::InsufficientSources { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<tungstenite::Error> for VolatilityError {
+    fn from(e: tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(e))
+    }
+}
+
+impl From<serde_json::Error> for VolatilityError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<csv::Error> for VolatilityError {
+    fn from(e: csv::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for VolatilityError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<std::num::ParseFloatError> for VolatilityError {
+    fn from(e: std::num::ParseFloatError) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for VolatilityError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<url::ParseError> for VolatilityError {
+    fn from(e: url::ParseError) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+#[cfg(feature = "uniswap")]
+impl From<web3::contract::Error> for VolatilityError {
+    fn from(e: web3::contract::Error) -> Self {
+        Self::Collector(e.into())
+    }
+}
+
+#[cfg(feature = "uniswap")]
+impl From<web3::Error> for VolatilityError {
+    fn from(e: web3::Error) -> Self {
+        Self::Collector(CollectorError::Transport(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retryable_kinds_are_transient() {
+        let transient = [
+            VolatilityError::NotConnected,
+            VolatilityError::InsufficientSources { got: 1, need: 2 },
+            VolatilityError::Collector(CollectorError::Connection("refused".to_string())),
+            VolatilityError::Collector(CollectorError::Timeout("no trade".to_string())),
+            VolatilityError::Collector(CollectorError::Transport("reset".to_string())),
+            tungstenite::Error::ConnectionClosed.into(),
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "pipe").into(),
+        ];
+        for error in transient {
+            assert!(error.is_transient(), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn bad_data_and_settings_are_not_transient() {
+        let permanent = [
+            VolatilityError::Config(ConfigError::Missing("BINANCE_WS_URL")),
+            VolatilityError::Collector(CollectorError::Subscription("rejected".to_string())),
+            VolatilityError::Collector(CollectorError::Parse("bad json".to_string())),
+            VolatilityError::Collector(CollectorError::Revert("reverted".to_string())),
+            VolatilityError::Collector(CollectorError::Decode("abi".to_string())),
+            VolatilityError::Parse("bad number".to_string()),
+            VolatilityError::NonFiniteVolatility(f64::NAN),
+            VolatilityError::Other("anything else".to_string()),
+            "x".parse::<f64>().unwrap_err().into(),
+        ];
+        for error in permanent {
+            assert!(!error.is_transient(), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn insufficient_sources_names_both_counts() {
+        let error = VolatilityError::InsufficientSources { got: 1, need: 3 };
+        assert_eq!(error.to_string(), "only 1 price source(s) reported, 3 required");
+    }
+}
@@ -0,0 +1,53 @@
+// Decimal places for logged prices and volatility figures, so the same
+// binary reads sensibly for BTC and for a token priced in fractions of a cent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub price_decimals: usize,
+    pub vol_decimals: usize,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self { price_decimals: 2, vol_decimals: 2 }
+    }
+}
+
+impl NumberFormat {
+    // "$2500.12"
+    pub fn price(&self, value: f64) -> String {
+        format!("${:.*}", self.price_decimals, value)
+    }
+
+    // A fraction as a percentage, e.g. 0.6523 as "65.23%"
+    pub fn pct(&self, fraction: f64) -> String {
+        format!("{:.*}%", self.vol_decimals, fraction * 100.0)
+    }
+
+    // Like pct, always with a sign, for changes
+    pub fn signed_pct(&self, fraction: f64) -> String {
+        format!("{:+.*}%", self.vol_decimals, fraction * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn figures_use_the_configured_decimals() {
+        let default = NumberFormat::default();
+        assert_eq!(default.price(2500.126), "$2500.13");
+        assert_eq!(default.pct(0.6523), "65.23%");
+        assert_eq!(default.signed_pct(-0.0125), "-1.25%");
+
+        // A token priced in fractions of a cent
+        let fine = NumberFormat { price_decimals: 6, vol_decimals: 0 };
+        assert_eq!(fine.price(0.000123456), "$0.000123");
+        assert_eq!(fine.pct(0.6523), "65%");
+        assert_eq!(fine.signed_pct(0.05), "+5%");
+
+        let whole = NumberFormat { price_decimals: 0, vol_decimals: 4 };
+        assert_eq!(whole.price(43210.6), "$43211");
+        assert_eq!(whole.pct(0.123456), "12.3456%");
+    }
+}
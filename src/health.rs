@@ -0,0 +1,116 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use crate::server::NotReady;
+
+#[derive(Debug, Default)]
+struct Health {
+    samples_needed: Option<usize>,
+    last_success: Option<DateTime<Utc>>,
+}
+
+// Liveness and readiness for container probes. The main loop records each
+// successful fetch and how many samples are still missing; `/ready` fails
+// until the window can report and while fetches have been failing for
+// longer than `max_staleness`.
+#[derive(Clone)]
+pub struct HealthState {
+    inner: Arc<RwLock<Health>>,
+    max_staleness: Duration,
+}
+
+impl HealthState {
+    pub fn new(max_staleness: Duration) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Health::default())),
+            max_staleness,
+        }
+    }
+
+    pub fn record_success(&self, at: DateTime<Utc>) {
+        self.inner.write().unwrap_or_else(|e| e.into_inner()).last_success = Some(at);
+    }
+
+    pub fn set_samples_needed(&self, needed: usize) {
+        self.inner.write().unwrap_or_else(|e| e.into_inner()).samples_needed = Some(needed);
+    }
+
+    // Ok once ready, otherwise the reason served with the 503
+    pub fn readiness(&self, now: DateTime<Utc>) -> Result<(), String> {
+        let health = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let Some(last_success) = health.last_success else {
+            return Err("no successful price fetch yet".to_string());
+        };
+        let age = now - last_success;
+        if age > self.max_staleness {
+            return Err(format!("last successful fetch {}s ago", age.num_seconds()));
+        }
+        match health.samples_needed {
+            Some(0) => Ok(()),
+            Some(needed) => Err(format!("{} more samples needed", needed)),
+            None => Err("volatility window not filled yet".to_string()),
+        }
+    }
+}
+
+pub fn router(state: HealthState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/ready", get(ready))
+        .with_state(state)
+}
+
+pub async fn serve(addr: SocketAddr, state: HealthState) -> Result<(), hyper::Error> {
+    log::info!("Health server listening on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+async fn ready(State(state): State<HealthState>) -> Result<&'static str, (StatusCode, Json<NotReady>)> {
+    state
+        .readiness(Utc::now())
+        .map(|_| "ready")
+        .map_err(|reason| (StatusCode::SERVICE_UNAVAILABLE, Json(NotReady { reason })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ready_flips_to_200_once_the_window_fills() {
+        let state = HealthState::new(Duration::seconds(30));
+        assert_eq!(healthz().await, "ok");
+        let (status, _) = ready(State(state.clone())).await.unwrap_err();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.record_success(Utc::now());
+        for needed in (1..=3).rev() {
+            state.set_samples_needed(needed);
+            let (status, Json(body)) = ready(State(state.clone())).await.unwrap_err();
+            assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(body.reason, format!("{} more samples needed", needed));
+        }
+
+        state.set_samples_needed(0);
+        assert_eq!(ready(State(state.clone())).await.unwrap(), "ready");
+    }
+
+    #[test]
+    fn stale_fetches_make_a_full_window_unready() {
+        let state = HealthState::new(Duration::seconds(30));
+        let fetched = Utc::now();
+        state.record_success(fetched);
+        state.set_samples_needed(0);
+
+        assert!(state.readiness(fetched + Duration::seconds(30)).is_ok());
+        let reason = state.readiness(fetched + Duration::seconds(31)).unwrap_err();
+        assert_eq!(reason, "last successful fetch 31s ago");
+    }
+}
@@ -0,0 +1,75 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use crate::price_collector::PricePoint;
+use crate::volatility::VolatilityResult;
+
+// Synchronous per-tick callbacks for code embedding the estimator. Both
+// methods default to no-ops so implementors only override what they need.
+pub trait TickHook: Send {
+    fn on_price(&mut self, _price: &PricePoint) {}
+    fn on_volatility(&mut self, _result: &VolatilityResult) {}
+}
+
+// Adapts closures into `TickHook`s
+struct PriceFn<F>(F);
+struct VolatilityFn<F>(F);
+
+impl<F: FnMut(&PricePoint) + Send> TickHook for PriceFn<F> {
+    fn on_price(&mut self, price: &PricePoint) {
+        (self.0)(price)
+    }
+}
+
+impl<F: FnMut(&VolatilityResult) + Send> TickHook for VolatilityFn<F> {
+    fn on_volatility(&mut self, result: &VolatilityResult) {
+        (self.0)(result)
+    }
+}
+
+// Registered hooks, invoked in registration order. A hook that panics is
+// logged and dropped so it can't take down the main loop.
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: Vec<Box<dyn TickHook>>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, hook: Box<dyn TickHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn on_price<F: FnMut(&PricePoint) + Send + 'static>(&mut self, f: F) {
+        self.register(Box::new(PriceFn(f)));
+    }
+
+    pub fn on_volatility<F: FnMut(&VolatilityResult) + Send + 'static>(&mut self, f: F) {
+        self.register(Box::new(VolatilityFn(f)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    pub fn notify_price(&mut self, price: &PricePoint) {
+        self.notify_each(|hook| hook.on_price(price));
+    }
+
+    pub fn notify_volatility(&mut self, result: &VolatilityResult) {
+        self.notify_each(|hook| hook.on_volatility(result));
+    }
+
+    fn notify_each(&mut self, mut call: impl FnMut(&mut dyn TickHook)) {
+        self.hooks.retain_mut(|hook| {
+            match catch_unwind(AssertUnwindSafe(|| call(hook.as_mut()))) {
+                Ok(()) => true,
+                Err(_) => {
+                    log::error!("Tick hook panicked and has been removed");
+                    false
+                }
+            }
+        });
+    }
+}
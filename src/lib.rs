@@ -0,0 +1,35 @@
+pub mod price_collector;
+pub mod volatility;
+pub mod volatility_series;
+pub mod annualization;
+pub mod config;
+pub mod error;
+pub mod daily_summary;
+pub mod quality;
+pub mod server;
+pub mod health;
+pub mod baseline;
+pub mod persistence;
+pub mod envelope;
+pub mod hooks;
+pub mod validation;
+pub mod status;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod csv_export;
+pub mod ndjson_export;
+pub mod series_export;
+pub mod alerts;
+pub mod logging;
+pub mod format;
+pub mod broadcast;
+pub mod clock;
+pub mod replay;
+pub mod synthetic;
+pub mod manual;
+pub mod pipeline;
+pub mod registry;
+pub mod correlation;
+pub mod downsample;
+pub mod seasonality;
+pub mod proxy;
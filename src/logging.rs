@@ -0,0 +1,86 @@
+use std::io::Write;
+use std::str::FromStr;
+use chrono::{SecondsFormat, Utc};
+use log::kv::{Error as KvError, Key, Value, VisitSource};
+use serde_json::{Map, Value as Json};
+
+// How log records are written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    // env_logger's usual human-readable lines
+    #[default]
+    Text,
+    // One JSON object per line: timestamp, level, target, message and any
+    // key-value fields attached to the record
+    Json,
+}
+
+impl LogFormat {
+    // LOG_FORMAT, read directly since logging starts before the config loads
+    pub fn from_env() -> Self {
+        std::env::var("LOG_FORMAT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format: {}", other)),
+        }
+    }
+}
+
+// Install the global logger; RUST_LOG still controls the level
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+    match format {
+        LogFormat::Text => {
+            builder
+                .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
+                .format_target(true);
+        }
+        LogFormat::Json => {
+            builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+        }
+    }
+    builder.init();
+}
+
+pub fn json_line(record: &log::Record) -> Json {
+    let mut line = Map::new();
+    line.insert("timestamp".into(), Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true).into());
+    line.insert("level".into(), record.level().as_str().into());
+    line.insert("target".into(), record.target().into());
+    line.insert("message".into(), record.args().to_string().into());
+    // A field that fails to convert is dropped rather than losing the record
+    let _ = record.key_values().visit(&mut FieldCollector(&mut line));
+    Json::Object(line)
+}
+
+struct FieldCollector<'a>(&'a mut Map<String, Json>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), KvError> {
+        // Numbers and booleans stay typed; anything else is its Display form
+        let json = if let Some(v) = value.to_u64() {
+            v.into()
+        } else if let Some(v) = value.to_i64() {
+            v.into()
+        } else if let Some(v) = value.to_f64() {
+            serde_json::Number::from_f64(v).map_or(Json::Null, Json::Number)
+        } else if let Some(v) = value.to_bool() {
+            v.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.as_str().to_string(), json);
+        Ok(())
+    }
+}
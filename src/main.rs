@@ -1,65 +1,1353 @@
 use std::error::Error;
-use tokio;
-use log::{info, error};
+use std::time::Instant;
+use clap::Parser;
+use rand::Rng;
+use log::{info, warn, error};
+use tokio::signal::unix::{signal, SignalKind};
 
-mod price_collector;
-mod volatility;
-mod config;
+use eth_volatility::volatility::{term_structure, SourceCalculators, VolatilityCalculator};
+use eth_volatility::annualization::Annualizer;
+use eth_volatility::config::{Config, ConfigError, ConfigOverrides};
+use eth_volatility::error::VolatilityError;
+use eth_volatility::daily_summary::{DailySummarySink, DailySummaryTracker};
+use eth_volatility::volatility_series::{EmaFilter, VolatilitySeries};
+use eth_volatility::quality::QualityReport;
+use eth_volatility::status::StatusReport;
+use eth_volatility::baseline::VolatilityBaseline;
+use eth_volatility::persistence::HistoryStore;
+use eth_volatility::csv_export::CsvSink;
+use eth_volatility::series_export::VolatilitySeriesSink;
+use eth_volatility::ndjson_export::NdjsonSink;
+use eth_volatility::replay::ReplayCollector;
+use eth_volatility::synthetic::{CollectorKind, SyntheticCollector};
+use eth_volatility::pipeline::MultiSymbolPipeline;
+use eth_volatility::registry::CollectorRegistry;
+use eth_volatility::downsample::BarAggregator;
+use eth_volatility::broadcast::{self as price_broadcast, PriceBroadcast};
+use eth_volatility::alerts::{AlertSink, RegimeAlert, ThresholdMonitor, VolatilityRegimeMonitor};
+use eth_volatility::logging::{self, LogFormat};
+use eth_volatility::validation::{MaxJump, MedianDeviation, PriceRange, ReturnZScore};
+use eth_volatility::server::{self, AppState, PauseChange, PauseWatch, VolatilitySnapshot, VolatilityState};
+use eth_volatility::health::{self, HealthState};
+#[cfg(feature = "metrics")]
+use eth_volatility::metrics::{self, Metrics};
+use eth_volatility::price_collector::{BinanceCollector, BinanceKlineCollector, BoxedCollector, CollectorError, GeminiCollector, HtxCollector, OkxCollector, PriceAggregator, PricePoint};
+#[cfg(feature = "uniswap")]
+use eth_volatility::price_collector::UniswapCollector;
+#[cfg(feature = "coinbase")]
+use eth_volatility::price_collector::CoinbaseCollector;
+#[cfg(feature = "kraken")]
+use eth_volatility::price_collector::KrakenCollector;
 
-use crate::volatility::VolatilityCalculator;
-use crate::config::Config;
-use crate::price_collector::{BinanceCollector, PriceAggregator};
+// Wilder's original ATR lookback, in OHLC bars
+const ATR_PERIOD: usize = 14;
+
+// Command-line flags; anything not given falls back to the environment
+#[derive(Debug, Parser)]
+#[command(about = "Real-time ETH volatility estimator")]
+struct Args {
+    #[arg(long, help = "Binance WebSocket URL (overrides BINANCE_WS_URL)")]
+    binance_url: Option<String>,
+    #[arg(long, help = "Seconds between price fetches (overrides UPDATE_INTERVAL_SECONDS)")]
+    update_interval: Option<i64>,
+    #[arg(long, help = "Rolling window length in hours (overrides VOLATILITY_WINDOW_HOURS)")]
+    volatility_window_hours: Option<i64>,
+    #[arg(long, help = "Binance trading pair, e.g. btcusdt (overrides TRADING_SYMBOL)")]
+    symbol: Option<String>,
+    #[arg(long, help = "TOML config file; environment variables override its values (overrides CONFIG_FILE)")]
+    config: Option<std::path::PathBuf>,
+}
+
+impl From<Args> for ConfigOverrides {
+    fn from(args: Args) -> Self {
+        Self {
+            binance_ws_url: args.binance_url,
+            update_interval_seconds: args.update_interval,
+            volatility_window_hours: args.volatility_window_hours,
+            trading_symbol: args.symbol,
+            config_file: args.config,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // Initialize logging with timestamp
-    env_logger::Builder::from_default_env()
-        .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
-        .format_target(true)
-        .init();
+    let overrides = ConfigOverrides::from(Args::parse());
+
+    // Load .env first so it can set LOG_FORMAT and RUST_LOG
+    dotenv::dotenv().ok();
+
+    let log_format = LogFormat::from_env();
+    logging::init(log_format);
     
     info!("Starting ETH/USDC volatility estimator...");
 
     // Load configuration
-    dotenv::dotenv().ok();
-    let config = Config::new();
     
+    let mut config = match Config::from_args_and_env(&overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    if config.log_format != log_format {
+        warn!("LOG_FORMAT from the config file only takes effect when set in the environment or .env");
+    }
+    if config.binance_ws_url.starts_with("ws://") {
+        warn!("BINANCE_WS_URL uses plain ws://, so the stream is unencrypted; use wss:// in production");
+    }
+    // tungstenite 0.19 has no permessage-deflate and rejects compressed
+    // frames, so requesting the extension would break the stream
+    if config.ws_compression {
+        warn!("WS_COMPRESSION is set, but the WebSocket client can't negotiate permessage-deflate; connecting uncompressed");
+    }
+
+    if !config.trading_symbols.is_empty() {
+        return run_symbols(&config, &overrides).await;
+    }
+
     // Initialize collectors
-    info!("Initializing Binance price collector with URL: {}", config.binance_ws_url);
-    let binance = BinanceCollector::new(config.binance_ws_url.clone());
-    let aggregator = PriceAggregator::new(binance);
+    let mut aggregator = build_aggregator(&config)?;
     
     // Initialize volatility calculator with configured window
-    let mut calculator = VolatilityCalculator::new(config.volatility_window);
+    let mut calculator = build_calculator(&config);
+    
+    // Reload persisted history so a restart doesn't wait hours to refill the window
+    if let Some(path) = config.history_path.clone() {
+        calculator = calculator.with_history_store(HistoryStore::new(path, config.history_format))?;
+    }
+
+    // Seed whatever part of the window history didn't cover from REST klines
+    if config.backfill_on_start {
+        let (interval, limit) = backfill_interval(config.volatility_window);
+        let binance = BinanceCollector::new(config.binance_ws_url.clone(), &config.trading_symbol)
+            .with_rest_base(&config.binance_rest_base);
+        match binance.fetch_historical(&config.trading_symbol, interval, limit).await {
+            Ok(points) => {
+                let added = calculator.backfill(points);
+                info!("Backfilled {} {} closes for {}", added, interval, config.trading_symbol);
+            }
+            Err(e) => error!("Backfill failed, starting with an empty window: {}", e),
+        }
+    }
+
+    // Every fetched price is published here; consumers other than the
+    // calculator subscribe and run as their own tasks
+    let price_feed = PriceBroadcast::new(config.broadcast_capacity);
+
+    // Optional CSV log of every fetched price
+    if let Some(path) = &config.csv_output_path {
+        let mut sink = CsvSink::open(path)?;
+        tokio::spawn(price_broadcast::consume(price_feed.subscribe(), "CSV", move |price| {
+            if let Err(e) = sink.write(&price) {
+                error!("Failed to write price to CSV: {}", e);
+            }
+        }));
+    }
+
+    // Optional NDJSON stream of every fetched price
+    if let Some(target) = &config.ndjson_output {
+        let mut sink = NdjsonSink::open(target)?;
+        tokio::spawn(price_broadcast::consume(price_feed.subscribe(), "NDJSON", move |price| {
+            if let Err(e) = sink.write(&price) {
+                error!("Failed to write price as NDJSON: {}", e);
+            }
+        }));
+    }
+
+    // Optional log of every volatility estimate, written from the main loop
+    let mut series_sink = config.vol_series_output.as_deref()
+        .map(VolatilitySeriesSink::open)
+        .transpose()?;
+
+    // Optional historical baseline for contextualizing each reading
+    let mut baseline = match &config.baseline {
+        Some(source) => {
+            let baseline = VolatilityBaseline::load(source)?;
+            info!("Loaded volatility baseline with mean {}", config.number_format.pct(baseline.mean()));
+            Some(baseline)
+        }
+        None => None,
+    };
+
+    // Recent volatility readings, used for the trend indicator
+    let mut volatility_series = VolatilitySeries::new(config.volatility_series_len);
+    // Optional volatility for each venue on its own, next to the blend
+    let mut per_source = config.per_source_volatility.then(|| source_calculators(&config));
+    // Optional EMA over the logged estimates
+    let mut smoothing = config.vol_smoothing_alpha.map(EmaFilter::new);
+    if let Some(interval) = config.series_interval {
+        volatility_series = volatility_series.with_interval(interval);
+    }
+
+    // End-of-day volatility rollup
+    let mut daily_tracker = DailySummaryTracker::new();
+    // Largest in-window gap already warned about, so each is logged once
+    let mut warned_gap: Option<chrono::Duration> = None;
+
+    // Alerts fire only when volatility crosses into or out of the configured band
+    let mut threshold_monitor = build_threshold_monitor(&config);
+    let mut regime_monitor = build_regime_monitor(&config);
+    let mut alert_sink = AlertSink::new(config.alert_webhook_url.clone());
+
+    let mut daily_sink = DailySummarySink::new(
+        config.daily_summary_path.clone(),
+        config.daily_summary_webhook_url.clone(),
+    );
+
+    // Optional HTTP server exposing data-quality signals
+    let app_state = AppState::default().with_control_token(config.http_control_token.clone());
+    if let Some(addr) = config.http_addr {
+        let state = app_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(addr, state).await {
+                error!("HTTP server failed: {}", e);
+            }
+        });
+    }
+
+    // Or bars built here from the fetched prices themselves, so the range
+    // estimators see exactly the ticks the close-to-close one does
+    let mut tick_bars = config.bar_interval.map(|interval| {
+        info!("Building {}s OHLC bars from fetched prices", interval.num_seconds());
+        BarAggregator::new(interval)
+    });
+
+    // Optional OHLC bars from the kline stream, read on their own task since
+    // a bar only arrives once per interval
+    let (ohlc_tx, mut ohlc_rx) = tokio::sync::mpsc::unbounded_channel();
+    if let Some(interval) = &config.ohlc_interval {
+        info!("Collecting {} OHLC bars for {}", interval, config.trading_symbol);
+        let klines = BinanceKlineCollector::new(config.binance_ws_url.clone(), &config.trading_symbol, interval)
+            .with_reconnect_log_every(config.reconnect_log_every);
+        let klines = match config.ws_proxy.clone() {
+            Some(proxy) => klines.with_proxy(proxy),
+            None => klines,
+        };
+        tokio::spawn(async move {
+            loop {
+                let error = match klines.next_bar().await {
+                    Ok(bar) => {
+                        if ohlc_tx.send(bar).is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(e) => e.to_string(),
+                };
+                error!("Error fetching OHLC bar: {}", error);
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    // Optional liveness/readiness probes for container orchestration
+    let health_state = HealthState::new(config.ready_max_staleness);
+    if let Some(addr) = config.health_addr {
+        let state = health_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(addr, state).await {
+                error!("Health server failed: {}", e);
+            }
+        });
+    }
+
+    // Optional Prometheus endpoint, refreshed every iteration
+    #[cfg(feature = "metrics")]
+    let metrics = match config.metrics_addr {
+        Some(addr) => {
+            let metrics = std::sync::Arc::new(Metrics::new()?);
+            let served = metrics.clone();
+            tokio::spawn(async move {
+                if let Err(e) = metrics::serve(addr, served).await {
+                    error!("Metrics server failed: {}", e);
+                }
+            });
+            Some(metrics)
+        }
+        None => None,
+    };
+
+    // SIGHUP triggers a warm reload of the configuration
+    let mut sighup = signal(SignalKind::hangup())?;
+    // Held for the whole loop so a Ctrl-C during a fetch or while processing
+    // a tick isn't lost between one wait and the next
+    let mut sigint = signal(SignalKind::interrupt())?;
     
     info!("Starting main loop with {} second intervals...", 
           config.update_interval.num_seconds());
 
+    // Only used in batch mode to detect a feed that has gone silent
+    let mut last_price_at = Instant::now();
+    let mut error_budget = ErrorBudget::new(config.max_consecutive_errors);
+    let mut gave_up = false;
+    let mut pause_watch = PauseWatch::new(app_state.paused.clone());
+    let mut warmed_up = false;
+
     // Main program loop
     loop {
-        info!("Fetching latest price...");
-        match aggregator.get_aggregated_price().await {
-            Ok(price) => {
-                info!("Received price: ${:.2} from {} at {}", 
-                    price.price, 
+        // Re-read each tick so a reload picks up new precision
+        let fmt = config.number_format;
+        let inactivity_timeout = config.inactivity_timeout
+            .filter(|_| config.batch_mode)
+            .and_then(|t| t.to_std().ok());
+        if let Some(timeout) = inactivity_timeout {
+            if last_price_at.elapsed() >= timeout {
+                warn!("No new price for {} seconds, ending batch run", timeout.as_secs());
+                break;
+            }
+        }
+
+        while let Ok(bar) = ohlc_rx.try_recv() {
+            calculator.add_ohlc(bar);
+        }
+
+        let (paused, change) = pause_watch.poll();
+        match change {
+            Some(PauseChange::Paused) => {
+                info!("Price collection paused, closing connections");
+                aggregator.close().await;
+            }
+            Some(PauseChange::Resumed) => {
+                // Collectors reconnect on their next fetch
+                info!("Price collection resumed");
+                calculator.mark_gap();
+            }
+            None => {}
+        }
+        let fetched = if paused {
+            // The window keeps its history but still ages out by time
+            calculator.evict_expired();
+            last_price_at = Instant::now();
+            None
+        } else {
+            info!("Fetching latest price...");
+            tokio::select! {
+                fetched = fetch_price(&aggregator, config.fetch_timeout) => Some(fetched),
+                _ = sigint.recv() => {
+                    info!("Received Ctrl-C, shutting down...");
+                    break;
+                }
+                // A fetch that hangs past the inactivity timeout ends the batch
+                // run too, rather than waiting for the next tick to notice
+                timeout = async {
+                    match inactivity_timeout {
+                        Some(timeout) => {
+                            tokio::time::sleep(timeout.saturating_sub(last_price_at.elapsed())).await;
+                            timeout
+                        }
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    warn!("No new price for {} seconds, ending batch run", timeout.as_secs());
+                    break;
+                }
+            }
+        };
+        match fetched {
+            None => {}
+            Some(Ok(price)) => {
+                last_price_at = Instant::now();
+                error_budget.success();
+                health_state.record_success(chrono::Utc::now());
+                info!(price = price.price, source = price.source.as_str(); "Received price: {} from {} at {}", 
+                    fmt.price(price.price), 
                     price.source,
                     price.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
                 );
+                price_feed.publish(&price);
+                if let Some(bar) = tick_bars.as_mut().and_then(|bars| bars.push(&price)) {
+                    calculator.add_ohlc(bar);
+                }
+                let (timestamp, last_price) = (price.timestamp, price.price);
                 calculator.add_price(price);
-                if let Some(volatility) = calculator.calculate_volatility() {
-                    info!("Current annualized volatility estimate: {:.2}%", volatility * 100.0);
+                if let Some(gap) = calculator.largest_gap().filter(|gap| *gap > config.gap_threshold) {
+                    if warned_gap != Some(gap) {
+                        warn!(
+                            "Window contains a {}s gap between prices ({:.1}x the update interval); volatility may be distorted",
+                            gap.num_seconds(),
+                            gap.num_milliseconds() as f64 / config.update_interval.num_milliseconds() as f64
+                        );
+                        warned_gap = Some(gap);
+                    }
+                }
+                let (mut result, failure) = match calculator.calculate_result(config.report_variance) {
+                    Ok(result) => (result, None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                if let (Some(message), true) = (&failure, config.json_output) {
+                    println!("{}", serde_json::json!({ "timestamp": timestamp, "error": message }));
+                }
+                if let Some(result) = result.as_mut() {
+                    result.gap = calculator.take_gap();
+                    if let Some(baseline) = &baseline {
+                        let comparison = baseline.compare(result.annualized_volatility);
+                        result.baseline_ratio = Some(comparison.ratio);
+                        result.baseline_percentile = comparison.percentile;
+                    }
+                    if config.json_output {
+                        match serde_json::to_string(result) {
+                            Ok(line) => println!("{}", line),
+                            Err(e) => error!("Failed to serialize volatility result: {}", e),
+                        }
+                    }
+                    if let Some(sink) = series_sink.as_mut() {
+                        if let Err(e) = sink.write(result) {
+                            error!("Failed to write volatility series: {}", e);
+                        }
+                    }
+                }
+                let volatility = result.as_ref().map(|r| r.annualized_volatility);
+                *app_state.volatility.write().unwrap_or_else(|e| e.into_inner()) = match (&result, &failure) {
+                    (Some(result), _) => VolatilityState::Ready(VolatilitySnapshot {
+                        annualized_volatility: result.annualized_volatility,
+                        window_seconds: calculator.window_size().num_seconds(),
+                        sample_count: result.sample_count,
+                        last_updated: result.timestamp,
+                    }),
+                    (None, Some(message)) => VolatilityState::NotReady(message.clone()),
+                    (None, None) => VolatilityState::NotReady(format!(
+                        "not enough data: {} more returns needed ({} points in window)",
+                        calculator.samples_needed(),
+                        calculator.sample_count()
+                    )),
+                };
+                if let Some(message) = &failure {
+                    error!("Volatility estimate unavailable: {}", message);
+                } else if let Some(result) = &result {
+                    let volatility = result.annualized_volatility;
+                    match smoothing.as_mut() {
+                        Some(filter) => {
+                            let smoothed = filter.update(volatility);
+                            info!(volatility = smoothed, raw_volatility = volatility, samples = result.sample_count; "Current annualized volatility estimate: {} (smoothed, raw {})", fmt.pct(smoothed), fmt.pct(volatility));
+                        }
+                        None => info!(volatility = volatility, samples = result.sample_count; "Current annualized volatility estimate: {}", fmt.pct(volatility)),
+                    }
+                    if let Some(ratio) = result.baseline_ratio {
+                        match result.baseline_percentile {
+                            Some(percentile) => info!("Volatility vs baseline: {:.2}x ({:.0}th percentile)", ratio, percentile),
+                            None => info!("Volatility vs baseline: {:.2}x", ratio),
+                        }
+                    }
+                    if let (Some(annual), Some(daily), Some(hourly)) = (calculator.as_annualized(), calculator.as_daily(), calculator.as_hourly()) {
+                        info!(daily_volatility = daily, hourly_volatility = hourly; "Window volatility: {} annualized, {} daily, {} hourly",
+                            fmt.pct(annual), fmt.pct(daily), fmt.pct(hourly));
+                    }
+                    if let Some(drift) = calculator.estimated_drift() {
+                        info!(drift = drift; "Annualized drift: {}", fmt.signed_pct(drift));
+                    }
+                    if let Some(adjusted) = calculator.seasonally_adjusted_volatility() {
+                        info!(seasonal_volatility = adjusted; "Seasonally adjusted volatility: {}", fmt.pct(adjusted));
+                    }
+                    if let Some(variance) = result.annualized_variance {
+                        info!("Current annualized realized variance: {:.6}", variance);
+                    }
+                    if let Some(level) = config.confidence_level {
+                        if let Some((_, lower, upper)) = calculator.volatility_with_ci(level) {
+                            info!("{:.0}% confidence interval: {} - {}", level * 100.0, fmt.pct(lower), fmt.pct(upper));
+                        }
+                    }
+                    if let Some(change) = calculator.window_return() {
+                        info!(window_return = change; "Price change over window: {}", fmt.signed_pct(change));
+                    }
+                    if let Some(ratio) = calculator.return_to_vol_ratio() {
+                        info!(return_to_vol = ratio; "Annualized return to volatility: {:+.2}", ratio);
+                    }
+                    if let Some(drawdown) = calculator.max_drawdown() {
+                        info!(max_drawdown = drawdown; "Max drawdown over window: {}", fmt.pct(drawdown));
+                    }
+                    if let Some((low, high)) = calculator.price_range() {
+                        info!(price_low = low, price_high = high; "Window price range: {} - {}", fmt.price(low), fmt.price(high));
+                    }
+                    if let Some((mean, upper, lower)) = config.bollinger_k.and_then(|k| calculator.bollinger_bands(k)) {
+                        info!(band_mean = mean, band_upper = upper, band_lower = lower; "Price bands: {} / {} / {}", fmt.price(lower), fmt.price(mean), fmt.price(upper));
+                    }
+                    if let Some(vwap) = calculator.vwap() {
+                        info!(vwap = vwap; "Window VWAP: {}", fmt.price(vwap));
+                    }
+                    if let Some(ewma) = config.ewma_lambda.and_then(|lambda| calculator.calculate_ewma_volatility(lambda)) {
+                        info!(ewma_volatility = ewma; "EWMA annualized volatility: {}", fmt.pct(ewma));
+                    }
+                    if let Some(mad) = calculator.calculate_mad_volatility() {
+                        info!(mad_volatility = mad; "MAD annualized volatility: {}", fmt.pct(mad));
+                    }
+                    if let Some(parkinson) = calculator.calculate_parkinson_volatility() {
+                        info!(parkinson_volatility = parkinson, bars = calculator.ohlc_count(); "Parkinson annualized volatility: {} over {} bars", fmt.pct(parkinson), calculator.ohlc_count());
+                    }
+                    if let Some(garman_klass) = calculator.calculate_garman_klass_volatility() {
+                        info!(garman_klass_volatility = garman_klass; "Garman-Klass annualized volatility: {}", fmt.pct(garman_klass));
+                    }
+                    if let Some(atr) = calculator.calculate_atr(ATR_PERIOD) {
+                        info!(atr = atr; "ATR({}): {}", ATR_PERIOD, fmt.price(atr));
+                    }
+                    volatility_series.record(timestamp, volatility);
+                    if let Some(trend) = volatility_series.trend(config.volatility_trend_flat_threshold) {
+                        info!("Volatility trend: {} ({:+.4}%/hour over {} readings)",
+                            trend.direction,
+                            trend.slope_per_hour * 100.0,
+                            volatility_series.len()
+                        );
+                    }
+                    if let Some(percentile) = volatility_series.current_percentile() {
+                        info!(volatility_percentile = percentile; "Volatility at the {:.0}th percentile of the last {} readings",
+                            percentile,
+                            volatility_series.len()
+                        );
+                    }
+                } else if calculator.samples_needed() > 0 {
+                    let needed = calculator.min_samples();
+                    info!(
+                        "Warming up: collected {}/{} samples, {:.0}% to first estimate",
+                        needed - calculator.samples_needed(),
+                        needed,
+                        calculator.warmup_progress() * 100.0
+                    );
+                } else if calculator.flat_window_suppressed() {
+                    warn!("Only one distinct price across {} points in the window; estimate suppressed by FLAT_WINDOW_POLICY", calculator.sample_count());
                 } else {
-                    info!("Not enough data points for volatility calculation yet");
+                    // A non-finite result is logged by the calculator itself
+                    warn!("No volatility estimate from a full window of {} points", calculator.sample_count());
+                }
+                // Logged again if a gap or a reload drains the window below min_samples
+                match (calculator.samples_needed() == 0, warmed_up) {
+                    (true, false) => info!("Warmup complete after {} samples", calculator.min_samples()),
+                    (false, true) => warn!("Window fell below {} samples, warming up again", calculator.min_samples()),
+                    _ => {}
+                }
+                warmed_up = calculator.samples_needed() == 0;
+                if let Some(zscore) = calculator.latest_return_zscore() {
+                    match config.zscore_alert_threshold {
+                        Some(threshold) if zscore.abs() >= threshold => {
+                            warn!("Unusual move: latest return z-score {:+.2} exceeds {:.2}", zscore, threshold);
+                            #[cfg(feature = "metrics")]
+                            if let Some(metrics) = &metrics {
+                                metrics.return_anomalies_total.inc();
+                            }
+                        }
+                        _ => info!("Latest return z-score: {:+.2}", zscore),
+                    }
+                }
+                if let Some(alert) = volatility.and_then(|v| threshold_monitor.update(timestamp, v)) {
+                    warn!(volatility = alert.volatility; "Volatility alert: {} moved from {:?} to {:?} range", fmt.pct(alert.volatility), alert.from, alert.to);
+                    if let Err(e) = alert_sink.emit(&alert).await {
+                        error!("Failed to send volatility alert: {}", e);
+                    }
+                }
+                if let Some(alert) = regime_monitor.as_mut().and_then(|m| m.check(timestamp, &calculator)) {
+                    report_regime_alert(&alert, &config, &alert_sink).await;
+                }
+                if let Some(summary) = daily_tracker.record(timestamp, last_price, volatility) {
+                    info!("Daily summary for {}: avg {}, min {}, max {}, close {}",
+                        summary.date,
+                        fmt.pct(summary.average_volatility),
+                        fmt.pct(summary.min_volatility),
+                        fmt.pct(summary.max_volatility),
+                        fmt.price(summary.close_price)
+                    );
+                    if daily_sink.is_enabled() {
+                        if let Err(e) = daily_sink.emit(&summary).await {
+                            error!("Failed to emit daily summary: {}", e);
+                        }
+                    }
+                }
+                if !config.volatility_windows.is_empty() {
+                    let report: Vec<String> = calculator.calculate_windows(&config.volatility_windows)
+                        .into_iter()
+                        .map(|(window, vol)| match vol {
+                            Some(v) => format!("{}h: {}", window.num_hours(), fmt.pct(v)),
+                            None => format!("{}h: n/a", window.num_hours()),
+                        })
+                        .collect();
+                    info!("Volatility by window: {}", report.join(", "));
+                }
+                if let Some(preset) = config.volatility_preset {
+                    let windows = calculator.calculate_preset(preset);
+                    let report: Vec<String> = windows.iter()
+                        .copied()
+                        .map(|(window, vol)| match vol {
+                            Some(v) => format!("{}d: {}", window.num_days(), fmt.pct(v)),
+                            None => format!("{}d: n/a", window.num_days()),
+                        })
+                        .collect();
+                    info!("Realized volatility ({:?} preset): {}", preset, report.join(", "));
+
+                    let points: Vec<_> = windows.iter()
+                        .filter_map(|(window, vol)| vol.map(|v| (*window, v)))
+                        .collect();
+                    if let Some(term) = term_structure(&points, config.term_structure_flat_threshold) {
+                        info!("Volatility term structure: {} ({:+.4}% per day of tenor)",
+                            term.shape, term.slope_per_day * 100.0);
+                    }
                 }
             }
-            Err(e) => {
+            Some(Err(VolatilityError::ReplayFinished(_))) => {
+                info!("Replay finished");
+                break;
+            }
+            Some(Err(e)) => {
                 error!("Error fetching price: {}", e);
+                let errors = aggregator.consecutive_errors("Binance");
+                if errors > 1 {
+                    warn!("{} consecutive Binance errors, {} reconnects so far", errors, aggregator.reconnects("Binance"));
+                }
+                #[cfg(feature = "metrics")]
+                if let Some(metrics) = &metrics {
+                    metrics.price_fetch_errors_total.inc();
+                }
+                // Collectors reconnect after an error, so the stream has a hole
+                calculator.mark_gap();
+                if error_budget.failure() {
+                    error!("Giving up after {} consecutive failed fetches", error_budget.consecutive());
+                    gave_up = true;
+                    break;
+                }
+            }
+        }
+
+        if let Some(per_source) = per_source.as_mut() {
+            for price in aggregator.source_prices() {
+                per_source.add_price(price);
+            }
+            let report: Vec<String> = per_source.calculate_all()
+                .into_iter()
+                .map(|(source, vol)| match vol.filter(|v| v.is_finite()) {
+                    Some(v) => format!("{}: {}", source, fmt.pct(v)),
+                    None => format!("{}: n/a", source),
+                })
+                .collect();
+            if !report.is_empty() {
+                info!("Per-source annualized volatility: {}", report.join(", "));
             }
         }
         
-        info!("Waiting for next update...");
-        tokio::time::sleep(tokio::time::Duration::from_secs(
-            config.update_interval.num_seconds() as u64
-        )).await;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &metrics {
+            if let Some(volatility) = calculator.calculate_volatility().filter(|v| v.is_finite()) {
+                metrics.current_volatility.set(volatility);
+            }
+            if let Some(price) = calculator.prices().last() {
+                metrics.last_price.set(price.price);
+            }
+            metrics.price_history_len.set(calculator.sample_count() as i64);
+            Metrics::sync_counter(&metrics.binance_reconnects_total, aggregator.reconnects("Binance"));
+            metrics.binance_consecutive_errors.set(aggregator.consecutive_errors("Binance") as i64);
+        }
+
+        *app_state.quality.write().unwrap_or_else(|e| e.into_inner()) = QualityReport::build(
+            aggregator.source_stats(),
+            &calculator,
+            config.volatility_window,
+            config.update_interval,
+            config.sample_interval.unwrap_or(config.update_interval),
+        );
+
+        let status = StatusReport::build(&calculator);
+        if !status.ready {
+            let filled = status.covered_seconds as f64 / status.window_seconds.max(1) as f64 * 100.0;
+            match status.seconds_until_ready {
+                Some(secs) => info!("Window {:.0}% warmed, estimated {}m {}s until ready", filled, secs / 60, secs % 60),
+                None => info!("Window {:.0}% warmed, waiting for samples to estimate time until ready", filled),
+            }
+        }
+        *app_state.status.write().unwrap_or_else(|e| e.into_inner()) = status;
+        *app_state.snapshot.write().unwrap_or_else(|e| e.into_inner()) = calculator.snapshot();
+        health_state.set_samples_needed(calculator.samples_needed());
+
+        // Replays are paced by the collector itself
+        let pause = if config.replay_path.is_some() {
+            tokio::time::Duration::ZERO
+        } else {
+            info!("Waiting for next update...");
+            let draw = rand::thread_rng().gen_range(-1.0..=1.0);
+            jittered_interval(config.update_interval.to_std()?, config.update_jitter_pct, draw)
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(pause) => {}
+            _ = sigint.recv() => {
+                info!("Received Ctrl-C, shutting down...");
+                break;
+            }
+            _ = sighup.recv() => {
+                info!("Received SIGHUP, reloading configuration...");
+                let new_config = match Config::reload(&overrides) {
+                    Ok(new_config) => new_config,
+                    Err(e) => {
+                        error!("Keeping previous configuration, reloaded one is invalid: {}", e);
+                        continue;
+                    }
+                };
+
+                let ignored = new_config.restart_required(&config);
+                if !ignored.is_empty() {
+                    warn!("Ignoring changes to {} until restart", ignored.join(", "));
+                }
+                // Only a multi-symbol process reconciles its symbol list
+                if !new_config.trading_symbols.is_empty() {
+                    warn!("Ignoring TRADING_SYMBOLS until restart; switching to multi-symbol mode needs one");
+                }
+
+                // Only reconnect when the set of sources actually changed
+                if new_config.sources_differ(&config) {
+                    warn!("Price sources changed; reconnecting. Newly added sources start with a gap in their history");
+                    match build_aggregator(&new_config) {
+                        Ok(new_aggregator) => aggregator = new_aggregator,
+                        Err(e) => error!("Keeping previous price sources, new configuration is invalid: {}", e),
+                    }
+                } else {
+                    aggregator = aggregator
+                        .with_strategy(new_config.aggregation_strategy)
+                        .with_timestamp_mode(new_config.aggregation_timestamp)
+                        .with_min_sources(new_config.min_sources)
+                        .with_max_divergence(max_divergence(&new_config), new_config.source_divergence_action)
+                        .with_circuit_breaker(new_config.circuit_breaker)
+                        .with_liquidity_tiers(new_config.liquidity_tiers.clone())
+                        .with_time_offsets(new_config.time_offsets.clone());
+                    if let Err(e) = aggregator.validate() {
+                        error!("Invalid liquidity tiers after reload, using equal weights: {}", e);
+                        aggregator = aggregator.with_liquidity_tiers(Default::default());
+                    }
+                }
+
+                // Another pair's prices can't continue this window: the first
+                // return would run from the old pair's last price to the new
+                // pair's first. Everything built from the old prices goes too.
+                if new_config.trading_symbol != config.trading_symbol {
+                    warn!("Trading symbol changed from {} to {}, starting a fresh window", config.trading_symbol, new_config.trading_symbol);
+                    calculator.flush_history();
+                    if config.history_path.is_some() {
+                        warn!("History for {} is not saved until restart", new_config.trading_symbol);
+                    }
+                    calculator = build_calculator(&new_config);
+                    calculator.mark_gap();
+                    per_source = None;
+                    volatility_series = VolatilitySeries::new(config.volatility_series_len);
+                    if let Some(interval) = config.series_interval {
+                        volatility_series = volatility_series.with_interval(interval);
+                    }
+                    smoothing = new_config.vol_smoothing_alpha.map(EmaFilter::new);
+                    tick_bars = config.bar_interval.map(BarAggregator::new);
+                    warned_gap = None;
+                }
+
+                // Otherwise existing price history is kept; a shorter window just evicts more
+                calculator.set_window_size(new_config.volatility_window);
+                calculator.set_flat_window_policy(new_config.flat_window_policy);
+                calculator.set_return_type(new_config.return_type);
+                calculator.set_interval_estimate(new_config.interval_estimate);
+                calculator.set_demean(new_config.demean_returns);
+                calculator.set_min_samples(new_config.min_samples);
+                calculator.set_sample_interval(new_config.sample_interval);
+                if !new_config.per_source_volatility {
+                    per_source = None;
+                } else if per_source.is_none()
+                    || new_config.volatility_window != config.volatility_window
+                    || new_config.min_samples != config.min_samples
+                    || new_config.return_type != config.return_type
+                    || new_config.interval_estimate != config.interval_estimate
+                    || new_config.demean_returns != config.demean_returns
+                {
+                    per_source = Some(source_calculators(&new_config));
+                }
+                if smoothing.map(|filter| filter.alpha()) != new_config.vol_smoothing_alpha {
+                    smoothing = new_config.vol_smoothing_alpha.map(EmaFilter::new);
+                }
+                calculator.set_non_finite_policy(new_config.non_finite_policy);
+                calculator.set_annualizer(new_config.annualizer());
+                if new_config.baseline != config.baseline {
+                    baseline = match &new_config.baseline {
+                        Some(source) => match VolatilityBaseline::load(source) {
+                            Ok(loaded) => Some(loaded),
+                            Err(e) => {
+                                error!("Failed to reload volatility baseline: {}", e);
+                                baseline
+                            }
+                        },
+                        None => None,
+                    };
+                }
+                if (new_config.vol_alert_high, new_config.vol_alert_low, new_config.vol_alert_hysteresis_pct)
+                    != (config.vol_alert_high, config.vol_alert_low, config.vol_alert_hysteresis_pct)
+                {
+                    threshold_monitor = build_threshold_monitor(&new_config);
+                }
+                if (new_config.regime_alert_ratio, new_config.regime_short_window, new_config.regime_long_window)
+                    != (config.regime_alert_ratio, config.regime_short_window, config.regime_long_window)
+                {
+                    regime_monitor = build_regime_monitor(&new_config);
+                }
+                error_budget.set_max(new_config.max_consecutive_errors);
+                alert_sink = AlertSink::new(new_config.alert_webhook_url.clone());
+                daily_sink = DailySummarySink::new(
+                    new_config.daily_summary_path.clone(),
+                    new_config.daily_summary_webhook_url.clone(),
+                );
+                config = new_config;
+                info!("Configuration reloaded, {} second intervals", config.update_interval.num_seconds());
+            }
+        }
+    }
+
+    match calculator.calculate_volatility().filter(|v| v.is_finite()) {
+        Some(volatility) => info!(volatility = volatility; "Final annualized volatility estimate: {}", config.number_format.pct(volatility)),
+        None => info!("Not enough data points for a final volatility estimate"),
+    }
+    aggregator.close().await;
+    calculator.flush_history();
+
+    if gave_up {
+        return Err(format!("{} consecutive price fetch failures", error_budget.consecutive()).into());
+    }
+    Ok(())
+}
+
+// Consecutive failed fetches allowed before the main loop gives up, so a
+// supervisor can restart the process; no limit when `max` is None
+struct ErrorBudget {
+    max: Option<u32>,
+    consecutive: u32,
+}
+
+impl ErrorBudget {
+    fn new(max: Option<u32>) -> Self {
+        Self { max, consecutive: 0 }
+    }
+
+    fn set_max(&mut self, max: Option<u32>) {
+        self.max = max;
+    }
+
+    fn success(&mut self) {
+        self.consecutive = 0;
+    }
+
+    // Counts a failure; true once the count reaches the limit
+    fn failure(&mut self) -> bool {
+        self.consecutive += 1;
+        self.max.is_some_and(|max| self.consecutive >= max)
+    }
+
+    fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+}
+
+// Finest kline interval whose 1000-bar limit still spans the window
+fn backfill_interval(window: chrono::Duration) -> (&'static str, u32) {
+    const INTERVALS: &[(&str, i64)] = &[
+        ("1m", 60), ("3m", 180), ("5m", 300), ("15m", 900), ("30m", 1800),
+        ("1h", 3600), ("2h", 7200), ("4h", 14400), ("6h", 21600), ("8h", 28800),
+        ("12h", 43200), ("1d", 86400),
+    ];
+    let window_seconds = window.num_seconds().max(1);
+    let (name, seconds) = INTERVALS.iter()
+        .find(|(_, seconds)| seconds * 1000 >= window_seconds)
+        .unwrap_or(&INTERVALS[INTERVALS.len() - 1]);
+    let bars = (window_seconds + seconds - 1) / seconds;
+    (name, bars.clamp(1, 1000) as u32)
+}
+
+// Points a full window holds at the configured sampling rate, bounded by MAX_POINTS
+fn expected_points(config: &Config) -> usize {
+    let interval = config.sample_interval.unwrap_or(config.update_interval).num_milliseconds().max(1);
+    let points = (config.volatility_window.num_milliseconds() / interval) as usize + 1;
+    config.max_points.map_or(points, |max| points.min(max))
+}
+
+fn build_threshold_monitor(config: &Config) -> ThresholdMonitor {
+    ThresholdMonitor::new(config.vol_alert_high, config.vol_alert_low)
+        .with_hysteresis_pct(config.vol_alert_hysteresis_pct)
+}
+
+fn build_regime_monitor(config: &Config) -> Option<VolatilityRegimeMonitor> {
+    config.regime_alert_ratio
+        .map(|ratio| VolatilityRegimeMonitor::new(config.regime_short_window, config.regime_long_window, ratio))
+}
+
+// Log a regime change and forward it to the alert webhook
+async fn report_regime_alert(alert: &RegimeAlert, config: &Config, alert_sink: &AlertSink) {
+    let fmt = config.number_format;
+    let (short, long) = (config.regime_short_window.num_minutes(), config.regime_long_window.num_hours());
+    if alert.elevated {
+        warn!(regime_ratio = alert.ratio; "Volatility regime shift: {}m volatility {} is {:.2}x the {}h {}",
+            short, fmt.pct(alert.short_volatility), alert.ratio, long, fmt.pct(alert.long_volatility));
+    } else {
+        info!(regime_ratio = alert.ratio; "Volatility regime back to normal: {}m volatility {} is {:.2}x the {}h {}",
+            short, fmt.pct(alert.short_volatility), alert.ratio, long, fmt.pct(alert.long_volatility));
+    }
+    if let Err(e) = alert_sink.emit(alert).await {
+        error!("Failed to send regime alert: {}", e);
+    }
+}
+
+// A fetch still pending after `timeout` is abandoned and reported as a
+// timed-out error, so one stuck collector can't stall the loop
+async fn fetch_price(aggregator: &PriceAggregator, timeout: Option<std::time::Duration>) -> Result<PricePoint, VolatilityError> {
+    let Some(timeout) = timeout else {
+        return aggregator.get_aggregated_price().await;
+    };
+    match tokio::time::timeout(timeout, aggregator.get_aggregated_price()).await {
+        Ok(result) => result,
+        Err(_) => Err(CollectorError::Timeout(format!("no price within {}s, fetch abandoned", timeout.as_secs())).into()),
+    }
+}
+
+// The update interval moved by `draw` (in [-1, 1]) times `jitter_pct`
+// percent of itself, so instances started together drift apart. Never
+// shorter than a millisecond, whatever the inputs.
+fn jittered_interval(interval: std::time::Duration, jitter_pct: f64, draw: f64) -> std::time::Duration {
+    let factor = 1.0 + draw.clamp(-1.0, 1.0) * jitter_pct / 100.0;
+    interval.mul_f64(factor.max(0.0)).max(std::time::Duration::from_millis(1))
+}
+
+// The main window with every configured setting and validator, but no
+// history store or backfill; those are only attached at startup
+fn build_calculator(config: &Config) -> VolatilityCalculator {
+    let mut calculator = VolatilityCalculator::with_capacity(config.volatility_window, expected_points(config))
+        .with_flat_window_policy(config.flat_window_policy)
+        .with_non_finite_policy(config.non_finite_policy)
+        .with_annualizer(config.annualizer())
+        .with_gap_threshold(config.gap_threshold)
+        .with_spread_adjustment(config.spread_adjustment)
+        .with_return_type(config.return_type)
+        .with_interval_estimate(config.interval_estimate)
+        .with_demean(config.demean_returns)
+        .with_min_samples(config.min_samples)
+        .with_eviction_reference(config.eviction_reference)
+        .with_excluded_weekdays(config.excluded_weekdays.clone());
+    if config.price_min.is_some() || config.price_max.is_some() {
+        calculator = calculator.with_validator(Box::new(PriceRange {
+            min: config.price_min,
+            max: config.price_max,
+        }));
+    }
+    if let Some(pct) = config.max_price_jump_pct {
+        calculator = calculator.with_validator(Box::new(MaxJump::new(pct / 100.0)));
+    }
+    if let Some(pct) = config.outlier_threshold_pct {
+        calculator = calculator.with_validator(Box::new(MedianDeviation { max_fraction: pct / 100.0 }));
+    }
+    if let Some(threshold) = config.zscore_alert_threshold.filter(|_| config.zscore_reject) {
+        calculator = calculator.with_validator(Box::new(ReturnZScore::new(threshold)));
+    }
+    if let Some(max_points) = config.max_points {
+        calculator = calculator.with_max_points(max_points);
+    }
+    if let Some(interval) = config.sample_interval {
+        calculator = calculator.with_sample_interval(interval);
+    }
+    // Before the history reload, so the profile learns from restored points too
+    if config.seasonality_adjustment {
+        calculator = calculator.with_seasonality();
+    }
+    calculator
+}
+
+// Builds calculators with the main calculator's core settings, for the
+// per-source and per-symbol windows
+fn calculator_factory(config: &Config) -> impl Fn() -> VolatilityCalculator + Send + Sync + 'static {
+    let (window, min_samples, return_type) = (config.volatility_window, config.min_samples, config.return_type);
+    let (interval_estimate, demean) = (config.interval_estimate, config.demean_returns);
+    let (mode, convention) = (config.annualization_mode, config.annualization);
+    move || {
+        let annualizer = match mode {
+            Some(mode) => Box::new(mode) as Box<dyn Annualizer>,
+            None => convention.annualizer(),
+        };
+        VolatilityCalculator::new(window)
+            .with_annualizer(annualizer)
+            .with_return_type(return_type)
+            .with_interval_estimate(interval_estimate)
+            .with_demean(demean)
+            .with_min_samples(min_samples)
+    }
+}
+
+fn source_calculators(config: &Config) -> SourceCalculators {
+    SourceCalculators::new(calculator_factory(config))
+}
+
+// TRADING_SYMBOLS mode: one Binance collector and window per symbol, each on
+// its own task, with this function supervising until Ctrl-C. SIGHUP
+// reconciles the symbol list: removed symbols' tasks stop, added ones start
+// with a gap, and unchanged ones keep running with their history.
+async fn run_symbols(config: &Config, overrides: &ConfigOverrides) -> Result<(), Box<dyn Error>> {
+    let url = config.binance_ws_url.clone();
+    let (log_every, base, max) = (config.reconnect_log_every, config.reconnect_base, config.reconnect_max);
+    let (min_interval, ping, warmup, stream) =
+        (config.min_reconnect_interval, config.ping_interval, config.warmup_ticks, config.binance_stream);
+    let (stale_timeout, proxy, max_skew) = (config.stale_timeout, config.ws_proxy.clone(), config.max_clock_skew);
+    let (read_timeout, read_timeout_reconnect) = (config.read_timeout, config.read_timeout_reconnect);
+    let make_collector = move |symbol: &str| -> BoxedCollector {
+        let mut binance = BinanceCollector::new(url.clone(), symbol)
+            .with_reconnect_log_every(log_every)
+            .with_reconnect_backoff(base, max)
+            .with_min_reconnect_interval(min_interval)
+            .with_ping_interval(ping)
+            .with_warmup_ticks(warmup)
+            .with_stream(stream)
+            .with_max_clock_skew(max_skew);
+        if let Some(timeout) = stale_timeout {
+            binance = binance.with_stale_timeout(timeout);
+        }
+        if let Some(timeout) = read_timeout {
+            binance = binance.with_read_timeout(timeout, read_timeout_reconnect);
+        }
+        if let Some(proxy) = proxy.clone() {
+            binance = binance.with_proxy(proxy);
+        }
+        Box::new(binance)
+    };
+
+    let pipeline = MultiSymbolPipeline::new(
+        config.trading_symbols.clone(),
+        config.update_interval.to_std()?,
+        make_collector,
+        calculator_factory(config),
+    ).with_format(config.number_format);
+    info!("Tracking {} symbols: {}", pipeline.symbols().len(), pipeline.symbols().join(", "));
+    // Kept in step with `tasks`, which follows reloads rather than the startup list
+    let mut symbols = pipeline.symbols().to_vec();
+    let mut tasks = pipeline.spawn();
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("Received Ctrl-C, shutting down...");
+                break;
+            }
+            _ = sighup.recv() => {
+                info!("Received SIGHUP, reloading the symbol list...");
+                let new_config = match Config::reload(overrides) {
+                    Ok(new_config) => new_config,
+                    Err(e) => {
+                        error!("Keeping the current symbols, reloaded configuration is invalid: {}", e);
+                        continue;
+                    }
+                };
+                if new_config.trading_symbols.is_empty() {
+                    warn!("Leaving multi-symbol mode needs a restart; keeping {}", symbols.join(", "));
+                    continue;
+                }
+                let ignored = new_config.restart_required(config);
+                if !ignored.is_empty() {
+                    warn!("Ignoring changes to {} until restart", ignored.join(", "));
+                }
+                if new_config.trading_symbols.len() == symbols.len()
+                    && new_config.trading_symbols.iter().all(|symbol| symbols.contains(symbol))
+                {
+                    info!("Symbol list unchanged");
+                    continue;
+                }
+                let mut index = 0;
+                while index < symbols.len() {
+                    if new_config.trading_symbols.contains(&symbols[index]) {
+                        index += 1;
+                        continue;
+                    }
+                    let symbol = symbols.remove(index);
+                    tasks.remove(index).abort();
+                    pipeline.latest().write().unwrap_or_else(|e| e.into_inner()).remove(&symbol);
+                    info!("Stopped tracking {}", symbol);
+                }
+                for symbol in &new_config.trading_symbols {
+                    if !symbols.contains(symbol) {
+                        warn!("Now tracking {}, starting with a gap in its history", symbol);
+                        tasks.push(pipeline.spawn_added(symbol));
+                        symbols.push(symbol.clone());
+                    }
+                }
+                info!("Tracking {} symbols: {}", symbols.len(), symbols.join(", "));
+            }
+            (finished, index, _) = futures_util::future::select_all(tasks.iter_mut()) => {
+                let symbol = symbols[index].clone();
+                match finished {
+                    Err(e) if e.is_panic() => error!("{} task panicked, restarting it", symbol),
+                    _ => warn!("{} task stopped, restarting it", symbol),
+                }
+                tasks[index] = pipeline.spawn_symbol(&symbol);
+            }
+        }
+    }
+
+    for task in &tasks {
+        task.abort();
+    }
+    let latest = pipeline.latest();
+    for (symbol, volatility) in latest.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        match volatility {
+            Some(v) => info!("Final {} annualized volatility estimate: {}", symbol, config.number_format.pct(*v)),
+            None => info!("Not enough data points for a final {} estimate", symbol),
+        }
+    }
+    Ok(())
+}
+
+fn build_aggregator(config: &Config) -> Result<PriceAggregator, Box<dyn Error>> {
+    if let Some(path) = &config.replay_path {
+        info!("Replaying recorded prices from {}", path.display());
+        let replay = ReplayCollector::open(path)?.with_speedup(config.replay_speedup);
+        return Ok(PriceAggregator::new(vec![Box::new(replay)], config.aggregation_strategy));
+    }
+    if !config.collectors.is_empty() {
+        info!("Building collectors from COLLECTORS: {}", config.collectors.join(", "));
+        let aggregator = collector_registry()
+            .build_aggregator(&config.collectors, config, config.aggregation_strategy)?
+            .with_timestamp_mode(config.aggregation_timestamp)
+            .with_min_sources(config.min_sources)
+            .with_max_divergence(max_divergence(config), config.source_divergence_action)
+            .with_circuit_breaker(config.circuit_breaker)
+            .with_liquidity_tiers(config.liquidity_tiers.clone())
+            .with_time_offsets(config.time_offsets.clone());
+        aggregator.validate()?;
+        return Ok(aggregator);
+    }
+    if config.collector == CollectorKind::Synthetic {
+        return Ok(PriceAggregator::new(vec![Box::new(build_synthetic(config))], config.aggregation_strategy));
+    }
+
+    let mut aggregator = PriceAggregator::new(vec![Box::new(build_binance(config))], config.aggregation_strategy)
+        .with_timestamp_mode(config.aggregation_timestamp)
+        .with_min_sources(config.min_sources)
+        .with_max_divergence(max_divergence(config), config.source_divergence_action)
+        .with_circuit_breaker(config.circuit_breaker);
+    #[cfg(feature = "uniswap")]
+    if let Some(uniswap) = build_uniswap(config)? {
+        aggregator = aggregator.with_collector(Box::new(uniswap));
+    }
+    if let Some(gemini_url) = &config.gemini_ws_url {
+        aggregator = aggregator.with_collector(Box::new(build_gemini(config, gemini_url)));
+    }
+    if let Some(okx_url) = &config.okx_ws_url {
+        aggregator = aggregator.with_collector(Box::new(build_okx(config, okx_url)));
+    }
+    if let Some(htx_url) = &config.htx_ws_url {
+        aggregator = aggregator.with_collector(Box::new(build_htx(config, htx_url)));
+    }
+    #[cfg(feature = "coinbase")]
+    if let Some(coinbase_url) = &config.coinbase_ws_url {
+        aggregator = aggregator.with_collector(Box::new(build_coinbase(config, coinbase_url)));
+    }
+    #[cfg(feature = "kraken")]
+    if let Some(kraken_url) = &config.kraken_ws_url {
+        aggregator = aggregator.with_collector(Box::new(build_kraken(config, kraken_url)));
+    }
+    aggregator = aggregator
+        .with_liquidity_tiers(config.liquidity_tiers.clone())
+        .with_time_offsets(config.time_offsets.clone());
+    aggregator.validate()?;
+    Ok(aggregator)
+}
+
+fn max_divergence(config: &Config) -> Option<f64> {
+    config.max_source_divergence_pct.map(|pct| pct / 100.0)
+}
+
+// Every collector COLLECTORS can name. Venues other than Binance still need
+// their URL setting; naming one without it is an error rather than a skip.
+fn collector_registry() -> CollectorRegistry {
+    fn required<'a>(value: &'a Option<String>, var: &'static str) -> Result<&'a String, VolatilityError> {
+        value.as_ref().ok_or_else(|| ConfigError::Missing(var).into())
+    }
+    let registry = CollectorRegistry::new()
+        .register("binance", |config| Ok(Box::new(build_binance(config))))
+        .register("synthetic", |config| Ok(Box::new(build_synthetic(config))))
+        .register("gemini", |config| {
+            Ok(Box::new(build_gemini(config, required(&config.gemini_ws_url, "GEMINI_WS_URL")?)))
+        })
+        .register("okx", |config| {
+            Ok(Box::new(build_okx(config, required(&config.okx_ws_url, "OKX_WS_URL")?)))
+        })
+        .register("htx", |config| {
+            Ok(Box::new(build_htx(config, required(&config.htx_ws_url, "HTX_WS_URL")?)))
+        });
+    #[cfg(feature = "coinbase")]
+    let registry = registry.register("coinbase", |config| {
+        Ok(Box::new(build_coinbase(config, required(&config.coinbase_ws_url, "COINBASE_WS_URL")?)))
+    });
+    #[cfg(feature = "kraken")]
+    let registry = registry.register("kraken", |config| {
+        Ok(Box::new(build_kraken(config, required(&config.kraken_ws_url, "KRAKEN_WS_URL")?)))
+    });
+    #[cfg(feature = "uniswap")]
+    let registry = registry.register("uniswap", |config| match build_uniswap(config)? {
+        Some(uniswap) => Ok(Box::new(uniswap)),
+        None => Err(ConfigError::Missing(missing_uniswap_var(config)).into()),
+    });
+    registry
+}
+
+fn build_binance(config: &Config) -> BinanceCollector {
+    info!("Initializing Binance price collector for {} with URL: {}", config.trading_symbol, config.binance_ws_url);
+    let binance = BinanceCollector::new(config.binance_ws_url.clone(), &config.trading_symbol)
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_min_reconnect_interval(config.min_reconnect_interval)
+        .with_ping_interval(config.ping_interval)
+        .with_warmup_ticks(config.warmup_ticks)
+        .with_stream(config.binance_stream)
+        .with_max_clock_skew(config.max_clock_skew);
+    let binance = match config.stale_timeout {
+        Some(timeout) => binance.with_stale_timeout(timeout),
+        None => binance,
+    };
+    let binance = match config.read_timeout {
+        Some(timeout) => binance.with_read_timeout(timeout, config.read_timeout_reconnect),
+        None => binance,
+    };
+    match config.ws_proxy.clone() {
+        Some(proxy) => {
+            info!("Connecting to Binance through proxy {}", proxy.url());
+            binance.with_proxy(proxy)
+        }
+        None => binance,
+    }
+}
+
+fn build_synthetic(config: &Config) -> SyntheticCollector {
+    info!(
+        "Generating synthetic prices: {} volatility, {} drift, seed {}",
+        config.number_format.pct(config.synthetic_volatility),
+        config.number_format.pct(config.synthetic_drift),
+        config.synthetic_seed
+    );
+    SyntheticCollector::new(
+        config.synthetic_start_price,
+        config.synthetic_drift,
+        config.synthetic_volatility,
+        config.synthetic_seed,
+    )
+    .with_step(config.update_interval)
+}
+
+fn build_gemini(config: &Config, url: &str) -> GeminiCollector {
+    info!("Initializing Gemini price collector with URL: {}", url);
+    GeminiCollector::new(url.to_string())
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_warmup_ticks(config.warmup_ticks)
+}
+
+fn build_okx(config: &Config, url: &str) -> OkxCollector {
+    info!("Initializing OKX price collector with URL: {}", url);
+    OkxCollector::new(url.to_string())
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_warmup_ticks(config.warmup_ticks)
+}
+
+fn build_htx(config: &Config, url: &str) -> HtxCollector {
+    info!("Initializing HTX price collector with URL: {}", url);
+    HtxCollector::new(url.to_string())
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_warmup_ticks(config.warmup_ticks)
+}
+
+#[cfg(feature = "coinbase")]
+fn build_coinbase(config: &Config, url: &str) -> CoinbaseCollector {
+    info!("Initializing Coinbase price collector for {} with URL: {}", config.coinbase_product_id, url);
+    CoinbaseCollector::new(url.to_string(), &config.coinbase_product_id)
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_ping_interval(config.ping_interval)
+        .with_warmup_ticks(config.warmup_ticks)
+}
+
+#[cfg(feature = "kraken")]
+fn build_kraken(config: &Config, url: &str) -> KrakenCollector {
+    info!("Initializing Kraken price collector for {} with URL: {}", config.kraken_pair, url);
+    KrakenCollector::new(url.to_string(), &config.kraken_pair)
+        .with_reconnect_log_every(config.reconnect_log_every)
+        .with_reconnect_backoff(config.reconnect_base, config.reconnect_max)
+        .with_ping_interval(config.ping_interval)
+        .with_warmup_ticks(config.warmup_ticks)
+}
+
+#[cfg(feature = "uniswap")]
+fn build_uniswap(config: &Config) -> Result<Option<UniswapCollector>, VolatilityError> {
+    match (&config.ethereum_rpc_url, &config.uniswap_pool_address) {
+        (Some(rpc_url), Some(pool_address)) => {
+            info!("Connecting to Ethereum node at {}", rpc_url);
+            let transport = web3::transports::Http::new(rpc_url)?;
+            let pool_address: web3::types::H160 = pool_address.parse().map_err(|e| ConfigError::Invalid {
+                var: "UNISWAP_V3_POOL_ADDRESS",
+                value: pool_address.clone(),
+                reason: format!("{}", e),
+            })?;
+            Ok(Some(
+                UniswapCollector::new(
+                    pool_address,
+                    web3::Web3::new(transport),
+                    config.uniswap_token0_decimals,
+                    config.uniswap_token1_decimals,
+                )
+                .with_inverted(config.uniswap_invert),
+            ))
+        }
+        _ if config.require_uniswap => {
+            Err(ConfigError::Missing(missing_uniswap_var(config)).into())
+        }
+        _ => {
+            info!("Uniswap pool not configured, continuing without on-chain prices");
+            Ok(None)
+        }
+    }
+}
+
+// The first of the two Uniswap settings that is unset
+#[cfg(feature = "uniswap")]
+fn missing_uniswap_var(config: &Config) -> &'static str {
+    if config.ethereum_rpc_url.is_none() { "ETHEREUM_RPC_URL" } else { "UNISWAP_V3_POOL_ADDRESS" }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_become_config_overrides() {
+        let args = Args::try_parse_from([
+            "eth-volatility", "--symbol", "btcusdt", "--update-interval", "10", "--volatility-window-hours", "2",
+        ]).unwrap();
+        let overrides = ConfigOverrides::from(args);
+        assert_eq!(overrides.trading_symbol.as_deref(), Some("btcusdt"));
+        assert_eq!(overrides.update_interval_seconds, Some(10));
+        assert_eq!(overrides.volatility_window_hours, Some(2));
+        assert!(overrides.binance_ws_url.is_none() && overrides.config_file.is_none());
+
+        assert!(Args::try_parse_from(["eth-volatility", "--update-interval", "soon"]).is_err());
+    }
+
+    #[test]
+    fn jitter_scales_the_interval_within_its_band() {
+        let interval = std::time::Duration::from_secs(10);
+        assert_eq!(jittered_interval(interval, 0.0, 0.7), interval);
+        assert_eq!(jittered_interval(interval, 20.0, 0.0), interval);
+        assert_eq!(jittered_interval(interval, 20.0, 1.0), std::time::Duration::from_secs(12));
+        assert_eq!(jittered_interval(interval, 20.0, -0.5), std::time::Duration::from_secs(9));
+        // Draws outside [-1, 1] are clamped to the band's edges
+        assert_eq!(jittered_interval(interval, 20.0, -3.0), std::time::Duration::from_secs(8));
+        // Never down to zero, even at 100%
+        assert_eq!(jittered_interval(interval, 100.0, -1.0), std::time::Duration::from_millis(1));
+    }
+
+    #[test]
+    fn error_budget_trips_exactly_at_the_limit_and_resets_on_success() {
+        let mut budget = ErrorBudget::new(Some(3));
+        assert!(!budget.failure());
+        assert!(!budget.failure());
+        budget.success();
+        assert_eq!(budget.consecutive(), 0);
+
+        assert!(!budget.failure());
+        assert!(!budget.failure());
+        assert!(budget.failure());
+        assert_eq!(budget.consecutive(), 3);
+
+        // No limit retries forever
+        let mut unlimited = ErrorBudget::new(None);
+        assert!((0..1_000).all(|_| !unlimited.failure()));
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use tokio::sync::Notify;
+use crate::price_collector::{CollectorError, PriceCollector, PricePoint};
+use crate::error::VolatilityError;
+
+// What a fetch does when nothing has been pushed yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyQueuePolicy {
+    // Wait for the next push
+    #[default]
+    Wait,
+    // Fail the fetch with a timeout error straight away
+    Error,
+}
+
+#[derive(Default)]
+struct Queue {
+    points: Mutex<VecDeque<PricePoint>>,
+    pushed: Notify,
+}
+
+// Prices pushed by the embedding application instead of fetched from a
+// venue, for driving the pipeline from an upstream of its own or from
+// integration tests. Each fetch takes the oldest queued point.
+pub struct ManualCollector {
+    name: String,
+    queue: Arc<Queue>,
+    policy: EmptyQueuePolicy,
+}
+
+// Cloneable handle for pushing into a ManualCollector after it has been
+// handed to an aggregator
+#[derive(Clone)]
+pub struct ManualFeed {
+    queue: Arc<Queue>,
+}
+
+impl ManualFeed {
+    pub fn push(&self, point: PricePoint) {
+        self.queue.points.lock().unwrap_or_else(|e| e.into_inner()).push_back(point);
+        self.queue.pushed.notify_one();
+    }
+
+    // Points pushed but not fetched yet
+    pub fn pending(&self) -> usize {
+        self.queue.points.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl ManualCollector {
+    pub fn new() -> Self {
+        Self {
+            name: "Manual".to_string(),
+            queue: Arc::new(Queue::default()),
+            policy: EmptyQueuePolicy::Wait,
+        }
+    }
+
+    // Reported by name(); the points keep whatever source they were pushed with
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_empty_policy(mut self, policy: EmptyQueuePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn feed(&self) -> ManualFeed {
+        ManualFeed { queue: self.queue.clone() }
+    }
+
+    pub fn push(&self, point: PricePoint) {
+        self.feed().push(point);
+    }
+
+    fn pop(&self) -> Option<PricePoint> {
+        self.queue.points.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+    }
+}
+
+impl Default for ManualCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PriceCollector for ManualCollector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        loop {
+            // Registered before checking so a push in between isn't missed
+            let pushed = self.queue.pushed.notified();
+            if let Some(point) = self.pop() {
+                return Ok(point);
+            }
+            if self.policy == EmptyQueuePolicy::Error {
+                return Err(CollectorError::Timeout(format!("no {} price queued", self.name)).into());
+            }
+            pushed.await;
+        }
+    }
+}
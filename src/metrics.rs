@@ -0,0 +1,71 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::{extract::State, routing::get, Router};
+use prometheus::{Encoder, Gauge, IntCounter, IntGauge, Registry, TextEncoder};
+
+// Gauges and counters for the `/metrics` endpoint, refreshed by the main loop
+pub struct Metrics {
+    registry: Registry,
+    pub current_volatility: Gauge,
+    pub last_price: Gauge,
+    pub price_history_len: IntGauge,
+    pub binance_reconnects_total: IntCounter,
+    pub binance_consecutive_errors: IntGauge,
+    pub price_fetch_errors_total: IntCounter,
+    pub return_anomalies_total: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let metrics = Self {
+            current_volatility: Gauge::new("current_volatility", "Latest annualized volatility estimate")?,
+            last_price: Gauge::new("last_price", "Most recent price fed into the window")?,
+            price_history_len: IntGauge::new("price_history_len", "Price points currently in the window")?,
+            binance_reconnects_total: IntCounter::new("binance_reconnects_total", "Binance WebSocket reconnections")?,
+            binance_consecutive_errors: IntGauge::new("binance_consecutive_errors", "Failed Binance fetches since the last price")?,
+            price_fetch_errors_total: IntCounter::new("price_fetch_errors_total", "Failed price fetches")?,
+            return_anomalies_total: IntCounter::new("return_anomalies_total", "Stored returns beyond ZSCORE_ALERT_THRESHOLD")?,
+            registry,
+        };
+        metrics.registry.register(Box::new(metrics.current_volatility.clone()))?;
+        metrics.registry.register(Box::new(metrics.last_price.clone()))?;
+        metrics.registry.register(Box::new(metrics.price_history_len.clone()))?;
+        metrics.registry.register(Box::new(metrics.binance_reconnects_total.clone()))?;
+        metrics.registry.register(Box::new(metrics.binance_consecutive_errors.clone()))?;
+        metrics.registry.register(Box::new(metrics.price_fetch_errors_total.clone()))?;
+        metrics.registry.register(Box::new(metrics.return_anomalies_total.clone()))?;
+        Ok(metrics)
+    }
+
+    // Counters only go up, so bring one in line with a running total
+    pub fn sync_counter(counter: &IntCounter, total: u64) {
+        counter.inc_by(total.saturating_sub(counter.get()));
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+pub fn router(metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/metrics", get(render))
+        .with_state(metrics)
+}
+
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> Result<(), hyper::Error> {
+    log::info!("Metrics server listening on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(metrics).into_make_service())
+        .await
+}
+
+async fn render(State(metrics): State<Arc<Metrics>>) -> String {
+    metrics.render()
+}
@@ -0,0 +1,134 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::envelope::{self, Envelope};
+use crate::price_collector::PricePoint;
+use crate::error::VolatilityError;
+
+// Where NDJSON_OUTPUT sends its lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NdjsonTarget {
+    Stdout,
+    File(PathBuf),
+}
+
+impl FromStr for NdjsonTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err("expected stdout or a file path".to_string()),
+            "stdout" | "-" => Ok(Self::Stdout),
+            path => Ok(Self::File(PathBuf::from(path))),
+        }
+    }
+}
+
+// Writes every fetched price as one versioned JSON object per line,
+// {"v":1,"point":{...}}, for piping into jq and the like
+pub struct NdjsonSink {
+    writer: Box<dyn Write + Send>,
+}
+
+impl NdjsonSink {
+    // Appends to an existing file
+    pub fn open(target: &NdjsonTarget) -> Result<Self, VolatilityError> {
+        let writer: Box<dyn Write + Send> = match target {
+            NdjsonTarget::Stdout => Box::new(std::io::stdout()),
+            NdjsonTarget::File(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+        };
+        Ok(Self::new(writer))
+    }
+
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self { writer }
+    }
+
+    // Flushed per line so a downstream reader sees each point right away
+    pub fn write(&mut self, point: &PricePoint) -> Result<(), VolatilityError> {
+        serde_json::to_writer(&mut self.writer, &Envelope::new(point))?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Points from NDJSON written by NdjsonSink, skipping blank lines. Fails on
+// the first line that doesn't decode or has an unsupported version.
+pub fn read_points(reader: impl BufRead) -> Result<Vec<PricePoint>, VolatilityError> {
+    let mut points = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let at_line = |message: String| VolatilityError::Parse(format!("line {}: {}", index + 1, message));
+        let value = serde_json::from_str(&line).map_err(|e| at_line(e.to_string()))?;
+        let point = envelope::from_json(value).map_err(|e| match e {
+            VolatilityError::Parse(message) => at_line(message),
+            other => other,
+        })?;
+        points.push(point);
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use chrono::{TimeZone, Utc};
+
+    // Lets the test read back what the sink was handed
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn point(seconds: i64, price: f64) -> PricePoint {
+        PricePoint {
+            timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::seconds(seconds),
+            price,
+            source: "Binance:ETHUSDT".to_string(),
+            spread: None,
+            volume: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn each_point_is_one_enveloped_line_that_reads_back() {
+        let buffer = SharedBuffer::default();
+        let mut sink = NdjsonSink::new(Box::new(buffer.clone()));
+        sink.write(&point(0, 2500.0)).unwrap();
+        sink.write(&point(1, 2501.5)).unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["v"], 1);
+        assert_eq!(lines[1]["point"]["price"], 2501.5);
+
+        let points = read_points(output.as_bytes()).unwrap();
+        assert_eq!(points.iter().map(|p| (p.timestamp, p.price)).collect::<Vec<_>>(), [
+            (point(0, 0.0).timestamp, 2500.0),
+            (point(1, 0.0).timestamp, 2501.5),
+        ]);
+        assert_eq!(points[0].source, "Binance:ETHUSDT");
+    }
+
+    #[test]
+    fn a_newer_schema_fails_at_its_line() {
+        let input = format!("{}\n\n{}\n", r#"{"v":1,"point":{"timestamp":"2026-01-01T00:00:00Z","price":2500.0,"source":"x"}}"#, r#"{"v":2,"point":{}}"#);
+        let error = read_points(input.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("line 3") && error.to_string().contains("version 2"), "{}", error);
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::envelope::{self, Envelope};
+use crate::price_collector::PricePoint;
+use crate::error::VolatilityError;
+
+// On-disk encoding for persisted price history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistenceFormat {
+    // Pretty-printed JSON array, easy to inspect
+    Json,
+    // Compact binary, fastest to read and write
+    Bincode,
+    // One row per point, for spreadsheet import
+    Csv,
+}
+
+impl FromStr for PersistenceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "bincode" => Ok(Self::Bincode),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("unknown persistence format {:?} (expected json, bincode or csv)", other)),
+        }
+    }
+}
+
+// Flat record written to disk. Kept separate from `PricePoint` so that
+// non-self-describing formats like bincode always see every field.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPoint {
+    timestamp: DateTime<Utc>,
+    price: f64,
+    source: String,
+    spread: Option<f64>,
+}
+
+impl From<&PricePoint> for StoredPoint {
+    fn from(point: &PricePoint) -> Self {
+        Self {
+            timestamp: point.timestamp,
+            price: point.price,
+            source: point.source.clone(),
+            spread: point.spread,
+        }
+    }
+}
+
+impl From<StoredPoint> for PricePoint {
+    fn from(stored: StoredPoint) -> Self {
+        Self {
+            timestamp: stored.timestamp,
+            price: stored.price,
+            source: stored.source,
+            spread: stored.spread,
+            // Volume only matters when blending live sources, so isn't stored
+            volume: None,
+        }
+    }
+}
+
+// CSV can't nest the envelope, so its version is a leading column
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredCsvRow {
+    v: u32,
+    timestamp: DateTime<Utc>,
+    price: f64,
+    source: String,
+    spread: Option<f64>,
+}
+
+impl From<Envelope<StoredPoint>> for StoredCsvRow {
+    fn from(envelope: Envelope<StoredPoint>) -> Self {
+        let point = envelope.point;
+        Self {
+            v: envelope.v,
+            timestamp: point.timestamp,
+            price: point.price,
+            source: point.source,
+            spread: point.spread,
+        }
+    }
+}
+
+impl From<StoredCsvRow> for Envelope<StoredPoint> {
+    fn from(row: StoredCsvRow) -> Self {
+        Self {
+            v: row.v,
+            point: StoredPoint {
+                timestamp: row.timestamp,
+                price: row.price,
+                source: row.source,
+                spread: row.spread,
+            },
+        }
+    }
+}
+
+// Saves and loads price history in the configured format, one versioned
+// envelope per point
+pub struct HistoryStore {
+    path: PathBuf,
+    format: PersistenceFormat,
+}
+
+impl HistoryStore {
+    pub fn new(path: PathBuf, format: PersistenceFormat) -> Self {
+        Self { path, format }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Writes to a temporary file first so a crash never leaves a torn file
+    pub fn save<'a, I>(&self, points: I) -> Result<(), VolatilityError>
+    where
+        I: IntoIterator<Item = &'a PricePoint>,
+    {
+        let records: Vec<Envelope<StoredPoint>> = points.into_iter()
+            .map(|point| Envelope::new(StoredPoint::from(point)))
+            .collect();
+        let tmp_path = self.path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            match self.format {
+                PersistenceFormat::Json => serde_json::to_writer(&mut writer, &records)?,
+                PersistenceFormat::Bincode => bincode::serialize_into(&mut writer, &records)?,
+                PersistenceFormat::Csv => {
+                    let mut csv_writer = csv::Writer::from_writer(&mut writer);
+                    for record in records {
+                        csv_writer.serialize(StoredCsvRow::from(record))?;
+                    }
+                    csv_writer.flush()?;
+                }
+            }
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    // A missing or empty file is an empty history, and so is JSON cut off
+    // mid-document, since there's nothing past the cut to lose. Contents
+    // that don't decode in the configured format, or carry a schema version
+    // this build doesn't read, are an error naming the file. Files from
+    // before versioning are read as envelope::UNVERSIONED and rewritten as
+    // v1 on the next save.
+    pub fn load(&self) -> Result<Vec<PricePoint>, VolatilityError> {
+        if !self.path.exists() || fs::metadata(&self.path)?.len() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let records: Vec<StoredPoint> = match self.format {
+            PersistenceFormat::Json => {
+                let values: Vec<serde_json::Value> = match serde_json::from_reader(reader) {
+                    Ok(values) => values,
+                    Err(e) if e.is_eof() => {
+                        log::warn!("{} is truncated, starting with an empty history: {}", self.path.display(), e);
+                        return Ok(Vec::new());
+                    }
+                    Err(e) => return Err(self.decode_error(e)),
+                };
+                values.into_iter()
+                    .map(envelope::from_json)
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| self.decode_error(e))?
+            }
+            // Bincode isn't self-describing, so an unversioned file only
+            // shows itself by failing to decode as envelopes
+            PersistenceFormat::Bincode => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                let enveloped = bincode::deserialize::<Vec<Envelope<StoredPoint>>>(&bytes)
+                    .map_err(VolatilityError::from)
+                    .and_then(|envelopes| envelopes.into_iter().map(Envelope::open).collect());
+                match enveloped {
+                    Ok(records) => records,
+                    Err(e) => bincode::deserialize::<Vec<StoredPoint>>(&bytes)
+                        .map_err(|_| self.decode_error(e))?,
+                }
+            }
+            PersistenceFormat::Csv => {
+                let mut csv_reader = csv::Reader::from_reader(reader);
+                // A header-only file decodes as no rows, so check the header
+                // itself to catch a file in another format
+                let versioned = match csv_reader.headers().map_err(|e| self.decode_error(e))?.get(0) {
+                    Some("v") => true,
+                    Some("timestamp") => false,
+                    other => return Err(self.decode_error(format!("unexpected first column {:?}", other.unwrap_or("")))),
+                };
+                if versioned {
+                    csv_reader.deserialize::<StoredCsvRow>()
+                        .map(|row| Envelope::from(row?).open())
+                        .collect::<Result<_, VolatilityError>>()
+                        .map_err(|e| self.decode_error(e))?
+                } else {
+                    csv_reader.deserialize::<StoredPoint>()
+                        .collect::<Result<_, _>>()
+                        .map_err(|e| self.decode_error(e))?
+                }
+            }
+        };
+        Ok(records.into_iter().map(PricePoint::from).collect())
+    }
+
+    fn decode_error(&self, e: impl std::fmt::Display) -> VolatilityError {
+        VolatilityError::Parse(format!(
+            "{} is not a valid {:?} history file: {}",
+            self.path.display(), self.format, e
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn points() -> Vec<PricePoint> {
+        (0..3)
+            .map(|i| PricePoint {
+                timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 0, i, 0).unwrap(),
+                price: 2500.0 + i as f64,
+                source: "Binance:ETHUSDT".to_string(),
+                spread: (i == 1).then_some(0.5),
+                volume: None,
+            })
+            .collect()
+    }
+
+    // A fresh path under the system temp directory, unique to this test run
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("eth-volatility-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn loaded(path: PathBuf, format: PersistenceFormat) -> Vec<(DateTime<Utc>, f64, Option<f64>)> {
+        let points = HistoryStore::new(path.clone(), format).load().unwrap();
+        fs::remove_file(path).unwrap();
+        points.into_iter().map(|p| (p.timestamp, p.price, p.spread)).collect()
+    }
+
+    fn expected() -> Vec<(DateTime<Utc>, f64, Option<f64>)> {
+        points().into_iter().map(|p| (p.timestamp, p.price, p.spread)).collect()
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for (name, format) in [
+            ("roundtrip.json", PersistenceFormat::Json),
+            ("roundtrip.bin", PersistenceFormat::Bincode),
+            ("roundtrip.csv", PersistenceFormat::Csv),
+        ] {
+            let path = temp_path(name);
+            HistoryStore::new(path.clone(), format).save(points().iter()).unwrap();
+            assert_eq!(loaded(path, format), expected(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn unversioned_json_history_is_migrated() {
+        let path = temp_path("v0.json");
+        let bare: Vec<StoredPoint> = points().iter().map(StoredPoint::from).collect();
+        fs::write(&path, serde_json::to_string(&bare).unwrap()).unwrap();
+        assert_eq!(loaded(path, PersistenceFormat::Json), expected());
+    }
+
+    #[test]
+    fn unversioned_bincode_history_is_migrated() {
+        let path = temp_path("v0.bin");
+        let bare: Vec<StoredPoint> = points().iter().map(StoredPoint::from).collect();
+        fs::write(&path, bincode::serialize(&bare).unwrap()).unwrap();
+        assert_eq!(loaded(path, PersistenceFormat::Bincode), expected());
+    }
+
+    #[test]
+    fn unversioned_csv_history_is_migrated() {
+        let path = temp_path("v0.csv");
+        fs::write(&path, concat!(
+            "timestamp,price,source,spread\n",
+            "2026-01-01T00:00:00Z,2500.0,Binance:ETHUSDT,\n",
+            "2026-01-01T00:01:00Z,2501.0,Binance:ETHUSDT,0.5\n",
+            "2026-01-01T00:02:00Z,2502.0,Binance:ETHUSDT,\n",
+        )).unwrap();
+        assert_eq!(loaded(path, PersistenceFormat::Csv), expected());
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let path = temp_path("v2.json");
+        fs::write(&path, r#"[{"v":2,"point":{"timestamp":"2026-01-01T00:00:00Z","price":1.0,"source":"x","spread":null}}]"#).unwrap();
+        let error = HistoryStore::new(path.clone(), PersistenceFormat::Json).load().unwrap_err();
+        fs::remove_file(path).unwrap();
+        assert!(error.to_string().contains("unsupported schema version 2"), "{}", error);
+    }
+
+    #[test]
+    fn empty_and_truncated_files_are_an_empty_history() {
+        let path = temp_path("empty.bin");
+        fs::write(&path, "").unwrap();
+        assert!(loaded(path, PersistenceFormat::Bincode).is_empty());
+
+        let path = temp_path("truncated.json");
+        fs::write(&path, r#"[{"v":1,"point":{"timestamp":"2026-01-01T00:00:00Z","pri"#).unwrap();
+        assert!(loaded(path, PersistenceFormat::Json).is_empty());
+    }
+
+    #[test]
+    fn a_file_in_another_format_is_an_error() {
+        let path = temp_path("mismatch.json");
+        HistoryStore::new(path.clone(), PersistenceFormat::Json).save(points().iter()).unwrap();
+        let error = HistoryStore::new(path.clone(), PersistenceFormat::Csv).load().unwrap_err();
+        fs::remove_file(path).unwrap();
+        assert!(error.to_string().contains("is not a valid Csv history file"), "{}", error);
+    }
+}
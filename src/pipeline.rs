@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use log::{error, info};
+use tokio::task::JoinHandle;
+use crate::format::NumberFormat;
+use crate::hooks::HookRegistry;
+use crate::price_collector::BoxedCollector;
+use crate::volatility::VolatilityCalculator;
+
+// Latest estimate per symbol; None until that symbol's window can report
+pub type SymbolVolatility = Arc<RwLock<BTreeMap<String, Option<f64>>>>;
+
+// One collector and one calculator per symbol, each pair running as its
+// own task so a slow or failing feed doesn't hold up the others
+pub struct MultiSymbolPipeline {
+    symbols: Vec<String>,
+    update_interval: std::time::Duration,
+    make_collector: Arc<dyn Fn(&str) -> BoxedCollector + Send + Sync>,
+    make_calculator: Arc<dyn Fn() -> VolatilityCalculator + Send + Sync>,
+    make_hooks: Arc<dyn Fn(&str) -> HookRegistry + Send + Sync>,
+    latest: SymbolVolatility,
+    format: NumberFormat,
+}
+
+impl MultiSymbolPipeline {
+    pub fn new(
+        symbols: Vec<String>,
+        update_interval: std::time::Duration,
+        make_collector: impl Fn(&str) -> BoxedCollector + Send + Sync + 'static,
+        make_calculator: impl Fn() -> VolatilityCalculator + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            symbols,
+            update_interval,
+            make_collector: Arc::new(make_collector),
+            make_calculator: Arc::new(make_calculator),
+            make_hooks: Arc::new(|_: &str| HookRegistry::new()),
+            latest: Arc::new(RwLock::new(BTreeMap::new())),
+            format: NumberFormat::default(),
+        }
+    }
+
+    // How each task logs its estimate
+    pub fn with_format(mut self, format: NumberFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // Callbacks run on every price and estimate, one registry per symbol
+    // task, built when the task starts
+    pub fn with_hooks(mut self, make_hooks: impl Fn(&str) -> HookRegistry + Send + Sync + 'static) -> Self {
+        self.make_hooks = Arc::new(make_hooks);
+        self
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    // Shared view of every symbol's latest estimate
+    pub fn latest(&self) -> SymbolVolatility {
+        self.latest.clone()
+    }
+
+    // Start one task per symbol, in symbol order
+    pub fn spawn(&self) -> Vec<JoinHandle<()>> {
+        self.symbols.iter().map(|symbol| self.spawn_symbol(symbol)).collect()
+    }
+
+    // Start a task that fetches, updates its own window and publishes its
+    // estimate every update interval until aborted. Calling it again for a
+    // symbol whose task died starts over with a fresh window.
+    pub fn spawn_symbol(&self, symbol: &str) -> JoinHandle<()> {
+        self.spawn_task(symbol, false)
+    }
+
+    // Like spawn_symbol, for a symbol added while running: its first
+    // estimate is flagged as following a gap
+    pub fn spawn_added(&self, symbol: &str) -> JoinHandle<()> {
+        self.spawn_task(symbol, true)
+    }
+
+    fn spawn_task(&self, symbol: &str, gap: bool) -> JoinHandle<()> {
+        let symbol = symbol.to_string();
+        let collector = (self.make_collector)(&symbol);
+        let mut calculator = (self.make_calculator)();
+        if gap {
+            calculator.mark_gap();
+        }
+        let mut hooks = (self.make_hooks)(&symbol);
+        let latest = self.latest.clone();
+        let update_interval = self.update_interval;
+        let format = self.format;
+        latest.write().unwrap_or_else(|e| e.into_inner()).insert(symbol.clone(), None);
+        tokio::spawn(async move {
+            loop {
+                match collector.get_latest_price().await {
+                    Ok(price) => {
+                        hooks.notify_price(&price);
+                        calculator.add_price(price);
+                        let volatility = match calculator.calculate_result(false) {
+                            Ok(Some(mut result)) => {
+                                result.gap = calculator.take_gap();
+                                hooks.notify_volatility(&result);
+                                Some(result.annualized_volatility)
+                            }
+                            Ok(None) => None,
+                            Err(e) => {
+                                error!("{} volatility unavailable: {}", symbol, e);
+                                None
+                            }
+                        };
+                        latest.write().unwrap_or_else(|e| e.into_inner()).insert(symbol.clone(), volatility);
+                        match volatility {
+                            Some(v) => info!(symbol = symbol.as_str(), volatility = v; "{} annualized volatility: {}", symbol, format.pct(v)),
+                            None => info!("{}: {} more returns needed", symbol, calculator.samples_needed()),
+                        }
+                    }
+                    Err(e) => {
+                        error!("{} price fetch failed: {}", symbol, e);
+                        calculator.mark_gap();
+                    }
+                }
+                tokio::time::sleep(update_interval).await;
+            }
+        })
+    }
+}
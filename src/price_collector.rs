@@ -1,11 +1,19 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
-use tungstenite::{connect, Message};
+use chrono::{DateTime, Duration, Utc};
+use tungstenite::Message;
 use url::Url;
 use async_trait::async_trait;
-use log;
 use tokio::sync::Mutex;
+use std::io::Read;
+use std::time::Instant;
+use flate2::read::GzDecoder;
+use crate::proxy::WsProxy;
+use crate::error::VolatilityError;
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
+use futures_util::{future::join_all, stream, FutureExt, SinkExt, Stream, StreamExt};
 
 #[cfg(feature = "uniswap")]
 use web3::{
@@ -13,16 +21,285 @@ use web3::{
     contract::{Contract, Options},
 };
 
+type AsyncWsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+// Open a WebSocket directly, or tunnelled through `proxy` when one is given
+async fn connect_websocket(url: &Url, proxy: Option<&WsProxy>) -> Result<AsyncWsStream, VolatilityError> {
+    let Some(proxy) = proxy else {
+        return Ok(tokio_tungstenite::connect_async(url.as_str()).await?.0);
+    };
+    let host = url.host_str().ok_or_else(|| VolatilityError::Parse(format!("WebSocket URL {} has no host", url)))?;
+    let port = url.port_or_known_default().ok_or_else(|| VolatilityError::Parse(format!("WebSocket URL {} has no port", url)))?;
+    log::debug!("Connecting to {}:{} through proxy {}", host, port, proxy.url());
+    let stream = proxy.connect(host, port).await?;
+    Ok(tokio_tungstenite::client_async_tls(url.as_str(), stream).await?.0)
+}
+
+// Send a Close frame and flush it, giving up after a second so a dead peer
+// can't hold up a reconnect or shutdown
+async fn close_websocket(mut socket: AsyncWsStream, venue: &str) {
+    match tokio::time::timeout(std::time::Duration::from_secs(1), socket.close(None)).await {
+        Ok(Ok(())) => log::debug!("Closed {} WebSocket", venue),
+        Ok(Err(e)) => log::debug!("Error closing {} WebSocket: {}", venue, e),
+        Err(_) => log::debug!("Timed out closing {} WebSocket", venue),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricePoint {
     pub timestamp: DateTime<Utc>,
     pub price: f64,
     pub source: String,
+    // Best ask minus best bid, when the source provides book data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spread: Option<f64>,
+    // Traded quantity behind this price, when the source reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+}
+
+// One bar of range data; `timestamp` is the bar's open time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OhlcPoint {
+    pub timestamp: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub source: String,
+}
+
+// Classified collector failure, so callers can tell transient problems from fatal ones
+#[derive(Debug)]
+pub enum CollectorError {
+    // Couldn't open the connection; worth retrying
+    Connection(String),
+    // Connected, but the venue didn't accept or confirm the subscription
+    Subscription(String),
+    // A message arrived but its contents couldn't be read
+    Parse(String),
+    // Nothing usable arrived in time
+    Timeout(String),
+    // Network or RPC transport problem; worth retrying
+    Transport(String),
+    // The contract call reverted; retrying won't help
+    Revert(String),
+    // ABI or response decoding failed
+    Decode(String),
+}
+
+impl CollectorError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Connection(_) | Self::Timeout(_) | Self::Transport(_))
+    }
+}
+
+impl std::fmt::Display for CollectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Connection(msg) => write!(f, "connection error: {}", msg),
+            Self::Subscription(msg) => write!(f, "subscription error: {}", msg),
+            Self::Parse(msg) => write!(f, "parse error: {}", msg),
+            Self::Timeout(msg) => write!(f, "timed out: {}", msg),
+            Self::Transport(msg) => write!(f, "transport error: {}", msg),
+            Self::Revert(msg) => write!(f, "contract reverted: {}", msg),
+            Self::Decode(msg) => write!(f, "decode error: {}", msg),
+        }
+    }
+}
+
+impl Error for CollectorError {}
+
+#[cfg(feature = "uniswap")]
+impl From<web3::contract::Error> for CollectorError {
+    fn from(e: web3::contract::Error) -> Self {
+        use web3::contract::Error as ContractError;
+        match e {
+            ContractError::Api(api_error) => match api_error {
+                web3::Error::Rpc(rpc_error) if rpc_error.message.to_lowercase().contains("revert") => {
+                    Self::Revert(rpc_error.message)
+                }
+                web3::Error::Decoder(msg) | web3::Error::InvalidResponse(msg) => Self::Decode(msg),
+                other => Self::Transport(other.to_string()),
+            },
+            other => Self::Decode(other.to_string()),
+        }
+    }
 }
 
 #[async_trait]
 pub trait PriceCollector {
-    async fn get_latest_price(&self) -> Result<PricePoint, Box<dyn Error>>;
+    // Venue name used for stats, liquidity tiers and time offsets, e.g. "Binance"
+    fn name(&self) -> &str;
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError>;
+
+    // Connections re-established after the first, for collectors that track it
+    fn reconnects(&self) -> u64 {
+        0
+    }
+
+    // Failed fetches since the last successful one, for collectors that track it
+    fn consecutive_errors(&self) -> u32 {
+        0
+    }
+
+    // Release any open connection before shutdown
+    async fn close(&self) {}
+}
+
+// Every price a collector produces, as a stream. Each item is one
+// get_latest_price call, so an error is yielded without ending the stream
+// and the next poll reconnects as usual.
+pub fn price_stream<C>(collector: &C) -> impl Stream<Item = Result<PricePoint, VolatilityError>> + '_
+where
+    C: PriceCollector + ?Sized,
+{
+    stream::unfold(collector, |collector| async move {
+        Some((collector.get_latest_price().await, collector))
+    })
+}
+
+// Failed reconnects are logged on the first attempt and every Nth after that
+pub const DEFAULT_RECONNECT_LOG_EVERY: u32 = 10;
+
+// Rate-limits connection logging so an outage is visible without flooding
+// the log with identical messages, and summarizes once the link recovers
+pub struct ReconnectLogger {
+    every: u32,
+    failures: AtomicU32,
+    // Successful connections, including the first
+    connections: AtomicU64,
+}
+
+impl ReconnectLogger {
+    pub fn new(every: u32) -> Self {
+        Self {
+            every: every.max(1),
+            failures: AtomicU32::new(0),
+            connections: AtomicU64::new(0),
+        }
+    }
+
+    // Connections established after the initial one
+    pub fn reconnects(&self) -> u64 {
+        self.connections.load(Ordering::Relaxed).saturating_sub(1)
+    }
+
+    fn should_log(&self, failures: u32) -> bool {
+        failures.is_multiple_of(self.every)
+    }
+
+    pub fn attempt(&self, venue: &str) {
+        let failures = self.failures.load(Ordering::Relaxed);
+        if failures == 0 {
+            log::info!("Establishing new {} WebSocket connection...", venue);
+        } else if self.should_log(failures) {
+            log::warn!("Still reconnecting to {} ({} failed attempts so far)", venue, failures);
+        } else {
+            log::debug!("Reconnecting to {} (attempt {})", venue, failures + 1);
+        }
+    }
+
+    pub fn failed(&self, venue: &str, error: &dyn Error) {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures == 1 || self.should_log(failures) {
+            log::error!("{} connection attempt {} failed: {}", venue, failures, error);
+        } else {
+            log::debug!("{} connection attempt {} failed: {}", venue, failures, error);
+        }
+    }
+
+    pub fn connected(&self, venue: &str) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+        let failures = self.failures.swap(0, Ordering::Relaxed);
+        if failures > 0 {
+            log::info!("Reconnected to {} after {} failed attempts", venue, failures);
+        }
+    }
+}
+
+// Discards the first few points after each (re)connection, which tend to
+// be a burst of backlogged trades or a stale snapshot
+pub struct ConnectionWarmup {
+    ticks: u32,
+    remaining: AtomicU32,
+}
+
+impl ConnectionWarmup {
+    pub fn new(ticks: u32) -> Self {
+        Self {
+            ticks,
+            remaining: AtomicU32::new(ticks),
+        }
+    }
+
+    // Call whenever a fresh connection is established
+    pub fn reset(&self) {
+        self.remaining.store(self.ticks, Ordering::Relaxed);
+    }
+
+    // True while the current connection is still warming up
+    pub fn should_discard(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+pub const DEFAULT_BINANCE_REST_BASE: &str = "https://api.binance.com";
+
+pub const DEFAULT_RECONNECT_BASE: std::time::Duration = std::time::Duration::from_millis(500);
+pub const DEFAULT_RECONNECT_MAX: std::time::Duration = std::time::Duration::from_secs(60);
+pub const DEFAULT_PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+pub const DEFAULT_MAX_CLOCK_SKEW_SECONDS: i64 = 5;
+
+// Exponential backoff between reconnect attempts: the delay doubles after
+// each consecutive failure up to `max`, and is drawn from the upper half of
+// that range so restarted instances don't retry in lockstep
+pub struct ReconnectBackoff {
+    base: std::time::Duration,
+    max: std::time::Duration,
+    failures: AtomicU32,
+}
+
+impl ReconnectBackoff {
+    pub fn new(base: std::time::Duration, max: std::time::Duration) -> Self {
+        Self {
+            base,
+            max: max.max(base),
+            failures: AtomicU32::new(0),
+        }
+    }
+
+    // Delay before the next attempt; zero until something has failed
+    pub fn delay(&self) -> std::time::Duration {
+        let failures = self.failures.load(Ordering::Relaxed);
+        if failures == 0 {
+            return std::time::Duration::ZERO;
+        }
+        let ceiling = self.base
+            .saturating_mul(2u32.saturating_pow(failures - 1))
+            .min(self.max);
+        ceiling.mul_f64(rand::random::<f64>() * 0.5 + 0.5)
+    }
+
+    pub async fn wait(&self) {
+        let delay = self.delay();
+        if !delay.is_zero() {
+            log::debug!("Backing off {:?} before reconnecting", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    pub fn failed(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Call after a successful read
+    pub fn reset(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
 }
 
 // Uniswap collector behind feature flag
@@ -30,26 +307,67 @@ pub trait PriceCollector {
 pub struct UniswapCollector {
     pool_address: H160,
     web3_client: web3::Web3<web3::transports::Http>,
+    token0_decimals: u8,
+    token1_decimals: u8,
+    // Report token0 per token1 instead of the pool's native token1 per token0
+    invert: bool,
 }
 
 #[cfg(feature = "uniswap")]
 const UNISWAP_V3_POOL_ABI: &[u8] = include_bytes!("../abi/uniswap_v3_pool.json");
 
+// Human-readable price from a pool's sqrtPriceX96. (sqrtPriceX96 / 2^96)^2
+// is token1 per token0 in base units; scaling by 10^(decimals0 - decimals1)
+// gives whole tokens. `invert` flips it to token0 per token1.
+#[cfg(feature = "uniswap")]
+pub fn sqrt_price_to_price(sqrt_price_x96: U256, token0_decimals: u8, token1_decimals: u8, invert: bool) -> f64 {
+    // sqrtPriceX96 is a uint160, too wide for as_u128
+    let sqrt_price = sqrt_price_x96.0.iter()
+        .rev()
+        .fold(0.0, |acc, word| acc * 2.0_f64.powi(64) + *word as f64);
+    let ratio = sqrt_price / 2.0_f64.powi(96);
+    let price = ratio * ratio * 10.0_f64.powi(token0_decimals as i32 - token1_decimals as i32);
+    if invert {
+        1.0 / price
+    } else {
+        price
+    }
+}
+
+// Transport failures are retried this many times before giving up
+#[cfg(feature = "uniswap")]
+const UNISWAP_MAX_RETRIES: u32 = 3;
+
 #[cfg(feature = "uniswap")]
 impl UniswapCollector {
-    pub fn new(pool_address: H160, web3_client: web3::Web3<web3::transports::Http>) -> Self {
+    pub fn new(
+        pool_address: H160,
+        web3_client: web3::Web3<web3::transports::Http>,
+        token0_decimals: u8,
+        token1_decimals: u8,
+    ) -> Self {
         Self {
             pool_address,
             web3_client,
+            token0_decimals,
+            token1_decimals,
+            invert: false,
         }
     }
 
-    async fn get_slot0(&self) -> Result<(U256, i32, u16, u16, u16, u8, bool), Box<dyn Error>> {
+    // For pools whose token order is the reverse of the quote wanted, e.g.
+    // USDC/WETH when the ETH price in USDC is needed
+    pub fn with_inverted(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    async fn get_slot0(&self) -> Result<(U256, i32, u16, u16, u16, u8, bool), CollectorError> {
         let contract = Contract::from_json(
             self.web3_client.eth(),
             self.pool_address,
             UNISWAP_V3_POOL_ABI,
-        )?;
+        ).map_err(|e| CollectorError::Decode(format!("invalid pool ABI: {}", e)))?;
 
         let result: (U256, i32, u16, u16, u16, u8, bool) = contract
             .query("slot0", (), None, Options::default(), None)
@@ -57,89 +375,687 @@ impl UniswapCollector {
 
         Ok(result)
     }
+
+    // Retry transport errors with a short linear delay; reverts and decode
+    // errors fail immediately
+    async fn get_slot0_with_retry(&self) -> Result<(U256, i32, u16, u16, u16, u8, bool), CollectorError> {
+        let mut attempt = 0;
+        loop {
+            match self.get_slot0().await {
+                Ok(result) => return Ok(result),
+                Err(e) if e.is_transient() && attempt < UNISWAP_MAX_RETRIES => {
+                    attempt += 1;
+                    log::warn!(
+                        "Uniswap slot0 query for pool {:?} failed ({}), retry {}/{}",
+                        self.pool_address, e, attempt, UNISWAP_MAX_RETRIES
+                    );
+                    tokio::time::sleep(std::time::Duration::from_millis(500 * attempt as u64)).await;
+                }
+                Err(e) => {
+                    log::error!("Uniswap slot0 query for pool {:?} failed: {}", self.pool_address, e);
+                    return Err(e);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(feature = "uniswap")]
 #[async_trait]
 impl PriceCollector for UniswapCollector {
-    async fn get_latest_price(&self) -> Result<PricePoint, Box<dyn Error>> {
-        let (sqrt_price_x96, _, _, _, _, _, _) = self.get_slot0().await?;
+    fn name(&self) -> &str {
+        "Uniswap"
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        let (sqrt_price_x96, _, _, _, _, _, _) = self.get_slot0_with_retry().await?;
         
-        // Convert sqrtPriceX96 to actual price
-        let price = (sqrt_price_x96.as_u128() as f64).powi(2) / 2.0_f64.powi(192);
+        let price = sqrt_price_to_price(sqrt_price_x96, self.token0_decimals, self.token1_decimals, self.invert);
         
         Ok(PricePoint {
             timestamp: Utc::now(),
             price,
             source: "Uniswap".to_string(),
+            spread: None,
+            volume: None,
         })
     }
 }
 
 pub struct BinanceCollector {
     websocket_url: String,
-    socket: Mutex<Option<tungstenite::WebSocket<tungstenite::stream::MaybeTlsStream<std::net::TcpStream>>>>,
+    // Lowercase pair name as used in stream names, e.g. "ethusdc"
+    symbol: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+    ping_interval: std::time::Duration,
+    // Rebuild the connection if a call waits this long without a trade
+    stale_timeout: Option<std::time::Duration>,
+    // Give up on a single fetch after this long, keeping the socket unless
+    // `reconnect_on_read_timeout` is set
+    read_timeout: Option<std::time::Duration>,
+    reconnect_on_read_timeout: bool,
+    // REST host for historical klines, without a trailing slash
+    rest_base: String,
+    // Connection time or the latest trade, whichever is later; the stale
+    // watchdog counts from here so it spans timed-out fetches
+    last_trade_at: std::sync::Mutex<Option<Instant>>,
+    proxy: Option<WsProxy>,
+    // Failed fetches (connect or read) since the last price
+    consecutive_errors: AtomicU32,
+    stream: BinanceStream,
+    // Hard floor between connection attempts, on top of the backoff
+    min_reconnect_interval: std::time::Duration,
+    last_attempt: std::sync::Mutex<Option<Instant>>,
+    // Warn when a trade's exchange timestamp is further than this from local time
+    max_clock_skew: Duration,
+    // Exchange minus local time for the latest trade, in milliseconds
+    clock_skew_ms: AtomicI64,
+    // Text messages read off the socket but not handed out yet: those that
+    // arrived ahead of the subscription ack and whatever was already waiting
+    // when a fetch drained the socket
+    queued: std::sync::Mutex<VecDeque<String>>,
+    // A socket failure found while draining behind a price that was still
+    // handed out; the next fetch reports it
+    deferred_failure: std::sync::Mutex<Option<tungstenite::Error>>,
+    subscription_timeout: std::time::Duration,
+}
+
+// Request id sent with SUBSCRIBE and echoed back in Binance's reply
+const BINANCE_SUBSCRIPTION_ID: u64 = 1;
+
+// Messages read while waiting for the subscription ack before giving up
+const MAX_MESSAGES_BEFORE_ACK: usize = 100;
+
+// How long to wait for the subscription ack before giving up
+pub const DEFAULT_SUBSCRIPTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// Most messages held between fetches; beyond it the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 10_000;
+
+// Which Binance stream to follow. aggTrade batches fills of one taker
+// order at one price, so it is lighter than the raw trade stream;
+// bookTicker prices at the best bid/ask mid, which moves even when a thin
+// market isn't trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinanceStream {
+    #[default]
+    Trade,
+    AggTrade,
+    BookTicker,
+}
+
+impl BinanceStream {
+    // Stream suffix and event type, e.g. "aggTrade" in ethusdc@aggTrade
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Trade => "trade",
+            Self::AggTrade => "aggTrade",
+            Self::BookTicker => "bookTicker",
+        }
+    }
+}
+
+impl FromStr for BinanceStream {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "trade" => Ok(Self::Trade),
+            "aggtrade" | "agg_trade" => Ok(Self::AggTrade),
+            "bookticker" | "book_ticker" | "mid" => Ok(Self::BookTicker),
+            other => Err(format!("unknown Binance stream: {}", other)),
+        }
+    }
 }
 
+// Both trade and aggTrade events carry the price, quantity and trade time
+// under the same keys; they differ in their id fields, which aren't needed
 #[derive(Debug, Deserialize)]
 struct BinanceTradeEvent {
+    #[serde(rename = "e")]
+    event: Option<String>,
     #[serde(rename = "p")]
     price: String,
+    #[serde(rename = "q")]
+    quantity: Option<String>,
     #[serde(rename = "T")]
     timestamp: i64,
 }
 
+// {"result": null, "id": 1} on success, {"error": {...}, "id": 1} otherwise
+#[derive(Debug, Deserialize)]
+struct BinanceSubscriptionResponse {
+    id: Option<serde_json::Value>,
+    error: Option<BinanceApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceApiError {
+    code: i64,
+    msg: String,
+}
+
+// Just the exchange time of a trade or futures bookTicker event
+#[derive(Debug, Deserialize)]
+struct BinanceEventTime {
+    #[serde(rename = "T")]
+    timestamp: Option<i64>,
+}
+
+// Best bid and ask. Spot updates carry no event type or timestamp; futures
+// ones add both.
+#[derive(Debug, Deserialize)]
+struct BinanceBookTickerEvent {
+    #[serde(rename = "e")]
+    event: Option<String>,
+    #[serde(rename = "b")]
+    bid: String,
+    #[serde(rename = "a")]
+    ask: String,
+    #[serde(rename = "T")]
+    timestamp: Option<i64>,
+}
+
 impl BinanceCollector {
-    pub fn new(websocket_url: String) -> Self {
+    pub fn new(websocket_url: String, symbol: &str) -> Self {
         Self { 
             websocket_url,
+            symbol: symbol.to_lowercase(),
             socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+            ping_interval: DEFAULT_PING_INTERVAL,
+            stale_timeout: None,
+            read_timeout: None,
+            reconnect_on_read_timeout: false,
+            rest_base: DEFAULT_BINANCE_REST_BASE.to_string(),
+            last_trade_at: std::sync::Mutex::new(None),
+            proxy: None,
+            consecutive_errors: AtomicU32::new(0),
+            stream: BinanceStream::Trade,
+            min_reconnect_interval: std::time::Duration::ZERO,
+            last_attempt: std::sync::Mutex::new(None),
+            max_clock_skew: Duration::seconds(DEFAULT_MAX_CLOCK_SKEW_SECONDS),
+            clock_skew_ms: AtomicI64::new(0),
+            queued: std::sync::Mutex::new(VecDeque::new()),
+            deferred_failure: std::sync::Mutex::new(None),
+            subscription_timeout: DEFAULT_SUBSCRIPTION_TIMEOUT,
+        }
+    }
+
+    pub fn with_max_clock_skew(mut self, max_skew: Duration) -> Self {
+        self.max_clock_skew = max_skew;
+        self
+    }
+
+    // Exchange timestamp minus local receive time for the latest trade;
+    // positive when the exchange is ahead
+    pub fn clock_skew(&self) -> Duration {
+        Duration::milliseconds(self.clock_skew_ms.load(Ordering::Relaxed))
+    }
+
+    // Never start connection attempts closer together than this, however
+    // the caller loops, to stay clear of Binance's connection rate limits
+    pub fn with_min_reconnect_interval(mut self, interval: std::time::Duration) -> Self {
+        self.min_reconnect_interval = interval;
+        self
+    }
+
+    // Sleep out whatever is left of the cooldown since the previous attempt
+    async fn wait_for_cooldown(&self) {
+        let remaining = {
+            let mut last_attempt = self.last_attempt.lock().unwrap_or_else(|e| e.into_inner());
+            let remaining = last_attempt
+                .map(|last| self.min_reconnect_interval.saturating_sub(last.elapsed()))
+                .unwrap_or_default();
+            *last_attempt = Some(Instant::now() + remaining);
+            remaining
+        };
+        if !remaining.is_zero() {
+            log::debug!("Waiting {:?} before the next Binance connection attempt", remaining);
+            tokio::time::sleep(remaining).await;
+        }
+    }
+
+    // Follow aggTrade or bookTicker instead of raw trades
+    pub fn with_stream(mut self, stream: BinanceStream) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    // Connections re-established after the first
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnect_log.reconnects()
+    }
+
+    // Connect through an HTTP CONNECT or SOCKS5 proxy
+    pub fn with_proxy(mut self, proxy: WsProxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn proxy(&self) -> Option<&WsProxy> {
+        self.proxy.as_ref()
+    }
+
+    // Send a ping whenever the stream has been silent this long
+    pub fn with_ping_interval(mut self, interval: std::time::Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    // Treat the connection as dead, even if it still answers pings, when no
+    // trade arrives for this long
+    pub fn with_stale_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.stale_timeout = Some(timeout);
+        self
+    }
+
+    // Fail a fetch that waits this long for a trade. The socket is kept for
+    // the next call unless `reconnect` is set; the stale watchdog decides
+    // separately when the connection itself is dead.
+    pub fn with_read_timeout(mut self, timeout: std::time::Duration, reconnect: bool) -> Self {
+        self.read_timeout = Some(timeout);
+        self.reconnect_on_read_timeout = reconnect;
+        self
+    }
+
+    // Give up on a connection whose SUBSCRIBE isn't confirmed this quickly
+    pub fn with_subscription_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.subscription_timeout = timeout;
+        self
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    // Skip this many points after every (re)connection
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    // REST host for backfill, e.g. https://testnet.binance.vision to match a
+    // testnet stream
+    pub fn with_rest_base(mut self, base: &str) -> Self {
+        self.rest_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn klines_url(&self) -> String {
+        format!("{}/api/v3/klines", self.rest_base)
+    }
+
+    // Historical closes from the REST klines endpoint, oldest first, each
+    // stamped with its bar's close time. Binance caps `limit` at 1000.
+    pub async fn fetch_historical(&self, symbol: &str, interval: &str, limit: u32) -> Result<Vec<PricePoint>, VolatilityError> {
+        let url = self.klines_url();
+        let klines: Vec<Vec<serde_json::Value>> = reqwest::Client::new()
+            .get(url)
+            .query(&[
+                ("symbol", symbol.to_uppercase()),
+                ("interval", interval.to_string()),
+                ("limit", limit.min(1000).to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let source = format!("Binance:{}", symbol.to_uppercase());
+        klines.iter()
+            .map(|kline| {
+                let close = kline.get(4).and_then(|v| v.as_str()).ok_or_else(|| VolatilityError::Parse("kline is missing its close price".to_string()))?;
+                let close_time = kline.get(6).and_then(|v| v.as_i64()).ok_or_else(|| VolatilityError::Parse("kline is missing its close time".to_string()))?;
+                let volume = kline.get(5).and_then(|v| v.as_str()).and_then(|v| v.parse().ok());
+                Ok(PricePoint {
+                    timestamp: DateTime::from_timestamp_millis(close_time).ok_or_else(|| VolatilityError::Parse("kline close time out of range".to_string()))?,
+                    price: close.parse()?,
+                    source: source.clone(),
+                    spread: None,
+                    volume,
+                })
+            })
+            .collect()
+    }
+
+    // Each trade as it arrives over the WebSocket
+    pub fn stream(&self) -> impl Stream<Item = Result<PricePoint, VolatilityError>> + '_ {
+        price_stream(self)
+    }
+
+    // Label carried by this collector's price points, e.g. "Binance:ETHUSDC"
+    pub fn source_label(&self) -> String {
+        format!("Binance:{}", self.symbol.to_uppercase())
+    }
+
+    pub fn subscription_message(&self) -> String {
+        serde_json::json!({
+            "method": "SUBSCRIBE",
+            "params": [format!("{}@{}", self.symbol, self.stream.name())],
+            "id": BINANCE_SUBSCRIPTION_ID,
+        }).to_string()
+    }
+
+    // Ok(true) for the reply to our SUBSCRIBE, Ok(false) for any other
+    // message, and an error if Binance rejected the subscription
+    pub fn parse_subscription_response(msg: &str) -> Result<bool, CollectorError> {
+        let Ok(response) = serde_json::from_str::<BinanceSubscriptionResponse>(msg) else {
+            return Ok(false);
+        };
+        if response.id.and_then(|id| id.as_u64()) != Some(BINANCE_SUBSCRIPTION_ID) {
+            return Ok(false);
+        }
+        match response.error {
+            Some(error) => Err(CollectorError::Subscription(format!(
+                "Binance rejected the subscription ({}): {}",
+                error.code, error.msg
+            ))),
+            None => Ok(true),
+        }
+    }
+
+    // A price point from a trade or aggTrade event; None for anything else on the
+    // stream, such as subscription results. A trade whose fields don't parse
+    // is an error, so the caller can skip just that message.
+    pub fn parse_trade(&self, msg: &str) -> Result<Option<PricePoint>, CollectorError> {
+        if self.stream == BinanceStream::BookTicker {
+            return self.parse_book_ticker(msg);
+        }
+        let Ok(trade) = serde_json::from_str::<BinanceTradeEvent>(msg) else {
+            return Ok(None);
+        };
+        if trade.event.as_deref().is_some_and(|event| event != self.stream.name()) {
+            return Ok(None);
+        }
+        let price = trade.price.parse()
+            .map_err(|e| CollectorError::Parse(format!("trade price {:?}: {}", trade.price, e)))?;
+        let timestamp = DateTime::from_timestamp_millis(trade.timestamp).unwrap_or_else(Utc::now);
+        Ok(Some(PricePoint {
+            timestamp,
+            price,
+            source: self.source_label(),
+            spread: None,
+            volume: trade.quantity.and_then(|q| q.parse().ok()),
+        }))
+    }
+
+    // Mid of the best bid and ask, with their difference as the spread
+    fn parse_book_ticker(&self, msg: &str) -> Result<Option<PricePoint>, CollectorError> {
+        let Ok(book) = serde_json::from_str::<BinanceBookTickerEvent>(msg) else {
+            return Ok(None);
+        };
+        if book.event.as_deref().is_some_and(|event| event != self.stream.name()) {
+            return Ok(None);
+        }
+        let parse = |side: &str, value: &str| value.parse::<f64>()
+            .map_err(|e| CollectorError::Parse(format!("best {} {:?}: {}", side, value, e)));
+        let (bid, ask) = (parse("bid", &book.bid)?, parse("ask", &book.ask)?);
+        if !(bid > 0.0 && ask >= bid) {
+            return Err(CollectorError::Parse(format!("crossed or empty book: bid {} ask {}", bid, ask)));
+        }
+        let timestamp = book.timestamp.and_then(DateTime::from_timestamp_millis).unwrap_or_else(Utc::now);
+        Ok(Some(PricePoint {
+            timestamp,
+            price: (bid + ask) / 2.0,
+            source: "Binance:mid".to_string(),
+            spread: Some(ask - bid),
+            volume: None,
+        }))
+    }
+
+    // Records how far `exchange_time` is from `local_time` and warns past
+    // the threshold: a large offset means a bad local clock or a broken
+    // feed, either of which throws off window eviction and annualization
+    fn check_clock_skew(&self, exchange_time: DateTime<Utc>, local_time: DateTime<Utc>) {
+        let skew = exchange_time - local_time;
+        self.clock_skew_ms.store(skew.num_milliseconds(), Ordering::Relaxed);
+        if skew.abs() > self.max_clock_skew {
+            log::warn!(
+                "Binance trade timestamp {} is {:.1}s {} local time; check the system clock",
+                exchange_time,
+                skew.num_milliseconds().abs() as f64 / 1000.0,
+                if skew > Duration::zero() { "ahead of" } else { "behind" },
+            );
         }
     }
 
-    async fn ensure_connection(&self) -> Result<(), Box<dyn Error>> {
+    fn mark_trade(&self) {
+        *self.last_trade_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
         let mut socket_guard = self.socket.lock().await;
         if socket_guard.is_none() {
-            log::info!("Establishing new Binance WebSocket connection...");
-            let (mut ws_stream, _) = connect(Url::parse(&self.websocket_url)?)?;
-            
-            // Subscribe to trade stream
-            let subscribe_msg = r#"{"method": "SUBSCRIBE", "params": ["ethusdc@trade"], "id": 1}"#;
-            log::debug!("Sending subscription message: {}", subscribe_msg);
-            ws_stream.write_message(Message::Text(subscribe_msg.into()))?;
+            self.backoff.wait().await;
+            self.wait_for_cooldown().await;
+            self.reconnect_log.attempt("Binance");
+            match self.open_connection().await {
+                Ok((ws_stream, early)) => {
+                    self.reconnect_log.connected("Binance");
+                    self.warmup.reset();
+                    self.mark_trade();
+                    self.queued.lock().unwrap_or_else(|e| e.into_inner()).extend(early);
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("Binance", &e);
+                    self.backoff.failed();
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
 
-            // Read subscription confirmation
-            let conf_msg = ws_stream.read_message()?;
-            log::debug!("Received subscription confirmation: {:?}", conf_msg);
+    // The subscribed socket, plus any stream messages Binance sent before
+    // confirming the subscription
+    async fn open_connection(&self) -> Result<(AsyncWsStream, VecDeque<String>), CollectorError> {
+        let url = Url::parse(&self.websocket_url)
+            .map_err(|e| CollectorError::Connection(format!("invalid URL {}: {}", self.websocket_url, e)))?;
+        let mut ws_stream = connect_websocket(&url, self.proxy.as_ref()).await
+            .map_err(|e| CollectorError::Connection(e.to_string()))?;
+        
+        // Subscribe to trade stream
+        let subscribe_msg = self.subscription_message();
+        log::debug!("Sending subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg)).await
+            .map_err(|e| CollectorError::Subscription(e.to_string()))?;
 
-            *socket_guard = Some(ws_stream);
+        // Wait for the reply carrying our request id; trades can arrive
+        // first and are kept rather than mistaken for the confirmation
+        let mut early = VecDeque::new();
+        let acked = tokio::time::timeout(self.subscription_timeout, async {
+            while early.len() < MAX_MESSAGES_BEFORE_ACK {
+                let msg = ws_stream.next().await
+                    .ok_or_else(|| CollectorError::Subscription("Binance closed the connection before confirming".to_string()))?
+                    .map_err(|e| CollectorError::Subscription(e.to_string()))?;
+                let Message::Text(text) = msg else {
+                    continue;
+                };
+                if Self::parse_subscription_response(&text)? {
+                    log::debug!("Received subscription confirmation: {}", text);
+                    return Ok(true);
+                }
+                early.push_back(text);
+            }
+            Ok::<_, CollectorError>(false)
+        }).await
+            .map_err(|_| CollectorError::Subscription(format!(
+                "no subscription confirmation from Binance within {:?}",
+                self.subscription_timeout
+            )))??;
+        if acked {
+            if !early.is_empty() {
+                log::debug!("Keeping {} Binance message(s) received before the confirmation", early.len());
+            }
+            return Ok((ws_stream, early));
         }
-        Ok(())
+        Err(CollectorError::Subscription(format!(
+            "no subscription confirmation from Binance within {} messages",
+            MAX_MESSAGES_BEFORE_ACK
+        )))
     }
 }
 
 #[async_trait]
 impl PriceCollector for BinanceCollector {
-    async fn get_latest_price(&self) -> Result<PricePoint, Box<dyn Error>> {
+    fn name(&self) -> &str {
+        "Binance"
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnect_count()
+    }
+
+    fn consecutive_errors(&self) -> u32 {
+        self.consecutive_errors.load(Ordering::Relaxed)
+    }
+
+    // Send a Close frame and drop the connection
+    async fn close(&self) {
+        if let Some(socket) = self.socket.lock().await.take() {
+            close_websocket(socket, "Binance").await;
+        }
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        let result = self.next_price().await;
+        match &result {
+            Ok(_) => self.consecutive_errors.store(0, Ordering::Relaxed),
+            Err(_) => {
+                self.consecutive_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+}
+
+// A collector dropped without close(), e.g. when its task is aborted, still
+// sends Close if there's a runtime left to run it on
+impl Drop for BinanceCollector {
+    fn drop(&mut self) {
+        let Some(socket) = self.socket.get_mut().take() else {
+            return;
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(close_websocket(socket, "Binance"));
+        }
+    }
+}
+
+impl BinanceCollector {
+    async fn next_price(&self) -> Result<PricePoint, VolatilityError> {
+        // The socket is already gone; this fetch is the one that fails
+        if let Some(e) = self.deferred_failure.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            self.backoff.failed();
+            return Err(e.into());
+        }
         self.ensure_connection().await?;
         
         let mut socket_guard = self.socket.lock().await;
+        let last_trade_at = self.last_trade_at.lock().unwrap_or_else(|e| e.into_inner()).unwrap_or_else(Instant::now);
+        let deadline = self.stale_timeout.map(|timeout| last_trade_at + timeout);
+        let read_deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
         if let Some(socket) = socket_guard.as_mut() {
+            // Take in whatever has already arrived, so bookTicker can skip
+            // straight to the newest quote
+            let failure = self.drain_ready(socket);
+            if let Some(price_point) = self.take_queued_price() {
+                if let Some(e) = failure {
+                    log::error!("WebSocket error: {}", e);
+                    self.defer_failure(e);
+                    *socket_guard = None;
+                }
+                return Ok(self.delivered(price_point));
+            }
+            if let Some(e) = failure {
+                log::error!("WebSocket error: {}", e);
+                self.backoff.failed();
+                *socket_guard = None;
+                return Err(e.into());
+            }
+            // Set once a ping goes out; any frame back, pong or not, clears it
+            let mut awaiting_pong = false;
             loop {
-                match socket.read_message() {
+                let now = Instant::now();
+                if deadline.is_some_and(|deadline| now >= deadline) {
+                    let timeout = self.stale_timeout.unwrap_or_default();
+                    log::warn!("No Binance trade for {:?}, reconnecting", timeout);
+                    // The socket still works, so tell Binance we're leaving
+                    if let Some(stale) = socket_guard.take() {
+                        close_websocket(stale, "Binance").await;
+                    }
+                    return Err(CollectorError::Timeout(format!("no Binance trade within {:?}", timeout)).into());
+                }
+                if read_deadline.is_some_and(|deadline| now >= deadline) {
+                    let timeout = self.read_timeout.unwrap_or_default();
+                    if self.reconnect_on_read_timeout {
+                        if let Some(socket) = socket_guard.take() {
+                            close_websocket(socket, "Binance").await;
+                        }
+                    }
+                    return Err(CollectorError::Timeout(format!("Binance read timed out after {:?}", timeout)).into());
+                }
+                let wait = [deadline, read_deadline].into_iter()
+                    .flatten()
+                    .map(|deadline| deadline.saturating_duration_since(now))
+                    .fold(self.ping_interval, std::time::Duration::min);
+                // Ping after a quiet spell so Binance doesn't drop the idle
+                // connection. Server pings are answered automatically.
+                let next = match tokio::time::timeout(wait, socket.next()).await {
+                    Ok(next) => next,
+                    Err(_) if [deadline, read_deadline].into_iter().flatten().any(|deadline| Instant::now() >= deadline) => continue,
+                    Err(_) if awaiting_pong => {
+                        // Nothing came back for a whole ping interval, so
+                        // the connection is dead even if TCP hasn't noticed
+                        log::warn!("No pong from Binance within {:?}, reconnecting", self.ping_interval);
+                        self.backoff.failed();
+                        *socket_guard = None;
+                        return Err(CollectorError::Timeout(format!("no Binance pong within {:?}", self.ping_interval)).into());
+                    }
+                    Err(_) => {
+                        log::debug!("No Binance message for {:?}, sending ping", self.ping_interval);
+                        if let Err(e) = socket.send(Message::Ping(Vec::new())).await {
+                            log::error!("Binance ping failed: {}", e);
+                            self.backoff.failed();
+                            *socket_guard = None;
+                            return Err(e.into());
+                        }
+                        awaiting_pong = true;
+                        continue;
+                    }
+                };
+                awaiting_pong = false;
+                match next.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
                     Ok(Message::Text(msg)) => {
-                        log::debug!("Received message: {}", msg);
-                        
-                        // Try to parse the message
-                        if let Ok(trade) = serde_json::from_str::<BinanceTradeEvent>(&msg) {
-                            let price_point = PricePoint {
-                                timestamp: DateTime::from_timestamp(trade.timestamp / 1000, 0)
-                                    .unwrap_or_else(|| Utc::now()),
-                                price: trade.price.parse()?,
-                                source: "Binance".to_string(),
-                            };
-                            log::debug!("Parsed price point: {:?}", price_point);
-                            return Ok(price_point);
+                        self.queue_message(msg);
+                        let failure = self.drain_ready(socket);
+                        match (self.take_queued_price(), failure) {
+                            (price_point, Some(e)) => {
+                                log::error!("WebSocket error: {}", e);
+                                *socket_guard = None;
+                                let Some(price_point) = price_point else {
+                                    self.backoff.failed();
+                                    return Err(e.into());
+                                };
+                                self.defer_failure(e);
+                                return Ok(self.delivered(price_point));
+                            }
+                            (Some(price_point), None) => return Ok(self.delivered(price_point)),
+                            (None, None) => continue,
                         }
                     }
                     Ok(msg) => {
@@ -147,6 +1063,7 @@ impl PriceCollector for BinanceCollector {
                     }
                     Err(e) => {
                         log::error!("WebSocket error: {}", e);
+                        self.backoff.failed();
                         // Clear the socket so we'll reconnect next time
                         *socket_guard = None;
                         return Err(e.into());
@@ -154,55 +1071,1648 @@ impl PriceCollector for BinanceCollector {
                 }
             }
         } else {
-            return Err("WebSocket connection not established".into());
+            Err(VolatilityError::NotConnected)
         }
     }
-}
 
-pub struct PriceAggregator {
-    binance: BinanceCollector,
-    #[cfg(feature = "uniswap")]
-    uniswap: Option<UniswapCollector>,
-}
+    fn queue_message(&self, msg: String) {
+        let mut queued = self.queued.lock().unwrap_or_else(|e| e.into_inner());
+        queued.push_back(msg);
+        if queued.len() > MAX_QUEUED_MESSAGES {
+            queued.pop_front();
+            log::debug!("Binance message queue full, dropping the oldest");
+        }
+    }
 
-impl PriceAggregator {
-    #[cfg(not(feature = "uniswap"))]
-    pub fn new(binance: BinanceCollector) -> Self {
-        Self { binance }
+    // Queue every message that is already waiting on the socket, without
+    // blocking. Returns the error that ended the drain, if the socket failed.
+    fn drain_ready(&self, socket: &mut AsyncWsStream) -> Option<tungstenite::Error> {
+        let failure = loop {
+            match socket.next().now_or_never() {
+                None => break None,
+                Some(Some(Ok(Message::Text(msg)))) => self.queue_message(msg),
+                Some(Some(Ok(_))) => {}
+                Some(Some(Err(e))) => break Some(e),
+                Some(None) => break Some(tungstenite::Error::ConnectionClosed),
+            }
+        };
+        self.check_newest_skew();
+        failure
     }
 
-    #[cfg(feature = "uniswap")]
-    pub fn new(binance: BinanceCollector, uniswap: Option<UniswapCollector>) -> Self {
-        Self { binance, uniswap }
-    }
-
-    pub async fn get_aggregated_price(&self) -> Result<PricePoint, Box<dyn Error>> {
-        #[cfg(feature = "uniswap")]
-        if let Some(uniswap) = &self.uniswap {
-            match uniswap.get_latest_price().await {
-                Ok(uni_price) => {
-                    match self.binance.get_latest_price().await {
-                        Ok(bin_price) => {
-                            return Ok(PricePoint {
-                                timestamp: Utc::now(),
-                                price: (uni_price.price + bin_price.price) / 2.0,
-                                source: "Aggregated".to_string(),
-                            });
+    // Only the newest queued message was just received; older ones have
+    // been waiting, and measuring them would read a backlog as clock skew
+    fn check_newest_skew(&self) {
+        let queued = self.queued.lock().unwrap_or_else(|e| e.into_inner());
+        let exchange_time = queued.back()
+            .and_then(|msg| serde_json::from_str::<BinanceEventTime>(msg).ok())
+            .and_then(|event| event.timestamp)
+            .and_then(DateTime::from_timestamp_millis);
+        if let Some(exchange_time) = exchange_time {
+            self.check_clock_skew(exchange_time, Utc::now());
+        }
+    }
+
+    // The newest queued price, dropping the rest of the backlog. Handing
+    // out older ones first would leave each fetch further behind the feed.
+    fn take_queued_price(&self) -> Option<PricePoint> {
+        let mut queued = self.queued.lock().unwrap_or_else(|e| e.into_inner());
+        let mut newest = None;
+        let mut superseded = 0;
+        while let Some(msg) = queued.pop_front() {
+            let Some(price_point) = self.accept_message(&msg) else {
+                continue;
+            };
+            superseded += newest.is_some() as usize;
+            newest = Some(price_point);
+        }
+        if superseded > 0 {
+            log::debug!("Skipped {} superseded Binance prices", superseded);
+        }
+        newest
+    }
+
+    fn defer_failure(&self, e: tungstenite::Error) {
+        *self.deferred_failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+    }
+
+    fn delivered(&self, price_point: PricePoint) -> PricePoint {
+        self.backoff.reset();
+        self.mark_trade();
+        price_point
+    }
+
+    // The price in one text message, if it carries one that survives warmup
+    fn accept_message(&self, msg: &str) -> Option<PricePoint> {
+        log::debug!("Received message: {}", msg);
+
+        // One malformed trade shouldn't fail the whole fetch
+        let price_point = match self.parse_trade(msg) {
+            Ok(Some(price_point)) => price_point,
+            Ok(None) => return None,
+            Err(e) => {
+                log::warn!("Skipping Binance message: {}", e);
+                return None;
+            }
+        };
+        if self.warmup.should_discard() {
+            log::debug!("Discarding warmup price point: {:?}", price_point);
+            return None;
+        }
+        log::debug!("Parsed price point: {:?}", price_point);
+        Some(price_point)
+    }
+}
+
+// Closed candles from Binance's kline stream, for the range-based estimators
+pub struct BinanceKlineCollector {
+    websocket_url: String,
+    symbol: String,
+    // Binance interval name, e.g. "1m"
+    interval: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    proxy: Option<WsProxy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceKlineEvent {
+    #[serde(rename = "k")]
+    kline: BinanceKline,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceKline {
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "c")]
+    close: String,
+    // Whether this is the final update for the bar
+    #[serde(rename = "x")]
+    closed: bool,
+}
+
+impl BinanceKlineCollector {
+    pub fn new(websocket_url: String, symbol: &str, interval: &str) -> Self {
+        Self {
+            websocket_url,
+            symbol: symbol.to_lowercase(),
+            interval: interval.to_string(),
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            proxy: None,
+        }
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: WsProxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let mut ws_stream = connect_websocket(&Url::parse(&self.websocket_url)?, self.proxy.as_ref()).await?;
+        let subscribe_msg = serde_json::json!({
+            "method": "SUBSCRIBE",
+            "params": [format!("{}@kline_{}", self.symbol, self.interval)],
+            "id": 1,
+        }).to_string();
+        log::debug!("Sending kline subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg)).await?;
+        Ok(ws_stream)
+    }
+
+    // Wait for the next bar to close. In-progress updates are skipped.
+    pub async fn next_bar(&self) -> Result<OhlcPoint, VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.reconnect_log.attempt("Binance klines");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("Binance klines");
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("Binance klines", &e);
+                    return Err(e);
+                }
+            }
+        }
+
+        let socket = socket_guard.as_mut().ok_or(VolatilityError::NotConnected)?;
+        loop {
+            match socket.next().await.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                Ok(Message::Text(msg)) => {
+                    let Ok(event) = serde_json::from_str::<BinanceKlineEvent>(&msg) else {
+                        log::debug!("Ignoring non-kline message: {}", msg);
+                        continue;
+                    };
+                    if !event.kline.closed {
+                        continue;
+                    }
+                    let kline = event.kline;
+                    return Ok(OhlcPoint {
+                        timestamp: DateTime::from_timestamp_millis(kline.open_time).unwrap_or_else(Utc::now),
+                        open: kline.open.parse()?,
+                        high: kline.high.parse()?,
+                        low: kline.low.parse()?,
+                        close: kline.close.parse()?,
+                        source: format!("Binance:{}", self.symbol.to_uppercase()),
+                    });
+                }
+                Ok(msg) => log::debug!("Received non-text message: {:?}", msg),
+                Err(e) => {
+                    log::error!("Kline WebSocket error: {}", e);
+                    *socket_guard = None;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+pub struct GeminiCollector {
+    websocket_url: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+}
+
+// Trade update from Gemini's v2 market data feed. The feed also carries
+// order book and heartbeat messages, which are distinguished by `type`.
+#[derive(Debug, Deserialize)]
+struct GeminiTradeEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    price: String,
+    quantity: Option<String>,
+    timestamp: i64,
+}
+
+impl GeminiCollector {
+    pub fn new(websocket_url: String) -> Self {
+        Self {
+            websocket_url,
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+        }
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    // Skip this many points after every (re)connection
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.backoff.wait().await;
+            self.reconnect_log.attempt("Gemini");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("Gemini");
+                    self.warmup.reset();
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("Gemini", &e);
+                    self.backoff.failed();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let mut ws_stream = connect_websocket(&Url::parse(&self.websocket_url)?, None).await?;
+
+        // Gemini only streams trades as part of the l2 subscription
+        let subscribe_msg = r#"{"type": "subscribe", "subscriptions": [{"name": "l2", "symbols": ["ETHUSD"]}]}"#;
+        log::debug!("Sending subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg.into())).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+#[async_trait]
+impl PriceCollector for GeminiCollector {
+    fn name(&self) -> &str {
+        "Gemini"
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnect_log.reconnects()
+    }
+
+    // Send a Close frame and drop the connection
+    async fn close(&self) {
+        if let Some(socket) = self.socket.lock().await.take() {
+            close_websocket(socket, "Gemini").await;
+        }
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        self.ensure_connection().await?;
+
+        let mut socket_guard = self.socket.lock().await;
+        if let Some(socket) = socket_guard.as_mut() {
+            loop {
+                match socket.next().await.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                    Ok(Message::Text(msg)) => {
+                        log::debug!("Received Gemini message: {}", msg);
+
+                        // Skip order book updates and heartbeats
+                        if let Ok(trade) = serde_json::from_str::<GeminiTradeEvent>(&msg) {
+                            if trade.event_type != "trade" {
+                                continue;
+                            }
+                            let price_point = PricePoint {
+                                timestamp: DateTime::from_timestamp_millis(trade.timestamp)
+                                    .unwrap_or_else(Utc::now),
+                                price: trade.price.parse()?,
+                                source: "Gemini".to_string(),
+                                spread: None,
+                                volume: trade.quantity.as_deref().and_then(|q| q.parse().ok()),
+                            };
+                            if self.warmup.should_discard() {
+                                log::debug!("Discarding warmup price point: {:?}", price_point);
+                                continue;
+                            }
+                            log::debug!("Parsed price point: {:?}", price_point);
+                            self.backoff.reset();
+                            return Ok(price_point);
+                        }
+                    }
+                    Ok(msg) => {
+                        log::debug!("Received non-text message: {:?}", msg);
+                    }
+                    Err(e) => {
+                        log::error!("Gemini WebSocket error: {}", e);
+                        self.backoff.failed();
+                        // Clear the socket so we'll reconnect next time
+                        *socket_guard = None;
+                        return Err(e.into());
+                    }
+                }
+            }
+        } else {
+            Err(VolatilityError::NotConnected)
+        }
+    }
+}
+
+pub struct OkxCollector {
+    websocket_url: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+    ping_interval: std::time::Duration,
+}
+
+// OKX drops connections that are silent for 30 seconds, so by default we
+// send a text "ping" once the socket has been idle for this long
+const OKX_PING_AFTER: std::time::Duration = std::time::Duration::from_secs(25);
+
+// Push message on the OKX `trades` channel. Each message carries one or
+// more trades in its `data` array.
+#[derive(Debug, Deserialize)]
+struct OkxTradeMessage {
+    data: Vec<OkxTrade>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OkxTrade {
+    px: String,
+    sz: Option<String>,
+    ts: String,
+}
+
+impl OkxCollector {
+    pub fn new(websocket_url: String) -> Self {
+        Self {
+            websocket_url,
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+            ping_interval: OKX_PING_AFTER,
+        }
+    }
+
+    // Keep this under OKX's 30 second idle limit
+    pub fn with_ping_interval(mut self, interval: std::time::Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    // Skip this many points after every (re)connection
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.backoff.wait().await;
+            self.reconnect_log.attempt("OKX");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("OKX");
+                    self.warmup.reset();
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("OKX", &e);
+                    self.backoff.failed();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let mut ws_stream = connect_websocket(&Url::parse(&self.websocket_url)?, None).await?;
+
+        // Subscribe to trade channel
+        let subscribe_msg = r#"{"op": "subscribe", "args": [{"channel": "trades", "instId": "ETH-USDT"}]}"#;
+        log::debug!("Sending subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg.into())).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+#[async_trait]
+impl PriceCollector for OkxCollector {
+    fn name(&self) -> &str {
+        "OKX"
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        self.ensure_connection().await?;
+
+        let mut socket_guard = self.socket.lock().await;
+        let Some(socket) = socket_guard.as_mut() else {
+            return Err(VolatilityError::NotConnected);
+        };
+        loop {
+            // A quiet spell inside one long read needs pinging too, or OKX
+            // drops the connection while we wait for the next trade
+            let next = match tokio::time::timeout(self.ping_interval, socket.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    log::debug!("No OKX message for {:?}, sending ping", self.ping_interval);
+                    if let Err(e) = socket.send(Message::Text("ping".into())).await {
+                        log::error!("OKX ping failed: {}", e);
+                        self.backoff.failed();
+                        *socket_guard = None;
+                        return Err(e.into());
+                    }
+                    continue;
+                }
+            };
+            match next.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                Ok(Message::Text(msg)) => {
+                    if msg == "pong" {
+                        log::debug!("Received OKX pong");
+                        continue;
+                    }
+                    log::debug!("Received OKX message: {}", msg);
+
+                    // Subscription acks and errors have no `data` field
+                    if let Ok(message) = serde_json::from_str::<OkxTradeMessage>(&msg) {
+                        // The last entry is the most recent trade
+                        if let Some(trade) = message.data.last() {
+                            let price_point = PricePoint {
+                                timestamp: DateTime::from_timestamp_millis(trade.ts.parse()?)
+                                    .unwrap_or_else(Utc::now),
+                                price: trade.px.parse()?,
+                                source: "OKX".to_string(),
+                                spread: None,
+                                volume: trade.sz.as_deref().and_then(|sz| sz.parse().ok()),
+                            };
+                            if self.warmup.should_discard() {
+                                log::debug!("Discarding warmup price point: {:?}", price_point);
+                                continue;
+                            }
+                            log::debug!("Parsed price point: {:?}", price_point);
+                            self.backoff.reset();
+                            return Ok(price_point);
+                        }
+                    }
+                }
+                Ok(msg) => {
+                    log::debug!("Received non-text message: {:?}", msg);
+                }
+                Err(e) => {
+                    log::error!("OKX WebSocket error: {}", e);
+                    self.backoff.failed();
+                    // Clear the socket so we'll reconnect next time
+                    *socket_guard = None;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+pub struct HtxCollector {
+    websocket_url: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+}
+
+// HTX (formerly Huobi) market messages. Every frame is GZIP-compressed JSON
+// carrying either a server ping, a subscription ack, or a trade tick.
+#[derive(Debug, Deserialize)]
+struct HtxMessage {
+    ping: Option<u64>,
+    tick: Option<HtxTradeTick>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtxTradeTick {
+    data: Vec<HtxTrade>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtxTrade {
+    price: f64,
+    amount: Option<f64>,
+    ts: i64,
+}
+
+impl HtxCollector {
+    pub fn new(websocket_url: String) -> Self {
+        Self {
+            websocket_url,
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+        }
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    // Skip this many points after every (re)connection
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.backoff.wait().await;
+            self.reconnect_log.attempt("HTX");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("HTX");
+                    self.warmup.reset();
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("HTX", &e);
+                    self.backoff.failed();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let mut ws_stream = connect_websocket(&Url::parse(&self.websocket_url)?, None).await?;
+
+        // Subscribe to trade details
+        let subscribe_msg = r#"{"sub": "market.ethusdt.trade.detail", "id": "eth-volatility"}"#;
+        log::debug!("Sending subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg.into())).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+fn gunzip(data: &[u8]) -> Result<String, std::io::Error> {
+    let mut decoded = String::new();
+    GzDecoder::new(data).read_to_string(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[async_trait]
+impl PriceCollector for HtxCollector {
+    fn name(&self) -> &str {
+        "HTX"
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnect_log.reconnects()
+    }
+
+    // Send a Close frame and drop the connection
+    async fn close(&self) {
+        if let Some(socket) = self.socket.lock().await.take() {
+            close_websocket(socket, "HTX").await;
+        }
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        self.ensure_connection().await?;
+
+        let mut socket_guard = self.socket.lock().await;
+        if let Some(socket) = socket_guard.as_mut() {
+            loop {
+                match socket.next().await.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                    Ok(Message::Binary(data)) => {
+                        let msg = match gunzip(&data) {
+                            Ok(msg) => msg,
+                            Err(e) => {
+                                log::warn!("Failed to decompress HTX frame: {}", e);
+                                continue;
+                            }
+                        };
+                        log::debug!("Received HTX message: {}", msg);
+
+                        let message = match serde_json::from_str::<HtxMessage>(&msg) {
+                            Ok(message) => message,
+                            Err(_) => continue,
+                        };
+
+                        // Answer server heartbeats with the same id or get disconnected
+                        if let Some(ping) = message.ping {
+                            let pong = format!(r#"{{"pong": {}}}"#, ping);
+                            if let Err(e) = socket.send(Message::Text(pong)).await {
+                                log::error!("HTX pong failed: {}", e);
+                                self.backoff.failed();
+                                *socket_guard = None;
+                                return Err(e.into());
+                            }
+                            continue;
+                        }
+
+                        // The last entry is the most recent trade
+                        if let Some(trade) = message.tick.and_then(|tick| tick.data.into_iter().last()) {
+                            let price_point = PricePoint {
+                                timestamp: DateTime::from_timestamp_millis(trade.ts)
+                                    .unwrap_or_else(Utc::now),
+                                price: trade.price,
+                                source: "HTX".to_string(),
+                                spread: None,
+                                volume: trade.amount,
+                            };
+                            if self.warmup.should_discard() {
+                                log::debug!("Discarding warmup price point: {:?}", price_point);
+                                continue;
+                            }
+                            log::debug!("Parsed price point: {:?}", price_point);
+                            self.backoff.reset();
+                            return Ok(price_point);
+                        }
+                    }
+                    Ok(msg) => {
+                        log::debug!("Received non-binary message: {:?}", msg);
+                    }
+                    Err(e) => {
+                        log::error!("HTX WebSocket error: {}", e);
+                        self.backoff.failed();
+                        // Clear the socket so we'll reconnect next time
+                        *socket_guard = None;
+                        return Err(e.into());
+                    }
+                }
+            }
+        } else {
+            Err(VolatilityError::NotConnected)
+        }
+    }
+}
+
+// Coinbase Exchange ticker channel: one message per match, with the best
+// bid/ask at that moment
+#[cfg(feature = "coinbase")]
+pub struct CoinbaseCollector {
+    websocket_url: String,
+    // Product as Coinbase names it, e.g. "ETH-USD"
+    product_id: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+    ping_interval: std::time::Duration,
+}
+
+#[cfg(feature = "coinbase")]
+#[derive(Debug, Deserialize)]
+struct CoinbaseTicker {
+    #[serde(rename = "type")]
+    kind: String,
+    price: Option<String>,
+    time: Option<DateTime<Utc>>,
+    best_bid: Option<String>,
+    best_ask: Option<String>,
+    last_size: Option<String>,
+}
+
+#[cfg(feature = "coinbase")]
+impl CoinbaseCollector {
+    pub fn new(websocket_url: String, product_id: &str) -> Self {
+        Self {
+            websocket_url,
+            product_id: product_id.to_uppercase(),
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+            ping_interval: DEFAULT_PING_INTERVAL,
+        }
+    }
+
+    pub fn with_ping_interval(mut self, interval: std::time::Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    pub fn subscription_message(&self) -> String {
+        serde_json::json!({
+            "type": "subscribe",
+            "product_ids": [self.product_id],
+            "channels": ["ticker"],
+        }).to_string()
+    }
+
+    // A price point from a ticker message; None for subscription
+    // acknowledgements, heartbeats and anything else
+    pub fn parse_ticker(&self, msg: &str) -> Option<PricePoint> {
+        let ticker: CoinbaseTicker = serde_json::from_str(msg).ok()?;
+        if ticker.kind != "ticker" {
+            return None;
+        }
+        let parse = |v: &Option<String>| v.as_deref().and_then(|v| v.parse::<f64>().ok());
+        let spread = match (parse(&ticker.best_bid), parse(&ticker.best_ask)) {
+            (Some(bid), Some(ask)) => Some(ask - bid),
+            _ => None,
+        };
+        Some(PricePoint {
+            timestamp: ticker.time.unwrap_or_else(Utc::now),
+            price: parse(&ticker.price)?,
+            source: format!("Coinbase:{}", self.product_id),
+            spread,
+            volume: parse(&ticker.last_size),
+        })
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.backoff.wait().await;
+            self.reconnect_log.attempt("Coinbase");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("Coinbase");
+                    self.warmup.reset();
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("Coinbase", &e);
+                    self.backoff.failed();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let (mut ws_stream, _) = tokio_tungstenite::connect_async(Url::parse(&self.websocket_url)?).await?;
+
+        let subscribe_msg = self.subscription_message();
+        log::debug!("Sending Coinbase subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg)).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+#[cfg(feature = "coinbase")]
+#[async_trait]
+impl PriceCollector for CoinbaseCollector {
+    fn name(&self) -> &str {
+        "Coinbase"
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnect_log.reconnects()
+    }
+
+    async fn close(&self) {
+        if let Some(mut socket) = self.socket.lock().await.take() {
+            if let Err(e) = socket.close(None).await {
+                log::debug!("Error closing Coinbase WebSocket: {}", e);
+            }
+        }
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        self.ensure_connection().await?;
+
+        let mut socket_guard = self.socket.lock().await;
+        let Some(socket) = socket_guard.as_mut() else {
+            return Err(VolatilityError::NotConnected);
+        };
+        loop {
+            let next = match tokio::time::timeout(self.ping_interval, socket.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    log::debug!("No Coinbase message for {:?}, sending ping", self.ping_interval);
+                    if let Err(e) = socket.send(Message::Ping(Vec::new())).await {
+                        log::error!("Coinbase ping failed: {}", e);
+                        self.backoff.failed();
+                        *socket_guard = None;
+                        return Err(e.into());
+                    }
+                    continue;
+                }
+            };
+            match next.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                Ok(Message::Text(msg)) => {
+                    log::debug!("Received Coinbase message: {}", msg);
+                    if let Some(price_point) = self.parse_ticker(&msg) {
+                        if self.warmup.should_discard() {
+                            log::debug!("Discarding warmup price point: {:?}", price_point);
+                            continue;
                         }
-                        Err(e) => {
-                            log::error!("Binance price collection failed: {}", e);
-                            return Ok(uni_price);
+                        self.backoff.reset();
+                        return Ok(price_point);
+                    }
+                }
+                Ok(msg) => {
+                    log::debug!("Received non-text Coinbase message: {:?}", msg);
+                }
+                Err(e) => {
+                    log::error!("Coinbase WebSocket error: {}", e);
+                    self.backoff.failed();
+                    *socket_guard = None;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+// Kraken public trade feed. Trades arrive as arrays rather than objects:
+// [channelID, [[price, volume, time, side, orderType, misc], ...], "trade", pair]
+#[cfg(feature = "kraken")]
+pub struct KrakenCollector {
+    websocket_url: String,
+    // Pair as Kraken names it, e.g. "ETH/USD"
+    pair: String,
+    socket: Mutex<Option<AsyncWsStream>>,
+    reconnect_log: ReconnectLogger,
+    backoff: ReconnectBackoff,
+    warmup: ConnectionWarmup,
+    ping_interval: std::time::Duration,
+}
+
+#[cfg(feature = "kraken")]
+impl KrakenCollector {
+    pub fn new(websocket_url: String, pair: &str) -> Self {
+        Self {
+            websocket_url,
+            pair: pair.to_uppercase(),
+            socket: Mutex::new(None),
+            reconnect_log: ReconnectLogger::new(DEFAULT_RECONNECT_LOG_EVERY),
+            backoff: ReconnectBackoff::new(DEFAULT_RECONNECT_BASE, DEFAULT_RECONNECT_MAX),
+            warmup: ConnectionWarmup::new(0),
+            ping_interval: DEFAULT_PING_INTERVAL,
+        }
+    }
+
+    pub fn with_ping_interval(mut self, interval: std::time::Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    pub fn with_reconnect_backoff(mut self, base: std::time::Duration, max: std::time::Duration) -> Self {
+        self.backoff = ReconnectBackoff::new(base, max);
+        self
+    }
+
+    pub fn with_reconnect_log_every(mut self, every: u32) -> Self {
+        self.reconnect_log = ReconnectLogger::new(every);
+        self
+    }
+
+    pub fn with_warmup_ticks(mut self, ticks: u32) -> Self {
+        self.warmup = ConnectionWarmup::new(ticks);
+        self
+    }
+
+    pub fn subscription_message(&self) -> String {
+        serde_json::json!({
+            "event": "subscribe",
+            "pair": [self.pair],
+            "subscription": { "name": "trade" },
+        }).to_string()
+    }
+
+    // The first trade of a trade message; None for event objects such as
+    // heartbeats and subscription status
+    pub fn parse_trade(&self, msg: &str) -> Option<PricePoint> {
+        let value: serde_json::Value = serde_json::from_str(msg).ok()?;
+        let frame = value.as_array()?;
+        if frame.get(2).and_then(|v| v.as_str()) != Some("trade") {
+            return None;
+        }
+        let trade = frame.get(1)?.as_array()?.first()?.as_array()?;
+        let field = |i: usize| trade.get(i).and_then(|v| v.as_str()).and_then(|v| v.parse::<f64>().ok());
+        let timestamp = field(2)
+            .and_then(|secs| DateTime::from_timestamp_millis((secs * 1000.0).round() as i64))
+            .unwrap_or_else(Utc::now);
+        Some(PricePoint {
+            timestamp,
+            price: field(0)?,
+            source: format!("Kraken:{}", self.pair),
+            spread: None,
+            volume: field(1),
+        })
+    }
+
+    async fn ensure_connection(&self) -> Result<(), VolatilityError> {
+        let mut socket_guard = self.socket.lock().await;
+        if socket_guard.is_none() {
+            self.backoff.wait().await;
+            self.reconnect_log.attempt("Kraken");
+            match self.open_connection().await {
+                Ok(ws_stream) => {
+                    self.reconnect_log.connected("Kraken");
+                    self.warmup.reset();
+                    *socket_guard = Some(ws_stream);
+                }
+                Err(e) => {
+                    self.reconnect_log.failed("Kraken", &e);
+                    self.backoff.failed();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_connection(&self) -> Result<AsyncWsStream, VolatilityError> {
+        let (mut ws_stream, _) = tokio_tungstenite::connect_async(Url::parse(&self.websocket_url)?).await?;
+
+        let subscribe_msg = self.subscription_message();
+        log::debug!("Sending Kraken subscription message: {}", subscribe_msg);
+        ws_stream.send(Message::Text(subscribe_msg)).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+#[cfg(feature = "kraken")]
+#[async_trait]
+impl PriceCollector for KrakenCollector {
+    fn name(&self) -> &str {
+        "Kraken"
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnect_log.reconnects()
+    }
+
+    async fn close(&self) {
+        if let Some(mut socket) = self.socket.lock().await.take() {
+            if let Err(e) = socket.close(None).await {
+                log::debug!("Error closing Kraken WebSocket: {}", e);
+            }
+        }
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        self.ensure_connection().await?;
+
+        let mut socket_guard = self.socket.lock().await;
+        let Some(socket) = socket_guard.as_mut() else {
+            return Err(VolatilityError::NotConnected);
+        };
+        loop {
+            let next = match tokio::time::timeout(self.ping_interval, socket.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    log::debug!("No Kraken message for {:?}, sending ping", self.ping_interval);
+                    if let Err(e) = socket.send(Message::Ping(Vec::new())).await {
+                        log::error!("Kraken ping failed: {}", e);
+                        self.backoff.failed();
+                        *socket_guard = None;
+                        return Err(e.into());
+                    }
+                    continue;
+                }
+            };
+            match next.unwrap_or(Err(tungstenite::Error::ConnectionClosed)) {
+                Ok(Message::Text(msg)) => {
+                    log::debug!("Received Kraken message: {}", msg);
+                    if let Some(price_point) = self.parse_trade(&msg) {
+                        if self.warmup.should_discard() {
+                            log::debug!("Discarding warmup price point: {:?}", price_point);
+                            continue;
                         }
+                        self.backoff.reset();
+                        return Ok(price_point);
                     }
                 }
+                Ok(msg) => {
+                    log::debug!("Received non-text Kraken message: {:?}", msg);
+                }
                 Err(e) => {
-                    log::error!("Uniswap price collection failed: {}", e);
-                    return self.binance.get_latest_price().await;
+                    log::error!("Kraken WebSocket error: {}", e);
+                    self.backoff.failed();
+                    *socket_guard = None;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+// How prices from several sources are blended into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationStrategy {
+    // Plain average, ignoring liquidity tiers
+    Mean,
+    // Middle price (mean of the middle two for an even count); robust to
+    // a single bad source once there are three or more
+    Median,
+    // Average weighted by liquidity tier, equal weights when none are set
+    WeightedMean,
+    // Average weighted by each source's reported traded volume; sources
+    // without one count as an average-volume source
+    VolumeWeighted,
+}
+
+impl FromStr for AggregationStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mean" => Ok(Self::Mean),
+            "median" => Ok(Self::Median),
+            "weighted" | "weighted_mean" | "weightedmean" => Ok(Self::WeightedMean),
+            "volume" | "volume_weighted" | "volumeweighted" => Ok(Self::VolumeWeighted),
+            other => Err(format!("unknown aggregation strategy: {}", other)),
+        }
+    }
+}
+
+// What to do with sources that stray too far from the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DivergenceAction {
+    // Leave them out of the blend
+    #[default]
+    Drop,
+    // Blend as usual but log every source's price
+    Warn,
+}
+
+impl FromStr for DivergenceAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "drop" => Ok(Self::Drop),
+            "warn" => Ok(Self::Warn),
+            other => Err(format!("unknown divergence action: {}", other)),
+        }
+    }
+}
+
+// Which time a blended price is stamped with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AggregationTimestamp {
+    // When the blend was made
+    #[default]
+    Now,
+    // The oldest of the blended sources' timestamps
+    Oldest,
+    // The newest of the blended sources' timestamps
+    Newest,
+}
+
+impl FromStr for AggregationTimestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "now" => Ok(Self::Now),
+            "oldest" | "min" => Ok(Self::Oldest),
+            "newest" | "max" => Ok(Self::Newest),
+            other => Err(format!("unknown aggregation timestamp mode: {}", other)),
+        }
+    }
+}
+
+pub type BoxedCollector = Box<dyn PriceCollector + Send + Sync>;
+
+pub struct PriceAggregator {
+    strategy: AggregationStrategy,
+    collectors: Vec<BoxedCollector>,
+    min_sources: usize,
+    source_stats: std::sync::Mutex<HashMap<String, SourceStats>>,
+    // Lowercased source name -> liquidity tier (1 = deepest market)
+    liquidity_tiers: HashMap<String, u8>,
+    // Lowercased source name -> correction added to that source's timestamps
+    time_offsets: HashMap<String, Duration>,
+    // Individual source prices from the most recent fetch
+    source_prices: std::sync::Mutex<Vec<PricePoint>>,
+    // Largest fraction a source may sit from the median of all sources
+    max_divergence: Option<f64>,
+    divergence_action: DivergenceAction,
+    timestamp_mode: AggregationTimestamp,
+    // Timestamp of the previous blend, which the next may not go behind
+    last_blended_at: std::sync::Mutex<Option<DateTime<Utc>>>,
+    circuit_breaker: Option<CircuitBreaker>,
+    // Source name -> breaker state, only while a breaker is configured
+    breakers: std::sync::Mutex<HashMap<String, BreakerState>>,
+}
+
+pub const DEFAULT_BREAKER_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Leave a degraded source out for a while rather than wait on it every
+// tick. A source trips after `failures` bad fetches in a row, bad meaning
+// an error or, with `max_latency`, a price that took longer than that. It
+// is then skipped for `cooldown` and probed once: a good fetch closes the
+// breaker, a bad one opens it for another cooldown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub failures: u32,
+    pub cooldown: std::time::Duration,
+    pub max_latency: Option<std::time::Duration>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    // Bad fetches in a row
+    strikes: u32,
+    open_until: Option<Instant>,
+}
+
+// Each tier below the first counts half as much as the one above it
+pub fn liquidity_tier_weight(tier: u8) -> f64 {
+    0.5_f64.powi(tier.saturating_sub(1) as i32)
+}
+
+// Fetch outcomes for one source since startup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceStats {
+    pub source: String,
+    pub attempts: u64,
+    pub successes: u64,
+    // Ticks left out while the source's circuit breaker was open
+    pub skipped: u64,
+}
+
+impl SourceStats {
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.successes as f64 / self.attempts as f64)
+    }
+}
+
+impl PriceAggregator {
+    pub fn new(collectors: Vec<BoxedCollector>, strategy: AggregationStrategy) -> Self {
+        Self {
+            strategy,
+            collectors,
+            min_sources: 1,
+            source_stats: std::sync::Mutex::new(HashMap::new()),
+            liquidity_tiers: HashMap::new(),
+            time_offsets: HashMap::new(),
+            source_prices: std::sync::Mutex::new(Vec::new()),
+            max_divergence: None,
+            divergence_action: DivergenceAction::Drop,
+            timestamp_mode: AggregationTimestamp::Now,
+            last_blended_at: std::sync::Mutex::new(None),
+            circuit_breaker: None,
+            breakers: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Single-venue convenience: Binance only, passed straight through
+    pub fn binance_only(binance: BinanceCollector) -> Self {
+        Self::new(vec![Box::new(binance)], AggregationStrategy::WeightedMean)
+    }
+
+    pub fn with_collector(mut self, collector: BoxedCollector) -> Self {
+        self.collectors.push(collector);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: AggregationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // Require at least this many sources to report before producing a price
+    pub fn with_min_sources(mut self, min_sources: usize) -> Self {
+        self.min_sources = min_sources;
+        self
+    }
+
+    // Weight sources by liquidity tier instead of equally, so deeper
+    // markets dominate the blend
+    pub fn with_liquidity_tiers(mut self, tiers: HashMap<String, u8>) -> Self {
+        self.liquidity_tiers = tiers.into_iter()
+            .map(|(source, tier)| (source.to_lowercase(), tier))
+            .collect();
+        self
+    }
+
+    // Flag sources more than `max_fraction` (e.g. 0.02 for 2%) from the
+    // median source price, usually a stale or broken feed
+    pub fn with_max_divergence(mut self, max_fraction: Option<f64>, action: DivergenceAction) -> Self {
+        self.max_divergence = max_fraction;
+        self.divergence_action = action;
+        self
+    }
+
+    // Stamp blended prices with a source time instead of the fetch time,
+    // for backfilled or replayed sources whose points aren't current
+    pub fn with_timestamp_mode(mut self, mode: AggregationTimestamp) -> Self {
+        self.timestamp_mode = mode;
+        self
+    }
+
+    // Skip sources that keep failing or answering slowly; None queries every
+    // source every tick. Changing it starts every source afresh.
+    pub fn with_circuit_breaker(mut self, breaker: Option<CircuitBreaker>) -> Self {
+        if self.circuit_breaker != breaker {
+            self.breakers.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+        self.circuit_breaker = breaker;
+        self
+    }
+
+    // Shift each source's timestamps onto a common UTC timeline, for venues
+    // that stamp trades in local time or with a known clock offset
+    pub fn with_time_offsets(mut self, offsets: HashMap<String, Duration>) -> Self {
+        self.time_offsets = offsets.into_iter()
+            .map(|(source, offset)| (source.to_lowercase(), offset))
+            .collect();
+        self
+    }
+
+    // Names of the sources this aggregator will query
+    pub fn active_sources(&self) -> Vec<&str> {
+        self.collectors.iter().map(|c| c.name()).collect()
+    }
+
+    // When liquidity tiers are configured, every active source needs one
+    pub fn validate(&self) -> Result<(), String> {
+        if self.liquidity_tiers.is_empty() {
+            return Ok(());
+        }
+        let missing: Vec<_> = self.active_sources()
+            .into_iter()
+            .filter(|source| !self.liquidity_tiers.contains_key(&source.to_lowercase()))
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("no liquidity tier configured for source(s): {}", missing.join(", ")))
+        }
+    }
+
+    fn weight_of(&self, price: &PricePoint) -> f64 {
+        // Tiers are keyed by venue, so "Binance:ETHUSDC" uses the "binance" tier
+        let venue = price.source.split(':').next().unwrap_or(&price.source);
+        self.liquidity_tiers
+            .get(&venue.to_lowercase())
+            .map_or(1.0, |tier| liquidity_tier_weight(*tier))
+    }
+
+    // Shut down open connections before exit
+    pub async fn close(&self) {
+        join_all(self.collectors.iter().map(|c| c.close())).await;
+    }
+
+    // Reconnects reported by the named source, zero if it isn't configured
+    pub fn reconnects(&self, source: &str) -> u64 {
+        self.collectors.iter()
+            .filter(|c| c.name().eq_ignore_ascii_case(source))
+            .map(|c| c.reconnects())
+            .sum()
+    }
+
+    // Consecutive failed fetches reported by the named source
+    pub fn consecutive_errors(&self, source: &str) -> u32 {
+        self.collectors.iter()
+            .filter(|c| c.name().eq_ignore_ascii_case(source))
+            .map(|c| c.consecutive_errors())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Per-source fetch outcomes, sorted by source name
+    pub fn source_stats(&self) -> Vec<SourceStats> {
+        let stats = self.source_stats.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<_> = stats.values().cloned().collect();
+        stats.sort_by(|a, b| a.source.cmp(&b.source));
+        stats
+    }
+
+    // Each source's own price from the last fetch, before blending. Set even
+    // when too few sources reported for an aggregate.
+    pub fn source_prices(&self) -> Vec<PricePoint> {
+        self.source_prices.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub async fn get_aggregated_price(&self) -> Result<PricePoint, VolatilityError> {
+        let mut prices = Vec::new();
+        let mut last_error: Option<VolatilityError> = None;
+
+        // Query every available source at once; failures are logged and dropped
+        let collectors = self.available_collectors();
+        let results = join_all(collectors.iter().map(|c| async move {
+            let started = Instant::now();
+            let result = c.get_latest_price().await;
+            (result, started.elapsed())
+        })).await;
+        for (collector, (result, latency)) in collectors.iter().zip(results) {
+            self.update_breaker(collector.name(), result.is_ok(), latency);
+            self.record_result(collector.name(), result, &mut prices, &mut last_error);
+        }
+        *self.source_prices.lock().unwrap_or_else(|e| e.into_inner()) = prices.clone();
+
+        if let Some(max_fraction) = self.max_divergence {
+            self.handle_divergence(&mut prices, max_fraction);
+        }
+
+        // Skip the tick rather than let too few sources drive the estimate,
+        // counting only those that survived the divergence check
+        if !prices.is_empty() && prices.len() < self.min_sources {
+            return Err(VolatilityError::InsufficientSources { got: prices.len(), need: self.min_sources });
+        }
+
+        // A single surviving source is passed through unchanged
+        match prices.len() {
+            0 => Err(last_error.unwrap_or(VolatilityError::InsufficientSources { got: 0, need: self.min_sources.max(1) })),
+            1 => Ok(prices.remove(0)),
+            _ => Ok(PricePoint {
+                timestamp: self.blended_timestamp(&prices),
+                price: self.combine(&prices),
+                source: "Aggregated".to_string(),
+                spread: mean_spread(&prices),
+                volume: total_volume(&prices),
+            }),
+        }
+    }
+
+    // Never earlier than the previous blend: with Oldest or Newest a lagging
+    // source, or one dropping out, would otherwise step the series back in
+    // time and hand the calculator a negative interval
+    fn blended_timestamp(&self, prices: &[PricePoint]) -> DateTime<Utc> {
+        let timestamps = prices.iter().map(|p| p.timestamp);
+        let timestamp = match self.timestamp_mode {
+            AggregationTimestamp::Now => None,
+            AggregationTimestamp::Oldest => timestamps.min(),
+            AggregationTimestamp::Newest => timestamps.max(),
+        }
+        .unwrap_or_else(Utc::now);
+        let mut last = self.last_blended_at.lock().unwrap_or_else(|e| e.into_inner());
+        let timestamp = last.map_or(timestamp, |last| timestamp.max(last));
+        *last = Some(timestamp);
+        timestamp
+    }
+
+    fn combine(&self, prices: &[PricePoint]) -> f64 {
+        match self.strategy {
+            AggregationStrategy::Mean => prices.iter().map(|p| p.price).sum::<f64>() / prices.len() as f64,
+            AggregationStrategy::Median => median(prices.iter().map(|p| p.price).collect()),
+            AggregationStrategy::WeightedMean => self.weighted_mean(prices),
+            AggregationStrategy::VolumeWeighted => volume_weighted_mean(prices),
+        }
+    }
+
+    // Warn about, and with DivergenceAction::Drop remove, sources too far
+    // from the median. With two sources the median sits halfway, so both
+    // diverge at once; nothing says which is wrong, and the one in the
+    // deepest liquidity tier (the first configured on a tie) is kept.
+    fn handle_divergence(&self, prices: &mut Vec<PricePoint>, max_fraction: f64) {
+        if prices.len() < 2 {
+            return;
+        }
+        let center = median(prices.iter().map(|p| p.price).collect());
+        let diverges = |p: &PricePoint| (p.price / center - 1.0).abs() > max_fraction;
+        if !prices.iter().any(diverges) {
+            return;
+        }
+
+        let report: Vec<String> = prices.iter()
+            .map(|p| format!("{} {:.2}{}", p.source, p.price, if diverges(p) { " (diverging)" } else { "" }))
+            .collect();
+        log::warn!(
+            "Sources disagree by more than {:.2}% around median {:.2}: {}",
+            max_fraction * 100.0,
+            center,
+            report.join(", ")
+        );
+        if self.divergence_action == DivergenceAction::Warn {
+            return;
+        }
+
+        if prices.iter().all(diverges) {
+            let mut keep = 0;
+            for (i, price) in prices.iter().enumerate() {
+                if self.weight_of(price) > self.weight_of(&prices[keep]) {
+                    keep = i;
+                }
+            }
+            let kept = prices.swap_remove(keep);
+            log::warn!("No majority among sources, keeping only {}", kept.source);
+            *prices = vec![kept];
+        } else {
+            prices.retain(|p| !diverges(p));
+        }
+    }
+
+    // Equal weights unless liquidity tiers are configured
+    fn weighted_mean(&self, prices: &[PricePoint]) -> f64 {
+        let total_weight: f64 = prices.iter().map(|p| self.weight_of(p)).sum();
+        prices.iter().map(|p| p.price * self.weight_of(p)).sum::<f64>() / total_weight
+    }
+
+    // Sources whose breaker is closed or due a probe. When every breaker is
+    // open they are all queried anyway, so the aggregate never goes dark
+    // just for lack of a healthy source.
+    fn available_collectors(&self) -> Vec<&BoxedCollector> {
+        if self.circuit_breaker.is_none() {
+            return self.collectors.iter().collect();
+        }
+        let now = Instant::now();
+        let (available, skipped): (Vec<_>, Vec<_>) = {
+            let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+            self.collectors.iter().partition(|c| {
+                breakers.get(c.name())
+                    .and_then(|state| state.open_until)
+                    .is_none_or(|until| now >= until)
+            })
+        };
+        if available.is_empty() {
+            return self.collectors.iter().collect();
+        }
+        let mut stats = self.source_stats.lock().unwrap_or_else(|e| e.into_inner());
+        for collector in skipped {
+            stats.entry(collector.name().to_string())
+                .or_insert_with(|| SourceStats { source: collector.name().to_string(), ..Default::default() })
+                .skipped += 1;
+        }
+        available
+    }
+
+    fn update_breaker(&self, name: &str, succeeded: bool, latency: std::time::Duration) {
+        let Some(breaker) = self.circuit_breaker else {
+            return;
+        };
+        let good = succeeded && breaker.max_latency.is_none_or(|max| latency <= max);
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let state = breakers.entry(name.to_string()).or_default();
+        if good {
+            if state.open_until.take().is_some() {
+                log::info!("{} recovered, closing its circuit breaker", name);
+            }
+            state.strikes = 0;
+            return;
+        }
+        state.strikes += 1;
+        if state.strikes >= breaker.failures.max(1) {
+            let reason = if succeeded {
+                format!("answered in {}ms", latency.as_millis())
+            } else {
+                "failed".to_string()
+            };
+            log::warn!(
+                "{} {} ({} bad fetches in a row), skipping it for {}s",
+                name,
+                reason,
+                state.strikes,
+                breaker.cooldown.as_secs()
+            );
+            state.open_until = Some(Instant::now() + breaker.cooldown);
+        }
+    }
+
+    // Count one source's outcome, keeping the price on success and the error otherwise
+    fn record_result(
+        &self,
+        name: &str,
+        result: Result<PricePoint, VolatilityError>,
+        prices: &mut Vec<PricePoint>,
+        last_error: &mut Option<VolatilityError>,
+    ) {
+        {
+            let mut stats = self.source_stats.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = stats.entry(name.to_string()).or_insert_with(|| SourceStats {
+                source: name.to_string(),
+                ..Default::default()
+            });
+            entry.attempts += 1;
+            if result.is_ok() {
+                entry.successes += 1;
+            }
+        }
+
+        match result {
+            Ok(mut price) => {
+                if let Some(offset) = self.time_offsets.get(&name.to_lowercase()) {
+                    price.timestamp += *offset;
                 }
+                prices.push(price);
+            }
+            Err(e) => {
+                log::error!("{} price collection failed: {}", name, e);
+                *last_error = Some(e);
+            }
+        }
+    }
+}
+
+pub(crate) fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+// Weight by reported volume. Sources without a (positive) volume get the
+// mean of the known ones, so with no volumes at all this is a plain mean.
+fn volume_weighted_mean(prices: &[PricePoint]) -> f64 {
+    let known: Vec<f64> = prices.iter()
+        .filter_map(|p| p.volume)
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .collect();
+    let fallback = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+    let weight_of = |p: &PricePoint| p.volume.filter(|v| v.is_finite() && *v > 0.0).unwrap_or(fallback);
+
+    let total_weight: f64 = prices.iter().map(weight_of).sum();
+    prices.iter().map(|p| p.price * weight_of(p)).sum::<f64>() / total_weight
+}
+
+// Combined volume across the sources that report one
+fn total_volume(prices: &[PricePoint]) -> Option<f64> {
+    let volumes: Vec<f64> = prices.iter().filter_map(|p| p.volume).collect();
+    (!volumes.is_empty()).then(|| volumes.iter().sum())
+}
+
+// Average spread across the sources that report one
+fn mean_spread(prices: &[PricePoint]) -> Option<f64> {
+    let spreads: Vec<f64> = prices.iter().filter_map(|p| p.spread).collect();
+    (!spreads.is_empty()).then(|| spreads.iter().sum::<f64>() / spreads.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration as StdDuration;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_jitter_and_resets() {
+        let backoff = ReconnectBackoff::new(StdDuration::from_millis(100), StdDuration::from_millis(500));
+        assert_eq!(backoff.delay(), StdDuration::ZERO);
+
+        // Ceilings 100, 200, 400, then capped at 500; jitter keeps each in [ceiling/2, ceiling]
+        for ceiling in [100, 200, 400, 500, 500] {
+            backoff.failed();
+            for _ in 0..20 {
+                let delay = backoff.delay();
+                let ceiling = StdDuration::from_millis(ceiling);
+                assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?} outside {:?}", delay, ceiling);
             }
         }
 
-        // If Uniswap is not enabled or not configured, use only Binance
-        self.binance.get_latest_price().await
+        backoff.reset();
+        assert_eq!(backoff.delay(), StdDuration::ZERO);
     }
-} 
\ No newline at end of file
+
+    // The USDC/WETH example from Uniswap's docs: USDC (6 decimals) is token0,
+    // so the pool quotes WETH per USDC and inverting gives ~1540.82 USDC per ETH
+    #[cfg(feature = "uniswap")]
+    #[test]
+    fn sqrt_price_converts_to_a_whole_token_price() {
+        let sqrt_price_x96 = U256::from_dec_str("2018382873588440326581633304624437").unwrap();
+        let weth_per_usdc = sqrt_price_to_price(sqrt_price_x96, 6, 18, false);
+        assert!((weth_per_usdc - 0.000649004842701).abs() < 1e-12);
+        let usdc_per_weth = sqrt_price_to_price(sqrt_price_x96, 6, 18, true);
+        assert!((usdc_per_weth - 1540.8205520).abs() < 1e-6, "{}", usdc_per_weth);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use crate::price_collector::SourceStats;
+use crate::volatility::VolatilityCalculator;
+
+// A feed silent for this many update intervals is considered to be in a gap
+const GAP_INTERVALS: i32 = 3;
+
+// Data-quality signals gathered into one view for the `/quality` endpoint
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QualityReport {
+    pub sources: Vec<SourceQuality>,
+    pub rejected_non_finite: u64,
+    // Rejected prices per validator, including the non-finite ones above
+    pub rejections: BTreeMap<String, u64>,
+    pub gap: GapStatus,
+    pub sample_count: usize,
+    pub expected_samples: usize,
+    // Observed samples as a fraction of what the window should hold
+    pub sample_density: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceQuality {
+    pub source: String,
+    pub attempts: u64,
+    pub successes: u64,
+    pub success_rate: Option<f64>,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GapStatus {
+    pub seconds_since_last_price: Option<i64>,
+    pub in_gap: bool,
+    // Longest spacing between consecutive points still in the window
+    pub largest_gap_seconds: Option<i64>,
+}
+
+impl QualityReport {
+    pub fn build(
+        source_stats: Vec<SourceStats>,
+        calculator: &VolatilityCalculator,
+        window: Duration,
+        update_interval: Duration,
+        // The spacing of points in the window: SAMPLE_INTERVAL when set,
+        // otherwise the update interval
+        sample_interval: Duration,
+    ) -> Self {
+        let sources = source_stats.into_iter()
+            .map(|stats| SourceQuality {
+                success_rate: stats.success_rate(),
+                source: stats.source,
+                attempts: stats.attempts,
+                successes: stats.successes,
+                skipped: stats.skipped,
+            })
+            .collect();
+
+        let seconds_since_last_price = calculator.newest_timestamp()
+            .map(|ts| (Utc::now() - ts).num_seconds());
+        let in_gap = seconds_since_last_price
+            .is_some_and(|secs| secs > (update_interval * GAP_INTERVALS).num_seconds());
+
+        let expected_samples = match sample_interval.num_seconds() {
+            secs if secs > 0 => (window.num_seconds() / secs) as usize,
+            _ => 0,
+        };
+        let sample_count = calculator.sample_count();
+
+        Self {
+            sources,
+            rejected_non_finite: calculator.rejected_non_finite(),
+            rejections: calculator.rejections().iter()
+                .map(|(name, count)| (name.to_string(), *count))
+                .collect(),
+            gap: GapStatus {
+                seconds_since_last_price,
+                in_gap,
+                largest_gap_seconds: calculator.largest_gap().map(|gap| gap.num_seconds()),
+            },
+            sample_count,
+            expected_samples,
+            sample_density: (expected_samples > 0).then(|| sample_count as f64 / expected_samples as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_samples_follow_the_sample_interval() {
+        let calculator = VolatilityCalculator::new(Duration::hours(1));
+        let sampled = QualityReport::build(Vec::new(), &calculator, Duration::hours(1), Duration::seconds(5), Duration::minutes(1));
+        assert_eq!(sampled.expected_samples, 60);
+        let unsampled = QualityReport::build(Vec::new(), &calculator, Duration::hours(1), Duration::seconds(5), Duration::seconds(5));
+        assert_eq!(unsampled.expected_samples, 720);
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use crate::config::Config;
+use crate::price_collector::{AggregationStrategy, BoxedCollector, PriceAggregator};
+use crate::error::VolatilityError;
+
+pub type CollectorFactory = Box<dyn Fn(&Config) -> Result<BoxedCollector, VolatilityError> + Send + Sync>;
+
+// Collector factories keyed by the names used in COLLECTORS, so adding a
+// source is a matter of registering one more factory
+#[derive(Default)]
+pub struct CollectorRegistry {
+    factories: BTreeMap<String, CollectorFactory>,
+}
+
+impl CollectorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Names are case-insensitive; registering a name again replaces it
+    pub fn register(
+        mut self,
+        name: &str,
+        factory: impl Fn(&Config) -> Result<BoxedCollector, VolatilityError> + Send + Sync + 'static,
+    ) -> Self {
+        self.factories.insert(name.to_lowercase(), Box::new(factory));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn build(&self, name: &str, config: &Config) -> Result<BoxedCollector, VolatilityError> {
+        let factory = self.factories.get(&name.to_lowercase()).ok_or_else(|| {
+            VolatilityError::Other(format!(
+                "unknown collector `{}`, expected one of: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+        factory(config).map_err(|e| VolatilityError::Other(format!("{} collector: {}", name, e)))
+    }
+
+    // An aggregator over the named collectors, in the order given
+    pub fn build_aggregator(
+        &self,
+        names: &[String],
+        config: &Config,
+        strategy: AggregationStrategy,
+    ) -> Result<PriceAggregator, VolatilityError> {
+        let collectors = names.iter()
+            .map(|name| self.build(name, config))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PriceAggregator::new(collectors, strategy))
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use crate::price_collector::{PriceCollector, PricePoint};
+use crate::error::VolatilityError;
+
+// Returned once every recorded point has been replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayFinished;
+
+impl fmt::Display for ReplayFinished {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "replay file exhausted")
+    }
+}
+
+impl Error for ReplayFinished {}
+
+// The columns written by CsvSink
+#[derive(Deserialize)]
+struct CsvRow {
+    timestamp: DateTime<Utc>,
+    price: f64,
+    source: String,
+}
+
+struct ReplayState {
+    points: VecDeque<PricePoint>,
+    previous: Option<DateTime<Utc>>,
+}
+
+// Feeds prices recorded by CSV_OUTPUT_PATH back through the pipeline, for
+// backtesting the estimator offline. By default points come out as fast as
+// they are asked for; a speedup paces them at their recorded spacing
+// divided by that factor.
+pub struct ReplayCollector {
+    state: Mutex<ReplayState>,
+    speedup: Option<f64>,
+}
+
+impl ReplayCollector {
+    pub fn open(path: &Path) -> Result<Self, VolatilityError> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut points = Vec::new();
+        for row in reader.deserialize::<CsvRow>() {
+            let row = row?;
+            points.push(PricePoint {
+                timestamp: row.timestamp,
+                price: row.price,
+                source: row.source,
+                spread: None,
+                volume: None,
+            });
+        }
+        points.sort_by_key(|p| p.timestamp);
+        log::info!("Loaded {} recorded prices from {}", points.len(), path.display());
+        Ok(Self {
+            state: Mutex::new(ReplayState { points: points.into(), previous: None }),
+            speedup: None,
+        })
+    }
+
+    // Replay at `speedup` times the recorded pace; non-positive means as fast as possible
+    pub fn with_speedup(mut self, speedup: f64) -> Self {
+        self.speedup = (speedup > 0.0).then_some(speedup);
+        self
+    }
+
+    pub async fn remaining(&self) -> usize {
+        self.state.lock().await.points.len()
+    }
+}
+
+#[async_trait]
+impl PriceCollector for ReplayCollector {
+    fn name(&self) -> &str {
+        "Replay"
+    }
+
+    async fn get_latest_price(&self) -> Result<PricePoint, VolatilityError> {
+        let mut state = self.state.lock().await;
+        let point = state.points.pop_front().ok_or(ReplayFinished)?;
+        if let (Some(speedup), Some(previous)) = (self.speedup, state.previous) {
+            if let Ok(gap) = (point.timestamp - previous).to_std() {
+                tokio::time::sleep(gap.div_f64(speedup)).await;
+            }
+        }
+        state.previous = Some(point.timestamp);
+        Ok(point)
+    }
+}
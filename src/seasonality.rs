@@ -0,0 +1,71 @@
+use chrono::{DateTime, Timelike, Utc};
+
+// Returns an hour needs before its factor is trusted
+pub const MIN_BUCKET_RETURNS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    // Squared returns per second of the time they spanned
+    sum: f64,
+    count: u64,
+}
+
+impl Bucket {
+    fn rate(&self) -> Option<f64> {
+        (self.count >= MIN_BUCKET_RETURNS).then(|| self.sum / self.count as f64)
+    }
+}
+
+// Average variance rate for each UTC hour of day, accumulated over every
+// return since startup rather than only the window, to capture crypto's
+// time-of-day volatility pattern
+#[derive(Debug, Clone, Default)]
+pub struct IntradayProfile {
+    buckets: [Bucket; 24],
+}
+
+impl IntradayProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A return ending at `timestamp` that spanned `interval_seconds`
+    pub fn add(&mut self, timestamp: DateTime<Utc>, value: f64, interval_seconds: f64) {
+        if interval_seconds > 0.0 && value.is_finite() {
+            let bucket = &mut self.buckets[timestamp.hour() as usize];
+            bucket.sum += value * value / interval_seconds;
+            bucket.count += 1;
+        }
+    }
+
+    // Undo an add, for a point replaced by a later one with its timestamp
+    pub fn remove(&mut self, timestamp: DateTime<Utc>, value: f64, interval_seconds: f64) {
+        if interval_seconds > 0.0 && value.is_finite() {
+            let bucket = &mut self.buckets[timestamp.hour() as usize];
+            if bucket.count > 0 {
+                bucket.sum = (bucket.sum - value * value / interval_seconds).max(0.0);
+                bucket.count -= 1;
+            }
+        }
+    }
+
+    pub fn returns_in(&self, hour: u32) -> u64 {
+        self.buckets.get(hour as usize).map_or(0, |bucket| bucket.count)
+    }
+
+    // How volatile `hour` typically is against the day as a whole: the
+    // square root of its variance rate over the mean rate of every hour
+    // with enough returns, so uneven sampling across hours doesn't skew
+    // it. Above 1 for hours that usually move more. None until `hour` has
+    // MIN_BUCKET_RETURNS.
+    pub fn factor(&self, hour: u32) -> Option<f64> {
+        let rate = self.buckets.get(hour as usize)?.rate()?;
+        let rates: Vec<f64> = self.buckets.iter().filter_map(Bucket::rate).collect();
+        let overall = rates.iter().sum::<f64>() / rates.len() as f64;
+        (overall > 0.0).then(|| (rate / overall).sqrt())
+    }
+
+    pub fn factor_at(&self, timestamp: DateTime<Utc>) -> Option<f64> {
+        self.factor(timestamp.hour())
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use serde::Serialize;
+use crate::volatility::VolatilityResult;
+use crate::error::VolatilityError;
+
+#[derive(Serialize)]
+struct SeriesRow {
+    timestamp: String,
+    annualized_volatility: f64,
+    sample_count: usize,
+}
+
+impl From<&VolatilityResult> for SeriesRow {
+    fn from(result: &VolatilityResult) -> Self {
+        Self {
+            timestamp: result.timestamp.to_rfc3339(),
+            annualized_volatility: result.annualized_volatility,
+            sample_count: result.sample_count,
+        }
+    }
+}
+
+enum SeriesWriter {
+    Csv(Box<csv::Writer<File>>),
+    Ndjson(File),
+}
+
+// Appends each volatility estimate to a file for plotting the history
+// offline: NDJSON for a .ndjson or .jsonl path, CSV otherwise
+pub struct VolatilitySeriesSink {
+    writer: SeriesWriter,
+}
+
+impl VolatilitySeriesSink {
+    // Appends to an existing file; a CSV header is only written to a new or empty one
+    pub fn open(path: &Path) -> Result<Self, VolatilityError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let is_ndjson = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ndjson") || ext.eq_ignore_ascii_case("jsonl"));
+        if is_ndjson {
+            return Ok(Self { writer: SeriesWriter::Ndjson(file) });
+        }
+        let needs_header = file.metadata()?.len() == 0;
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(file);
+        if needs_header {
+            writer.write_record(["timestamp", "annualized_volatility", "sample_count"])?;
+            writer.flush()?;
+        }
+        Ok(Self { writer: SeriesWriter::Csv(Box::new(writer)) })
+    }
+
+    // Flushed per reading so a crash loses at most the one being written
+    pub fn write(&mut self, result: &VolatilityResult) -> Result<(), VolatilityError> {
+        let row = SeriesRow::from(result);
+        match &mut self.writer {
+            SeriesWriter::Csv(writer) => {
+                writer.serialize(row)?;
+                writer.flush()?;
+            }
+            SeriesWriter::Ndjson(file) => {
+                serde_json::to_writer(&mut *file, &row)?;
+                file.write_all(b"\n")?;
+                file.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use chrono::{TimeZone, Utc};
+
+    fn result(minute: u32, volatility: f64, sample_count: usize) -> VolatilityResult {
+        VolatilityResult {
+            timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 0, minute, 0).unwrap(),
+            annualized_volatility: volatility,
+            annualized_variance: None,
+            sample_count,
+            gap: false,
+            baseline_ratio: None,
+            baseline_percentile: None,
+        }
+    }
+
+    // A fresh path under the system temp directory, unique to this test run
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("eth-volatility-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn csv_header_is_written_once_across_reopens() {
+        let path = temp_path("series.csv");
+        VolatilitySeriesSink::open(&path).unwrap().write(&result(0, 0.5, 10)).unwrap();
+        VolatilitySeriesSink::open(&path).unwrap().write(&result(1, 0.625, 11)).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, concat!(
+            "timestamp,annualized_volatility,sample_count\n",
+            "2026-01-01T00:00:00+00:00,0.5,10\n",
+            "2026-01-01T00:01:00+00:00,0.625,11\n",
+        ));
+    }
+
+    #[test]
+    fn ndjson_extension_writes_one_object_per_line() {
+        let path = temp_path("series.ndjson");
+        VolatilitySeriesSink::open(&path).unwrap().write(&result(0, 0.5, 10)).unwrap();
+        VolatilitySeriesSink::open(&path).unwrap().write(&result(1, 0.625, 11)).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let rows: Vec<serde_json::Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(rows, [
+            serde_json::json!({"timestamp": "2026-01-01T00:00:00+00:00", "annualized_volatility": 0.5, "sample_count": 10}),
+            serde_json::json!({"timestamp": "2026-01-01T00:01:00+00:00", "annualized_volatility": 0.625, "sample_count": 11}),
+        ]);
+    }
+}
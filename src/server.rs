@@ -0,0 +1,248 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use axum::{extract::State, http::{header, HeaderMap, StatusCode}, routing::{get, post}, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::quality::QualityReport;
+use crate::status::StatusReport;
+use crate::volatility::Snapshot;
+
+// State shared between the main loop, which refreshes it every tick, and
+// the HTTP handlers, which only read it
+#[derive(Clone, Default)]
+pub struct AppState {
+    pub quality: Arc<RwLock<QualityReport>>,
+    pub status: Arc<RwLock<StatusReport>>,
+    pub volatility: Arc<RwLock<VolatilityState>>,
+    pub snapshot: Arc<RwLock<Snapshot>>,
+    // Set by `POST /pause`; the main loop skips fetching while it's set
+    pub paused: Arc<AtomicBool>,
+    // Bearer token `POST /pause` and `/resume` require; without one they're
+    // disabled
+    pub control_token: Option<Arc<str>>,
+}
+
+impl AppState {
+    pub fn with_control_token(mut self, token: Option<String>) -> Self {
+        self.control_token = token.map(Arc::from);
+        self
+    }
+
+    // 404 while the control endpoints are disabled, 401 for a missing or
+    // wrong token
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let Some(token) = &self.control_token else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+        let presented = headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if presented == Some(&**token) {
+            Ok(())
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+// A change of the pause flag seen by `PauseWatch::poll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseChange {
+    Paused,
+    Resumed,
+}
+
+// Follows the pause flag from the main loop, reporting each change once so
+// connections are closed on pause and a gap marked on resume
+pub struct PauseWatch {
+    paused: Arc<AtomicBool>,
+    was_paused: bool,
+}
+
+impl PauseWatch {
+    pub fn new(paused: Arc<AtomicBool>) -> Self {
+        Self { paused, was_paused: false }
+    }
+
+    // Whether collection is paused now, and how that changed since the last poll
+    pub fn poll(&mut self) -> (bool, Option<PauseChange>) {
+        let paused = self.paused.load(Ordering::Relaxed);
+        let change = match (self.was_paused, paused) {
+            (false, true) => Some(PauseChange::Paused),
+            (true, false) => Some(PauseChange::Resumed),
+            _ => None,
+        };
+        self.was_paused = paused;
+        (paused, change)
+    }
+}
+
+// Latest estimate for `GET /volatility`. The main loop replaces it after
+// every price fetch, so the handler never touches the calculator itself.
+#[derive(Debug, Clone)]
+pub enum VolatilityState {
+    Ready(VolatilitySnapshot),
+    // Served as a 503 with this reason
+    NotReady(String),
+}
+
+impl Default for VolatilityState {
+    fn default() -> Self {
+        Self::NotReady("no price data collected yet".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VolatilitySnapshot {
+    pub annualized_volatility: f64,
+    pub window_seconds: i64,
+    pub sample_count: usize,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PauseState {
+    pub paused: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotReady {
+    pub reason: String,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/quality", get(quality))
+        .route("/status", get(status))
+        .route("/volatility", get(volatility))
+        .route("/snapshot", get(snapshot))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .with_state(state)
+}
+
+pub async fn serve(addr: SocketAddr, state: AppState) -> Result<(), hyper::Error> {
+    log::info!("HTTP server listening on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+}
+
+async fn quality(State(state): State<AppState>) -> Json<QualityReport> {
+    let report = state.quality.read().unwrap_or_else(|e| e.into_inner()).clone();
+    Json(report)
+}
+
+async fn status(State(state): State<AppState>) -> Json<StatusReport> {
+    let report = state.status.read().unwrap_or_else(|e| e.into_inner()).clone();
+    Json(report)
+}
+
+async fn snapshot(State(state): State<AppState>) -> Json<Snapshot> {
+    let snapshot = state.snapshot.read().unwrap_or_else(|e| e.into_inner()).clone();
+    Json(snapshot)
+}
+
+async fn pause(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<PauseState>, StatusCode> {
+    state.authorize(&headers)?;
+    state.paused.store(true, Ordering::Relaxed);
+    Ok(Json(PauseState { paused: true }))
+}
+
+async fn resume(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<PauseState>, StatusCode> {
+    state.authorize(&headers)?;
+    state.paused.store(false, Ordering::Relaxed);
+    Ok(Json(PauseState { paused: false }))
+}
+
+async fn volatility(State(state): State<AppState>) -> Result<Json<VolatilitySnapshot>, (StatusCode, Json<NotReady>)> {
+    match &*state.volatility.read().unwrap_or_else(|e| e.into_inner()) {
+        VolatilityState::Ready(snapshot) => Ok(Json(snapshot.clone())),
+        VolatilityState::NotReady(reason) => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(NotReady { reason: reason.clone() }),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn pause_is_disabled_without_a_token() {
+        let state = AppState::default();
+        let response = pause(State(state.clone()), bearer("anything")).await;
+        assert_eq!(response.unwrap_err(), StatusCode::NOT_FOUND);
+        assert!(!state.paused.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_need_the_configured_token() {
+        let state = AppState::default().with_control_token(Some("s3cret".to_string()));
+
+        assert_eq!(pause(State(state.clone()), HeaderMap::new()).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert_eq!(pause(State(state.clone()), bearer("wrong")).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert!(!state.paused.load(Ordering::Relaxed));
+
+        assert!(pause(State(state.clone()), bearer("s3cret")).await.unwrap().paused);
+        assert!(state.paused.load(Ordering::Relaxed));
+        assert!(!resume(State(state.clone()), bearer("s3cret")).await.unwrap().paused);
+        assert!(!state.paused.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn pause_watch_reports_each_change_once() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut watch = PauseWatch::new(flag.clone());
+        assert_eq!(watch.poll(), (false, None));
+
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(watch.poll(), (true, Some(PauseChange::Paused)));
+        assert_eq!(watch.poll(), (true, None));
+
+        flag.store(false, Ordering::Relaxed);
+        assert_eq!(watch.poll(), (false, Some(PauseChange::Resumed)));
+        assert_eq!(watch.poll(), (false, None));
+    }
+
+    async fn get_volatility(state: AppState) -> (StatusCode, serde_json::Value) {
+        use axum::response::IntoResponse;
+        let response = volatility(State(state)).await.into_response();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn volatility_is_served_once_ready_and_a_503_until_then() {
+        let state = AppState::default();
+        let (status, body) = get_volatility(state.clone()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, serde_json::json!({"reason": "no price data collected yet"}));
+
+        let last_updated = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        *state.volatility.write().unwrap() = VolatilityState::Ready(VolatilitySnapshot {
+            annualized_volatility: 0.65,
+            window_seconds: 3600,
+            sample_count: 42,
+            last_updated,
+        });
+        let (status, body) = get_volatility(state).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({
+            "annualized_volatility": 0.65,
+            "window_seconds": 3600,
+            "sample_count": 42,
+            "last_updated": "2026-01-01T00:00:00Z",
+        }));
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::Duration;
+use serde::Serialize;
+use crate::volatility::VolatilityCalculator;
+
+// Startup progress for the `/status` endpoint: how much of the window is
+// filled and, from the observed arrival rate, roughly when it will be
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatusReport {
+    pub ready: bool,
+    pub sample_count: usize,
+    pub required_samples: usize,
+    pub window_seconds: i64,
+    // Time between the oldest and newest points in the window
+    pub covered_seconds: i64,
+    pub samples_per_second: Option<f64>,
+    // None once ready, or before there is a rate to project from
+    pub seconds_until_ready: Option<i64>,
+}
+
+impl StatusReport {
+    pub fn build(calculator: &VolatilityCalculator) -> Self {
+        let window = calculator.window_size();
+        let sample_count = calculator.sample_count();
+        // Points, so one more than the returns the calculator waits for
+        let required_samples = calculator.min_samples() + 1;
+        let covered = match (calculator.oldest_timestamp(), calculator.newest_timestamp()) {
+            (Some(oldest), Some(newest)) => newest - oldest,
+            _ => Duration::zero(),
+        };
+
+        let samples_per_second = (sample_count >= 2 && covered > Duration::zero())
+            .then(|| (sample_count - 1) as f64 / (covered.num_milliseconds() as f64 / 1000.0));
+
+        // Eviction keeps the span just under the window, so count it as full
+        // once the remainder is less than one typical sample interval
+        let mean_interval = samples_per_second.map_or(0.0, |rate| 1.0 / rate);
+        let remaining = (window - covered).num_milliseconds() as f64 / 1000.0;
+        let ready = sample_count >= required_samples && remaining <= mean_interval;
+
+        // Whichever takes longer: spanning the window or collecting enough samples
+        let seconds_until_ready = if ready {
+            None
+        } else {
+            samples_per_second.map(|rate| {
+                let to_span = remaining.max(0.0).ceil() as i64;
+                let to_fill = (required_samples.saturating_sub(sample_count) as f64 / rate).ceil() as i64;
+                to_span.max(to_fill)
+            })
+        };
+
+        Self {
+            ready,
+            sample_count,
+            required_samples,
+            window_seconds: window.num_seconds(),
+            covered_seconds: covered.num_seconds(),
+            samples_per_second,
+            seconds_until_ready,
+        }
+    }
+}
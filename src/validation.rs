@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use crate::price_collector::PricePoint;
+
+// Consecutive rejections that agree with each other before a jump check
+// accepts the new level
+pub const DEFAULT_REBASELINE_AFTER: u32 = 5;
+
+// One check an incoming price must pass before it enters the window.
+// `window` is the history accepted so far, oldest first.
+pub trait PriceValidator: Send + Sync {
+    // Key under which rejections are counted
+    fn name(&self) -> &'static str;
+
+    fn validate(&self, price: &PricePoint, window: &VecDeque<PricePoint>) -> Result<(), String>;
+}
+
+// A NaN, infinite or non-positive price would poison every log return
+pub struct FinitePositive;
+
+impl PriceValidator for FinitePositive {
+    fn name(&self) -> &'static str {
+        "finite_positive"
+    }
+
+    fn validate(&self, price: &PricePoint, _window: &VecDeque<PricePoint>) -> Result<(), String> {
+        if price.price.is_finite() && price.price > 0.0 {
+            Ok(())
+        } else {
+            Err(format!("invalid price {}", price.price))
+        }
+    }
+}
+
+// Hard bounds on plausible prices; either side may be left open
+pub struct PriceRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl PriceValidator for PriceRange {
+    fn name(&self) -> &'static str {
+        "price_range"
+    }
+
+    fn validate(&self, price: &PricePoint, _window: &VecDeque<PricePoint>) -> Result<(), String> {
+        if let Some(min) = self.min.filter(|min| price.price < *min) {
+            return Err(format!("price {} below minimum {}", price.price, min));
+        }
+        if let Some(max) = self.max.filter(|max| price.price > *max) {
+            return Err(format!("price {} above maximum {}", price.price, max));
+        }
+        Ok(())
+    }
+}
+
+// Run of rejected prices for checks that compare against the last accepted
+// one. A genuine level shift is rejected tick after tick at a consistent
+// price, while a bad print is a one-off; once enough rejections in a row
+// agree with each other the check lets the next one through, so the window
+// re-anchors instead of locking the feed out for good.
+struct Rebaseline {
+    after: u32,
+    // Rejections in the current run and the latest rejected price
+    run: Mutex<(u32, f64)>,
+}
+
+impl Rebaseline {
+    fn new(after: u32) -> Self {
+        Self { after, run: Mutex::new((0, 0.0)) }
+    }
+
+    fn accepted(&self) {
+        self.run.lock().unwrap_or_else(|e| e.into_inner()).0 = 0;
+    }
+
+    // Record a rejection; true when it completes a run and should be let
+    // through instead. `agrees` compares it with the previous rejection.
+    fn rejected(&self, price: f64, agrees: impl Fn(f64) -> bool) -> bool {
+        let mut run = self.run.lock().unwrap_or_else(|e| e.into_inner());
+        let count = if run.0 > 0 && agrees(run.1) { run.0 + 1 } else { 1 };
+        if count > self.after {
+            *run = (0, 0.0);
+            return true;
+        }
+        *run = (count, price);
+        false
+    }
+}
+
+// Rejects a tick that moves more than `max_fraction` from the previous
+// accepted price, e.g. 0.1 for 10%
+pub struct MaxJump {
+    pub max_fraction: f64,
+    rebaseline: Rebaseline,
+}
+
+impl MaxJump {
+    pub fn new(max_fraction: f64) -> Self {
+        Self { max_fraction, rebaseline: Rebaseline::new(DEFAULT_REBASELINE_AFTER) }
+    }
+
+    // Accept the new level after this many rejected ticks in a row that
+    // are themselves within `max_fraction` of each other
+    pub fn with_rebaseline_after(mut self, ticks: u32) -> Self {
+        self.rebaseline = Rebaseline::new(ticks);
+        self
+    }
+}
+
+impl PriceValidator for MaxJump {
+    fn name(&self) -> &'static str {
+        "max_jump"
+    }
+
+    fn validate(&self, price: &PricePoint, window: &VecDeque<PricePoint>) -> Result<(), String> {
+        let Some(previous) = window.back() else {
+            return Ok(());
+        };
+        let jump = (price.price / previous.price - 1.0).abs();
+        if jump <= self.max_fraction {
+            self.rebaseline.accepted();
+            return Ok(());
+        }
+        if self.rebaseline.rejected(price.price, |last| (price.price / last - 1.0).abs() <= self.max_fraction) {
+            log::warn!("Price held near {} after a {:.2}% jump, accepting the new level", price.price, jump * 100.0);
+            return Ok(());
+        }
+        Err(format!(
+            "price {} moved {:.2}% from previous {}",
+            price.price,
+            jump * 100.0,
+            previous.price
+        ))
+    }
+}
+
+// Rejects a price more than `max_fraction` away from the median of the
+// current window, e.g. 0.2 for 20%. Unlike MaxJump this isn't fooled by a
+// single earlier bad print.
+pub struct MedianDeviation {
+    pub max_fraction: f64,
+}
+
+impl PriceValidator for MedianDeviation {
+    fn name(&self) -> &'static str {
+        "outlier"
+    }
+
+    fn validate(&self, price: &PricePoint, window: &VecDeque<PricePoint>) -> Result<(), String> {
+        if window.is_empty() {
+            return Ok(());
+        }
+        let mut prices: Vec<f64> = window.iter().map(|p| p.price).collect();
+        prices.sort_by(|a, b| a.total_cmp(b));
+        let mid = prices.len() / 2;
+        let median = if prices.len().is_multiple_of(2) {
+            (prices[mid - 1] + prices[mid]) / 2.0
+        } else {
+            prices[mid]
+        };
+
+        let deviation = (price.price / median - 1.0).abs();
+        if deviation > self.max_fraction {
+            return Err(format!(
+                "price {} is {:.2}% from window median {}",
+                price.price,
+                deviation * 100.0,
+                median
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Rejects a tick whose log return from the previous accepted price is more
+// than `max_zscore` standard deviations from the window's mean log return.
+// Adapts to the current regime where MaxJump's fixed percentage can't; lets
+// everything through until the window has a few returns to compare with,
+// and re-anchors after a level shift the same way MaxJump does.
+pub struct ReturnZScore {
+    pub max_zscore: f64,
+    rebaseline: Rebaseline,
+}
+
+impl ReturnZScore {
+    pub fn new(max_zscore: f64) -> Self {
+        Self { max_zscore, rebaseline: Rebaseline::new(DEFAULT_REBASELINE_AFTER) }
+    }
+
+    // Accept the new level after this many rejected ticks in a row whose
+    // returns from one to the next are within `max_zscore`
+    pub fn with_rebaseline_after(mut self, ticks: u32) -> Self {
+        self.rebaseline = Rebaseline::new(ticks);
+        self
+    }
+}
+
+impl PriceValidator for ReturnZScore {
+    fn name(&self) -> &'static str {
+        "return_zscore"
+    }
+
+    fn validate(&self, price: &PricePoint, window: &VecDeque<PricePoint>) -> Result<(), String> {
+        if window.len() < 4 {
+            return Ok(());
+        }
+        let returns: Vec<f64> = window.iter()
+            .zip(window.iter().skip(1))
+            .map(|(a, b)| (b.price / a.price).ln())
+            .collect();
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let stddev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        if stddev == 0.0 || !stddev.is_finite() {
+            return Ok(());
+        }
+
+        let Some(previous) = window.back() else {
+            return Ok(());
+        };
+        let zscore_from = |from: f64| ((price.price / from).ln() - mean) / stddev;
+        let zscore = zscore_from(previous.price);
+        if zscore.abs() <= self.max_zscore {
+            self.rebaseline.accepted();
+            return Ok(());
+        }
+        if self.rebaseline.rejected(price.price, |last| zscore_from(last).abs() <= self.max_zscore) {
+            log::warn!("Price held near {} after a {:+.2} z-score return, accepting the new level", price.price, zscore);
+            return Ok(());
+        }
+        Err(format!(
+            "return from {} to {} has z-score {:+.2}",
+            previous.price,
+            price.price,
+            zscore
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn point(price: f64) -> PricePoint {
+        PricePoint {
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap(),
+            price,
+            source: "test".to_string(),
+            spread: None,
+            volume: None,
+        }
+    }
+
+    // Feed prices through the validator, keeping the accepted ones as the
+    // window does, and return which were accepted
+    fn run(validator: &dyn PriceValidator, window: &mut VecDeque<PricePoint>, prices: &[f64]) -> Vec<bool> {
+        prices.iter()
+            .map(|&price| {
+                let accepted = validator.validate(&point(price), window).is_ok();
+                if accepted {
+                    window.push_back(point(price));
+                }
+                accepted
+            })
+            .collect()
+    }
+
+    #[test]
+    fn max_jump_rejects_a_one_off_bad_print() {
+        let validator = MaxJump::new(0.1);
+        let mut window = VecDeque::from([point(2500.0)]);
+        assert_eq!(run(&validator, &mut window, &[2510.0, 25.0, 2505.0]), [true, false, true]);
+    }
+
+    #[test]
+    fn max_jump_accepts_a_level_shift_after_a_run_of_rejections() {
+        let validator = MaxJump::new(0.1).with_rebaseline_after(3);
+        let mut window = VecDeque::from([point(2500.0)]);
+        let accepted = run(&validator, &mut window, &[3000.0, 3001.0, 3002.0, 3003.0, 3004.0]);
+        assert_eq!(accepted, [false, false, false, true, true]);
+        assert_eq!(window.back().unwrap().price, 3004.0);
+    }
+
+    #[test]
+    fn max_jump_run_needs_rejections_that_agree() {
+        let validator = MaxJump::new(0.1).with_rebaseline_after(2);
+        let mut window = VecDeque::from([point(2500.0)]);
+        // Scattered garbage never forms a run
+        assert_eq!(run(&validator, &mut window, &[25.0, 9000.0, 250.0, 90_000.0]), [false; 4]);
+        assert_eq!(window.back().unwrap().price, 2500.0);
+    }
+
+    // Alternating 0.1% moves, so the window has a small, non-zero spread
+    fn quiet_window() -> VecDeque<PricePoint> {
+        [2500.0, 2502.5, 2500.0, 2502.5, 2500.0, 2502.5].into_iter().map(point).collect()
+    }
+
+    #[test]
+    fn zscore_rejects_a_one_off_spike() {
+        let validator = ReturnZScore::new(4.0);
+        let mut window = quiet_window();
+        assert_eq!(run(&validator, &mut window, &[2750.0, 2500.0]), [false, true]);
+    }
+
+    #[test]
+    fn zscore_accepts_a_level_shift_after_a_run_of_rejections() {
+        let validator = ReturnZScore::new(4.0).with_rebaseline_after(3);
+        let mut window = quiet_window();
+        let accepted = run(&validator, &mut window, &[2750.0, 2751.0, 2750.0, 2751.0]);
+        assert_eq!(accepted, [false, false, false, true]);
+        assert_eq!(window.back().unwrap().price, 2751.0);
+    }
+
+    #[test]
+    fn median_deviation_drops_an_outlier_and_passes_normal_moves() {
+        let validator = MedianDeviation { max_fraction: 0.2 };
+        // Anything goes into an empty window
+        assert_eq!(run(&validator, &mut VecDeque::new(), &[9000.0]), [true]);
+
+        let mut window = quiet_window();
+        // 1% and 15% from the median of about 2501 pass; 2x and a tenth don't
+        let accepted = run(&validator, &mut window, &[2526.0, 5000.0, 2875.0, 250.0, 2490.0]);
+        assert_eq!(accepted, [true, false, true, false, true]);
+        assert_eq!(window.len(), 9);
+    }
+}
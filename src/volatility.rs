@@ -1,10 +1,405 @@
-use std::collections::VecDeque;
-use chrono::{Utc, Duration};
-use crate::price_collector::PricePoint;
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use serde::Serialize;
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+use crate::price_collector::{median, OhlcPoint, PricePoint};
+use crate::annualization::{Annualizer, Calendar365, Trading252};
+use crate::validation::{FinitePositive, PriceValidator};
+use crate::persistence::{HistoryStore, PersistenceFormat};
+use crate::clock::{Clock, SystemClock};
+use crate::error::VolatilityError;
+use crate::downsample::Downsampler;
+use crate::seasonality::IntradayProfile;
+
+// Returns needed before an estimate is reported, unless overridden
+pub const DEFAULT_MIN_SAMPLES: usize = 30;
+
+// Most points with_capacity reserves up front, so a long window over a short
+// interval can't allocate gigabytes before the first tick
+pub const MAX_PREALLOCATED_POINTS: usize = 100_000;
+
+// How to report a window where every price is identical. Zero variance can be
+// genuine (constant-rate moves), but a single distinct price usually means a
+// stale feed rather than a calm market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatWindowPolicy {
+    // Report 0% volatility as-is
+    Report,
+    // Report 0% volatility but log a warning
+    Warn,
+    // Treat the window as having insufficient data
+    Suppress,
+}
+
+impl FromStr for FlatWindowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "report" => Ok(Self::Report),
+            "warn" => Ok(Self::Warn),
+            "suppress" | "none" => Ok(Self::Suppress),
+            other => Err(format!("unknown flat window policy: {}", other)),
+        }
+    }
+}
+
+// What to do when a volatility result comes out NaN or infinite despite the
+// upstream guards. Either way the value is never passed on as if it were real.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    // Drop the result, as if there were not enough data
+    Suppress,
+    // Fail the calculation with VolatilityError::NonFiniteVolatility
+    Error,
+}
+
+impl FromStr for NonFinitePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "suppress" => Ok(Self::Suppress),
+            "error" => Ok(Self::Error),
+            other => Err(format!("unknown non-finite output policy: {}", other)),
+        }
+    }
+}
+
+// Named sets of windows reported together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolatilityPreset {
+    // Desk-standard 30/60/90-day realized volatility on a 252-day year
+    Bloomberg,
+}
+
+impl VolatilityPreset {
+    pub fn windows(&self) -> Vec<Duration> {
+        match self {
+            Self::Bloomberg => vec![Duration::days(30), Duration::days(60), Duration::days(90)],
+        }
+    }
+
+    pub fn annualizer(&self) -> Box<dyn Annualizer> {
+        match self {
+            Self::Bloomberg => Box::new(Trading252),
+        }
+    }
+
+    // History the calculator must retain to serve every window
+    pub fn longest_window(&self) -> Duration {
+        self.windows().into_iter().max().unwrap_or_else(Duration::zero)
+    }
+}
+
+impl FromStr for VolatilityPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bloomberg" | "30/60/90" => Ok(Self::Bloomberg),
+            other => Err(format!("unknown volatility preset: {}", other)),
+        }
+    }
+}
+
+// Shape of volatility across window tenors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermStructureShape {
+    // Short windows more volatile than long ones: near-term turbulence
+    Backwardation,
+    // Long windows more volatile than short ones: near-term calm
+    Contango,
+    Flat,
+}
+
+impl std::fmt::Display for TermStructureShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Backwardation => write!(f, "backwardation"),
+            Self::Contango => write!(f, "contango"),
+            Self::Flat => write!(f, "flat"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TermStructure {
+    // Change in annualized volatility per day of window length
+    pub slope_per_day: f64,
+    pub shape: TermStructureShape,
+}
+
+// Least-squares slope of volatility against tenor over a set of
+// (window, volatility) points. Needs at least two distinct tenors.
+pub fn term_structure(points: &[(Duration, f64)], flat_threshold: f64) -> Option<TermStructure> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let tenors: Vec<f64> = points.iter()
+        .map(|(window, _)| window.num_seconds() as f64 / 86_400.0)
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = tenors.iter().sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, vol)| vol).sum::<f64>() / n;
+    let covariance = tenors.iter().zip(points)
+        .map(|(x, (_, y))| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let variance_x = tenors.iter().map(|x| (x - mean_x).powi(2)).sum::<f64>();
+    if variance_x == 0.0 {
+        return None;
+    }
+
+    let slope_per_day = covariance / variance_x;
+    let shape = if slope_per_day.abs() < flat_threshold {
+        TermStructureShape::Flat
+    } else if slope_per_day < 0.0 {
+        TermStructureShape::Backwardation
+    } else {
+        TermStructureShape::Contango
+    };
+    Some(TermStructure { slope_per_day, shape })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VolatilityResult {
+    pub timestamp: DateTime<Utc>,
+    pub annualized_volatility: f64,
+    pub annualized_variance: Option<f64>,
+    pub sample_count: usize,
+    // Set on the first result after a discontinuity in the price stream
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub gap: bool,
+    // Current volatility relative to a configured historical baseline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_ratio: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_percentile: Option<f64>,
+}
+
+// Everything a dashboard or debugging session needs from the calculator in
+// one serializable value. Estimates that aren't available (or aren't
+// finite) are None.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Snapshot {
+    pub sample_count: usize,
+    pub window_seconds: i64,
+    pub oldest_timestamp: Option<DateTime<Utc>>,
+    pub newest_timestamp: Option<DateTime<Utc>>,
+    pub annualized_volatility: Option<f64>,
+    // Sum of squared returns, not annualized
+    pub realized_variance: Option<f64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+// What the window is measured back from when evicting. Exchange timestamps
+// and local time can disagree, so one reference is used for everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionReference {
+    // The newest point's own timestamp, so history survives a stalled feed
+    // and backfilled points aren't purged on arrival. The flip side is a
+    // stale anchor: after a stall the window still ends at the last point,
+    // so the estimate describes prices that may be hours old until new ones
+    // arrive or evict_expired runs. /ready's staleness check catches that.
+    #[default]
+    NewestPoint,
+    // The calculator's clock (wall-clock time unless replaced)
+    Clock,
+}
+
+impl FromStr for EvictionReference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "newest" | "newest_point" => Ok(Self::NewestPoint),
+            "clock" | "now" | "wall_clock" => Ok(Self::Clock),
+            other => Err(format!("unknown eviction reference: {}", other)),
+        }
+    }
+}
+
+// How the return between consecutive prices is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReturnType {
+    // ln(p_i / p_{i-1})
+    #[default]
+    Log,
+    // (p_i - p_{i-1}) / p_{i-1}; larger than the log return for big up
+    // moves, smaller for big down moves
+    Simple,
+}
+
+impl ReturnType {
+    fn of(&self, previous: f64, current: f64) -> f64 {
+        match self {
+            Self::Log => (current / previous).ln(),
+            Self::Simple => (current - previous) / previous,
+        }
+    }
+}
+
+impl FromStr for ReturnType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "log" => Ok(Self::Log),
+            "simple" | "arithmetic" => Ok(Self::Simple),
+            other => Err(format!("unknown return type: {}", other)),
+        }
+    }
+}
+
+// Which sample spacing annualization scales by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntervalEstimate {
+    // Total time over the number of returns
+    #[default]
+    Mean,
+    // The middle gap, the typical spacing between samples whatever a few
+    // long pauses or a dense burst add to the total
+    Median,
+}
+
+impl FromStr for IntervalEstimate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mean" | "average" => Ok(Self::Mean),
+            "median" => Ok(Self::Median),
+            other => Err(format!("unknown interval estimate: {}", other)),
+        }
+    }
+}
+
+// How bid/ask spread data, when present, feeds into the returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadAdjustment {
+    // Use mid/trade prices as-is
+    None,
+    // Pad each return by the half-spread paid at both ends, giving a more
+    // conservative, execution-aware estimate
+    Widen,
+}
+
+impl SpreadAdjustment {
+    fn apply(&self, log_return: f64, previous: &PricePoint, current: &PricePoint) -> f64 {
+        match self {
+            Self::None => log_return,
+            Self::Widen => {
+                let half_spread = |p: &PricePoint| p.spread.map_or(0.0, |s| s / (2.0 * p.price));
+                let cost = half_spread(previous) + half_spread(current);
+                // A zero return has no direction to widen toward
+                if log_return >= 0.0 { log_return + cost } else { log_return - cost }
+            }
+        }
+    }
+}
+
+impl FromStr for SpreadAdjustment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "widen" => Ok(Self::Widen),
+            other => Err(format!("unknown spread adjustment: {}", other)),
+        }
+    }
+}
 
 pub struct VolatilityCalculator {
     window_size: Duration,
     price_history: VecDeque<PricePoint>,
+    // Hard cap on price_history regardless of timestamps
+    max_points: Option<usize>,
+    // Decides what counts as "now" when evicting against the clock
+    clock: Box<dyn Clock>,
+    eviction_reference: EvictionReference,
+    // Range bars, only populated when OHLC collection is enabled
+    ohlc_history: VecDeque<OhlcPoint>,
+    flat_window_policy: FlatWindowPolicy,
+    annualizer: Box<dyn Annualizer>,
+    // Run in order on every incoming price; the first failure rejects it
+    validators: Vec<Box<dyn PriceValidator>>,
+    rejections: BTreeMap<&'static str, u64>,
+    gap_threshold: Option<Duration>,
+    gap_pending: bool,
+    spread_adjustment: SpreadAdjustment,
+    return_type: ReturnType,
+    interval_estimate: IntervalEstimate,
+    // Subtract the mean return before squaring; off gives the raw second moment
+    demean: bool,
+    excluded_weekdays: Vec<Weekday>,
+    non_finite_policy: NonFinitePolicy,
+    // Saved after every accepted price when set
+    history_store: Option<HistoryStore>,
+    // Return statistics over the whole window, kept up to date on every
+    // push and eviction so calculate_volatility doesn't rescan the buffer
+    running: RunningReturns,
+    // Returns needed before calculate_volatility reports anything
+    min_samples: usize,
+    // Fixed-interval bucketing applied to accepted prices, when enabled
+    downsampler: Option<Downsampler>,
+    // Time-of-day volatility pattern, when seasonal adjustment is enabled
+    seasonality: Option<IntradayProfile>,
+}
+
+// Welford accumulators for log returns that also support removing the
+// oldest return when its point is evicted
+#[derive(Debug, Clone, Default)]
+struct RunningReturns {
+    // Consecutive price pairs in the window, and how many of them are equal
+    pairs: usize,
+    flat_pairs: usize,
+    // Returns counted (pairs minus excluded weekdays)
+    count: usize,
+    mean: f64,
+    m2: f64,
+    // Undemeaned, for time-weighted annualization
+    sum_squares: f64,
+    total_interval: f64,
+}
+
+impl RunningReturns {
+    fn add(&mut self, flat: bool, sample: Option<(f64, f64)>) {
+        self.pairs += 1;
+        self.flat_pairs += flat as usize;
+        let Some((value, interval)) = sample else {
+            return;
+        };
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.sum_squares += value * value;
+        self.total_interval += interval;
+    }
+
+    fn remove(&mut self, flat: bool, sample: Option<(f64, f64)>) {
+        self.pairs -= 1;
+        self.flat_pairs -= flat as usize;
+        let Some((value, interval)) = sample else {
+            return;
+        };
+        if self.count <= 1 {
+            // Start clean rather than carry rounding error into the next fill
+            let (pairs, flat_pairs) = (self.pairs, self.flat_pairs);
+            *self = Self { pairs, flat_pairs, ..Self::default() };
+            return;
+        }
+        let previous_mean = (self.count as f64 * self.mean - value) / (self.count - 1) as f64;
+        self.m2 = (self.m2 - (value - self.mean) * (value - previous_mean)).max(0.0);
+        self.mean = previous_mean;
+        self.count -= 1;
+        self.sum_squares = (self.sum_squares - value * value).max(0.0);
+        self.total_interval -= interval;
+    }
 }
 
 impl VolatilityCalculator {
@@ -12,51 +407,1838 @@ impl VolatilityCalculator {
         Self {
             window_size,
             price_history: VecDeque::new(),
+            max_points: None,
+            clock: Box::new(SystemClock),
+            eviction_reference: EvictionReference::NewestPoint,
+            ohlc_history: VecDeque::new(),
+            flat_window_policy: FlatWindowPolicy::Report,
+            annualizer: Box::new(Calendar365),
+            validators: vec![Box::new(FinitePositive)],
+            rejections: BTreeMap::new(),
+            gap_threshold: None,
+            gap_pending: false,
+            spread_adjustment: SpreadAdjustment::None,
+            return_type: ReturnType::Log,
+            interval_estimate: IntervalEstimate::Mean,
+            demean: true,
+            excluded_weekdays: Vec::new(),
+            non_finite_policy: NonFinitePolicy::Suppress,
+            history_store: None,
+            running: RunningReturns::default(),
+            min_samples: DEFAULT_MIN_SAMPLES,
+            downsampler: None,
+            seasonality: None,
+        }
+    }
+
+    // A calculator whose buffer is allocated up front for `capacity` points,
+    // typically the window over the update interval, so a steady feed never
+    // reallocates it. One extra slot holds a new point until the oldest is
+    // evicted; a feed that outgrows it still works, the buffer just grows.
+    // The reservation stops at MAX_PREALLOCATED_POINTS.
+    pub fn with_capacity(window_size: Duration, capacity: usize) -> Self {
+        let reserved = capacity.min(MAX_PREALLOCATED_POINTS) + 1;
+        Self { price_history: VecDeque::with_capacity(reserved), ..Self::new(window_size) }
+    }
+
+    // A calculator that resumes from, and keeps saving to, a JSON history file
+    pub fn with_persistence(window_size: Duration, path: std::path::PathBuf) -> Result<Self, VolatilityError> {
+        Self::new(window_size).with_history_store(HistoryStore::new(path, PersistenceFormat::Json))
+    }
+
+    // Load whatever history the store holds that still falls in the window,
+    // then persist the buffer on every accepted price. A file that doesn't
+    // load is an error rather than something the first save overwrites;
+    // see HistoryStore::load for what counts as empty.
+    pub fn with_history_store(mut self, store: HistoryStore) -> Result<Self, VolatilityError> {
+        let points = store.load()?;
+        log::info!("Loaded {} persisted price points from {}", points.len(), store.path().display());
+        let mut points: Vec<_> = points.into_iter()
+            .filter(|p| p.price.is_finite() && p.price > 0.0)
+            .collect();
+        points.sort_by_key(|p| p.timestamp);
+        self.price_history.extend(points);
+        self.rebuild_running();
+        self.evict_old_prices();
+        self.history_store = Some(store);
+        Ok(self)
+    }
+
+    pub fn with_annualizer(mut self, annualizer: Box<dyn Annualizer>) -> Self {
+        self.annualizer = annualizer;
+        self
+    }
+
+    pub fn with_flat_window_policy(mut self, policy: FlatWindowPolicy) -> Self {
+        self.flat_window_policy = policy;
+        self
+    }
+
+    // Append a check to the validation chain, after the built-in finite/positive one
+    pub fn with_validator(mut self, validator: Box<dyn PriceValidator>) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    // A calculator that evicts against `clock` rather than the newest point
+    pub fn with_clock(window_size: Duration, clock: Box<dyn Clock>) -> Self {
+        Self { clock, eviction_reference: EvictionReference::Clock, ..Self::new(window_size) }
+    }
+
+    // Points the buffer holds before it has to reallocate
+    pub fn history_capacity(&self) -> usize {
+        self.price_history.capacity()
+    }
+
+    pub fn with_eviction_reference(mut self, reference: EvictionReference) -> Self {
+        self.eviction_reference = reference;
+        self.evict_old_prices();
+        self
+    }
+
+    // Keep at most this many points, dropping the oldest beyond it
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = Some(max_points);
+        self.evict_old_prices();
+        self
+    }
+
+    // Hold back the estimate until this many returns are in the window; a
+    // handful of returns gives a wildly unstable figure. At least one.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.set_min_samples(min_samples);
+        self
+    }
+
+    // Feed the window one close per `interval` instead of every tick
+    pub fn with_sample_interval(mut self, interval: Duration) -> Self {
+        self.set_sample_interval(Some(interval));
+        self
+    }
+
+    // Learn the hour-of-day volatility pattern from every return seen, for
+    // seasonally_adjusted_volatility
+    pub fn with_seasonality(mut self) -> Self {
+        self.seasonality = Some(IntradayProfile::new());
+        self
+    }
+
+    pub fn seasonality(&self) -> Option<&IntradayProfile> {
+        self.seasonality.as_ref()
+    }
+
+    // Consecutive points further apart than this are treated as a gap
+    pub fn with_gap_threshold(mut self, threshold: Duration) -> Self {
+        self.gap_threshold = Some(threshold);
+        if let Some(downsampler) = self.downsampler.as_mut() {
+            downsampler.set_max_gap(threshold);
+        }
+        self
+    }
+
+    pub fn with_spread_adjustment(mut self, adjustment: SpreadAdjustment) -> Self {
+        self.spread_adjustment = adjustment;
+        self.rebuild_running();
+        self
+    }
+
+    pub fn with_return_type(mut self, return_type: ReturnType) -> Self {
+        self.set_return_type(return_type);
+        self
+    }
+
+    // Drop returns whose (UTC) timestamp falls on these days, e.g. weekends
+    // for comparison against TradFi assets
+    pub fn with_excluded_weekdays(mut self, weekdays: Vec<Weekday>) -> Self {
+        self.excluded_weekdays = weekdays;
+        self.rebuild_running();
+        self
+    }
+
+    pub fn with_non_finite_policy(mut self, policy: NonFinitePolicy) -> Self {
+        self.non_finite_policy = policy;
+        self
+    }
+
+    // Record a discontinuity detected outside the calculator, e.g. a reconnect
+    pub fn mark_gap(&mut self) {
+        self.gap_pending = true;
+    }
+
+    // One-shot: returns true once after each gap
+    pub fn take_gap(&mut self) -> bool {
+        std::mem::take(&mut self.gap_pending)
+    }
+
+    pub fn set_flat_window_policy(&mut self, policy: FlatWindowPolicy) {
+        self.flat_window_policy = policy;
+    }
+
+    // Changing the interval drops any partly filled bucket
+    pub fn set_sample_interval(&mut self, interval: Option<Duration>) {
+        if self.downsampler.as_ref().map(|d| d.interval()) != interval {
+            let gap_threshold = self.gap_threshold;
+            self.downsampler = interval.map(|interval| {
+                let downsampler = Downsampler::new(interval);
+                match gap_threshold {
+                    Some(threshold) => downsampler.with_max_gap(threshold),
+                    None => downsampler,
+                }
+            });
+        }
+    }
+
+    pub fn set_min_samples(&mut self, min_samples: usize) {
+        self.min_samples = min_samples.max(1);
+    }
+
+    pub fn min_samples(&self) -> usize {
+        self.min_samples
+    }
+
+    // Returns still missing before the estimate is reported
+    pub fn samples_needed(&self) -> usize {
+        self.min_samples.saturating_sub(self.running.count)
+    }
+
+    // True when every return in the window is flat and FlatWindowPolicy::Suppress
+    // is holding the estimate back
+    pub fn flat_window_suppressed(&self) -> bool {
+        self.flat_window_policy == FlatWindowPolicy::Suppress
+            && self.running.pairs > 0
+            && self.running.flat_pairs == self.running.pairs
+    }
+
+    // Fraction of min_samples collected so far, 1.0 once the estimate is
+    // reported
+    pub fn warmup_progress(&self) -> f64 {
+        (self.running.count as f64 / self.min_samples as f64).min(1.0)
+    }
+
+    pub fn set_non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite_policy = policy;
+    }
+
+    pub fn set_return_type(&mut self, return_type: ReturnType) {
+        if self.return_type != return_type {
+            self.return_type = return_type;
+            self.rebuild_running();
         }
     }
 
+    // Ignored by time-weighted annualization, which uses the total span
+    pub fn with_interval_estimate(mut self, estimate: IntervalEstimate) -> Self {
+        self.interval_estimate = estimate;
+        self
+    }
+
+    pub fn set_interval_estimate(&mut self, estimate: IntervalEstimate) {
+        self.interval_estimate = estimate;
+    }
+
+    // De-meaned (the default) removes the window's drift from the variance;
+    // without it the variance is the mean squared return, the usual
+    // realized volatility convention, and a trend adds to it
+    pub fn with_demean(mut self, demean: bool) -> Self {
+        self.demean = demean;
+        self
+    }
+
+    pub fn set_demean(&mut self, demean: bool) {
+        self.demean = demean;
+    }
+
+    pub fn set_annualizer(&mut self, annualizer: Box<dyn Annualizer>) {
+        self.annualizer = annualizer;
+    }
+
+    // Change the window without dropping history that still falls inside it
+    pub fn set_window_size(&mut self, window_size: Duration) {
+        self.window_size = window_size;
+        self.evict_old_prices();
+    }
+
     pub fn add_price(&mut self, price: PricePoint) {
+        for validator in &self.validators {
+            if let Err(reason) = validator.validate(&price, &self.price_history) {
+                log::warn!("Rejecting price from {} ({}): {}", price.source, validator.name(), reason);
+                *self.rejections.entry(validator.name()).or_insert(0) += 1;
+                return;
+            }
+        }
+
+        match self.downsampler.as_mut() {
+            Some(downsampler) => {
+                for close in downsampler.push(price) {
+                    self.push_price(close);
+                }
+            }
+            None => self.push_price(price),
+        }
+
+        self.flush_history();
+    }
+
+    // Append an accepted price and keep the running statistics in step
+    fn push_price(&mut self, price: PricePoint) {
+        if let (Some(threshold), Some(newest)) = (self.gap_threshold, self.price_history.back()) {
+            if price.timestamp - newest.timestamp > threshold {
+                log::warn!(
+                    "Gap of {} seconds in price stream",
+                    (price.timestamp - newest.timestamp).num_seconds()
+                );
+                self.gap_pending = true;
+            }
+        }
+
+        // Several trades can share a timestamp; keep only the latest price so
+        // no return spans zero time
+        if self.price_history.back().is_some_and(|newest| newest.timestamp == price.timestamp) {
+            let len = self.price_history.len();
+            if len >= 2 {
+                let (previous, newest) = (&self.price_history[len - 2], &self.price_history[len - 1]);
+                let (flat, sample) = (previous.price == newest.price, self.pair_return(previous, newest));
+                self.running.remove(flat, sample);
+                if let (Some(profile), Some((value, interval))) = (self.seasonality.as_mut(), sample) {
+                    profile.remove(newest.timestamp, value, interval);
+                }
+            }
+            self.price_history.pop_back();
+        }
+
         self.price_history.push_back(price);
-        
-        // Remove old prices outside the window
-        let cutoff = Utc::now() - self.window_size;
-        while let Some(oldest) = self.price_history.front() {
-            if oldest.timestamp < cutoff {
-                self.price_history.pop_front();
-            } else {
-                break;
+        let len = self.price_history.len();
+        if len >= 2 {
+            let (previous, current) = (&self.price_history[len - 2], &self.price_history[len - 1]);
+            let (flat, sample) = (previous.price == current.price, self.pair_return(previous, current));
+            self.running.add(flat, sample);
+            if let (Some(profile), Some((value, interval))) = (self.seasonality.as_mut(), sample) {
+                profile.add(current.timestamp, value, interval);
             }
         }
+        self.evict_old_prices();
     }
 
-    pub fn calculate_volatility(&self) -> Option<f64> {
+    // Write the current buffer to the history store, if one is configured
+    pub fn flush_history(&self) {
+        if let Some(store) = &self.history_store {
+            if let Err(e) = store.save(self.price_history.iter()) {
+                log::error!("Failed to persist price history: {}", e);
+            }
+        }
+    }
+
+    // Prepend historical points older than anything already held, e.g. from
+    // a REST backfill at startup
+    pub fn backfill(&mut self, points: Vec<PricePoint>) -> usize {
+        let oldest = self.oldest_timestamp();
+        let mut points: Vec<_> = points.into_iter()
+            .filter(|p| p.price.is_finite() && p.price > 0.0)
+            .filter(|p| oldest.is_none_or(|oldest| p.timestamp < oldest))
+            .collect();
+        points.sort_by_key(|p| p.timestamp);
+        let added = points.len();
+        for point in points.into_iter().rev() {
+            self.price_history.push_front(point);
+        }
+        self.rebuild_running();
+        self.evict_old_prices();
+        added
+    }
+
+    pub fn add_ohlc(&mut self, bar: OhlcPoint) {
+        let prices = [bar.open, bar.high, bar.low, bar.close];
+        if prices.iter().any(|p| !p.is_finite() || *p <= 0.0) || bar.high < bar.low {
+            log::warn!("Rejecting invalid OHLC bar from {}: {:?}", bar.source, bar);
+            return;
+        }
+        self.ohlc_history.push_back(bar);
+        self.evict_old_prices();
+    }
+
+    pub fn ohlc_count(&self) -> usize {
+        self.ohlc_history.len()
+    }
+
+    // Points currently in the window, oldest first
+    pub fn prices(&self) -> impl Iterator<Item = &PricePoint> {
+        self.price_history.iter()
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.price_history.len()
+    }
+
+    pub fn window_size(&self) -> Duration {
+        self.window_size
+    }
+
+    pub fn oldest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.price_history.front().map(|p| p.timestamp)
+    }
+
+    pub fn newest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.price_history.back().map(|p| p.timestamp)
+    }
+
+    // Volume-weighted average price over the window, from the points that
+    // carry a volume. None without any volume data (or only zero volumes).
+    pub fn vwap(&self) -> Option<f64> {
+        let (notional, volume) = self.price_history.iter()
+            .filter_map(|p| p.volume.filter(|v| v.is_finite() && *v >= 0.0).map(|v| (p.price, v)))
+            .fold((0.0, 0.0), |(notional, total), (price, volume)| (notional + price * volume, total + volume));
+        (volume > 0.0).then(|| notional / volume)
+    }
+
+    // Current state in one struct; the estimates come from the running
+    // statistics and the range from a single pass over the buffer
+    pub fn snapshot(&self) -> Snapshot {
+        let range = self.price_range();
+        Snapshot {
+            sample_count: self.sample_count(),
+            window_seconds: self.window_size.num_seconds(),
+            oldest_timestamp: self.oldest_timestamp(),
+            newest_timestamp: self.newest_timestamp(),
+            annualized_volatility: self.calculate_volatility().filter(|v| v.is_finite()),
+            realized_variance: self.realized_variance().filter(|v| v.is_finite()),
+            min_price: range.map(|(min, _)| min),
+            max_price: range.map(|(_, max)| max),
+        }
+    }
+
+    // Simple return from the oldest to the newest price in the window
+    pub fn window_return(&self) -> Option<f64> {
         if self.price_history.len() < 2 {
             return None;
         }
+        let (first, last) = (self.price_history.front()?.price, self.price_history.back()?.price);
+        Some((last - first) / first)
+    }
 
-        // Calculate log returns
-        let mut returns: Vec<f64> = Vec::new();
-        let prices: Vec<_> = self.price_history.iter().collect();
-        
-        for i in 1..prices.len() {
-            let log_return = (prices[i].price / prices[i-1].price).ln();
-            returns.push(log_return);
+    // Annualized window return over annualized volatility, a rough
+    // risk-adjusted return. The return is scaled by the annualizer's year
+    // over the window's span, so both sides follow the same convention.
+    // None while the volatility is zero or unavailable.
+    pub fn return_to_vol_ratio(&self) -> Option<f64> {
+        let window_return = self.window_return()?;
+        let volatility = self.calculate_volatility().filter(|v| v.is_finite() && *v > 0.0)?;
+        let (first, last) = (self.price_history.front()?.timestamp, self.price_history.back()?.timestamp);
+        let span = (last - first).num_milliseconds() as f64 / 1000.0;
+        if span <= 0.0 {
+            return None;
         }
+        Some(window_return * self.year_seconds()? / span / volatility)
+    }
 
-        // Calculate standard deviation
-        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
-        let variance = returns.iter()
-            .map(|x| (x - mean).powi(2))
-            .sum::<f64>() / (returns.len() - 1) as f64;
-        
-        // Calculate annualized volatility
-        // Get the actual average time between samples
-        let time_diff = prices.last().unwrap().timestamp - prices.first().unwrap().timestamp;
-        let actual_interval = time_diff.num_seconds() as f64 / (prices.len() - 1) as f64;
-        let samples_per_year = (365.0 * 24.0 * 60.0 * 60.0) / actual_interval;
-        
-        let annualized_vol = variance.sqrt() * samples_per_year.sqrt();
-        
-        Some(annualized_vol)
+    // How many seconds the annualizer's "year" covers at the window's
+    // average sample spacing: 365 days under the calendar convention, n
+    // samples for a fixed n periods per year, one sample when not
+    // annualizing at all. The square of its factor is samples per year.
+    fn year_seconds(&self) -> Option<f64> {
+        let running = &self.running;
+        if running.count == 0 {
+            return None;
+        }
+        let interval = running.total_interval / running.count as f64;
+        if interval <= 0.0 {
+            return None;
+        }
+        let scale = self.annualizer.annualize(1.0, interval);
+        Some(scale * scale * interval)
+    }
+
+    // Largest peak-to-trough fall in the window, as a fraction of the peak
+    pub fn max_drawdown(&self) -> Option<f64> {
+        if self.price_history.len() < 2 {
+            return None;
+        }
+        let mut peak = f64::MIN;
+        let mut drawdown: f64 = 0.0;
+        for point in &self.price_history {
+            peak = peak.max(point.price);
+            drawdown = drawdown.max((peak - point.price) / peak);
+        }
+        Some(drawdown)
+    }
+
+    // Lowest and highest price currently in the window
+    pub fn price_range(&self) -> Option<(f64, f64)> {
+        let first = self.price_history.front()?.price;
+        Some(self.price_history.iter().fold((first, first), |(min, max), p| (min.min(p.price), max.max(p.price))))
+    }
+
+    // (mean, upper, lower) Bollinger-style bands on the window's prices:
+    // the mean plus and minus `k` sample standard deviations. Needs as many
+    // points as the volatility estimate does.
+    pub fn bollinger_bands(&self, k: f64) -> Option<(f64, f64, f64)> {
+        if self.price_history.len() <= self.min_samples {
+            return None;
+        }
+        let (mean, stddev) = self.price_mean_and_stddev()?;
+        Some((mean, mean + k * stddev, mean - k * stddev))
+    }
+
+    // Sample standard deviation of the prices themselves (not their
+    // returns), in price units
+    pub fn price_stddev(&self) -> Option<f64> {
+        self.price_mean_and_stddev().map(|(_, stddev)| stddev)
+    }
+
+    // Mean and sample standard deviation of the window's prices, by Welford
+    // over the buffer so nothing is copied. Needs two points.
+    fn price_mean_and_stddev(&self) -> Option<(f64, f64)> {
+        if self.price_history.len() < 2 {
+            return None;
+        }
+        let (count, mean, m2) = self.price_history.iter().fold((0.0, 0.0, 0.0), |(n, mean, m2), p| {
+            let n = n + 1.0;
+            let delta = p.price - mean;
+            let mean = mean + delta / n;
+            (n, mean, m2 + delta * (p.price - mean))
+        });
+        Some((mean, (m2 / (count - 1.0)).sqrt()))
+    }
+
+    // Longest spacing between consecutive points in the window. A large one
+    // stretches the average interval used for annualization.
+    pub fn largest_gap(&self) -> Option<Duration> {
+        self.price_history.iter()
+            .zip(self.price_history.iter().skip(1))
+            .map(|(previous, current)| current.timestamp - previous.timestamp)
+            .max()
+    }
+
+    pub fn rejected_non_finite(&self) -> u64 {
+        self.rejections.get(FinitePositive.name()).copied().unwrap_or(0)
+    }
+
+    // Rejected prices so far, keyed by the validator that rejected them
+    pub fn rejections(&self) -> &BTreeMap<&'static str, u64> {
+        &self.rejections
     }
-} 
\ No newline at end of file
+
+    fn evict_old_prices(&mut self) {
+        // Remove old prices outside the window
+        let reference = match self.eviction_reference {
+            EvictionReference::NewestPoint => {
+                let newest = self.price_history.back().map(|p| p.timestamp)
+                    .or_else(|| self.ohlc_history.back().map(|bar| bar.timestamp));
+                match newest {
+                    Some(newest) => newest,
+                    None => return,
+                }
+            }
+            EvictionReference::Clock => self.clock.now(),
+        };
+        self.evict_before(reference - self.window_size);
+    }
+
+    // Drop points that have aged out of the window by the calculator's
+    // clock, whatever the eviction reference; used while nothing new arrives
+    pub fn evict_expired(&mut self) {
+        self.evict_before(self.clock.now() - self.window_size);
+    }
+
+    fn evict_before(&mut self, cutoff: DateTime<Utc>) {
+        while self.price_history.front().is_some_and(|oldest| oldest.timestamp < cutoff) {
+            self.pop_oldest();
+        }
+        while self.ohlc_history.front().is_some_and(|bar| bar.timestamp < cutoff) {
+            self.ohlc_history.pop_front();
+        }
+
+        // Bad timestamps could otherwise keep everything "inside" the window
+        if let Some(max_points) = self.max_points {
+            let excess = self.price_history.len().saturating_sub(max_points);
+            if excess > 0 {
+                log::debug!("Price history over its {} point cap, evicting {} oldest", max_points, excess);
+                for _ in 0..excess {
+                    self.pop_oldest();
+                }
+            }
+        }
+    }
+
+    // Drop the front point along with its return from the running statistics
+    fn pop_oldest(&mut self) {
+        if let (Some(oldest), Some(next)) = (self.price_history.front(), self.price_history.get(1)) {
+            let (flat, sample) = (oldest.price == next.price, self.pair_return(oldest, next));
+            self.running.remove(flat, sample);
+        }
+        self.price_history.pop_front();
+    }
+
+    // O(1) from the running statistics; matches volatility_of over the
+    // whole buffer up to floating-point rounding
+    pub fn calculate_volatility(&self) -> Option<f64> {
+        let running = &self.running;
+        if running.pairs == 0 || !self.allow_flat(running.flat_pairs == running.pairs, self.price_history.len()) {
+            return None;
+        }
+        if running.count < self.min_samples {
+            return None;
+        }
+        // One return gives 0/0 here, as it does in mean_and_stddev
+        let stddev = if self.demean {
+            (running.m2 / (running.count - 1) as f64).sqrt()
+        } else {
+            (running.sum_squares / running.count as f64).sqrt()
+        };
+        let interval = running.total_interval / running.count as f64;
+        if interval <= 0.0 {
+            return None;
+        }
+        if self.annualizer.time_weighted() {
+            return Some(self.annualizer.annualize((running.sum_squares / running.total_interval).sqrt(), 1.0));
+        }
+        let interval = match self.interval_estimate {
+            IntervalEstimate::Mean => interval,
+            // Not kept running; a sort of the window's gaps per call
+            IntervalEstimate::Median => {
+                let gaps = self.price_history.iter()
+                    .zip(self.price_history.iter().skip(1))
+                    .filter_map(|(previous, current)| self.pair_return(previous, current))
+                    .map(|(_, interval)| interval)
+                    .collect();
+                Some(median(gaps)).filter(|gap| *gap > 0.0)?
+            }
+        };
+        Some(self.annualizer.annualize(stddev, interval))
+    }
+
+    // The window's estimate quoted over other horizons, rescaled by the
+    // square root of time from the one annual figure rather than recomputed:
+    // daily is annual over sqrt(days per year), hourly daily over sqrt(24).
+    // None below the sample threshold, and for daily and hourly when the
+    // annualizer isn't annual (AnnualizationMode::Raw).
+    pub fn as_annualized(&self) -> Option<f64> {
+        self.calculate_volatility()
+    }
+
+    pub fn as_daily(&self) -> Option<f64> {
+        let days = self.annualizer.days_per_year()?;
+        Some(self.as_annualized()? / days.sqrt())
+    }
+
+    pub fn as_hourly(&self) -> Option<f64> {
+        Some(self.as_daily()? / 24.0_f64.sqrt())
+    }
+
+    // The window's mean return scaled to a year, the drift de-meaning takes
+    // out of the variance: the per-sample mean times the annualizer's year
+    // over the sample spacing, so a mean return of 0.00001% every 10s is
+    // about +31.5% under the calendar convention, n times the mean for
+    // periods:n and the mean itself when not annualizing
+    pub fn estimated_drift(&self) -> Option<f64> {
+        let running = &self.running;
+        if running.count == 0 || running.count < self.min_samples {
+            return None;
+        }
+        let interval = running.total_interval / running.count as f64;
+        Some(running.mean * self.year_seconds()? / interval)
+    }
+
+    // The estimate with the hour-of-day pattern taken out, so a window
+    // spent in habitually volatile hours is marked down and one in quiet
+    // hours marked up. Each return's expected variance scales with its
+    // hour's squared factor, so the estimate is divided by the RMS of those
+    // factors, weighted by the time each return spans. Returns in hours
+    // without enough history are left out of the RMS; None unless
+    // seasonality is enabled and at least one return has a factor.
+    pub fn seasonally_adjusted_volatility(&self) -> Option<f64> {
+        let profile = self.seasonality.as_ref()?;
+        let factors: Vec<Option<f64>> = (0..24).map(|hour| profile.factor(hour)).collect();
+        let (mut weighted, mut span) = (0.0, 0.0);
+        for (previous, current) in self.price_history.iter().zip(self.price_history.iter().skip(1)) {
+            let Some((_, interval)) = self.pair_return(previous, current) else {
+                continue;
+            };
+            if let Some(factor) = factors[current.timestamp.hour() as usize] {
+                weighted += factor * factor * interval;
+                span += interval;
+            }
+        }
+        if span <= 0.0 || weighted <= 0.0 {
+            return None;
+        }
+        Some(self.calculate_volatility()? / (weighted / span).sqrt())
+    }
+
+    // (estimate, lower, upper) for the given two-sided confidence level, e.g.
+    // 0.95. With n returns, (n-1)s²/σ² is chi-square with n-1 degrees of
+    // freedom; annualization is a constant factor, so it carries over to the
+    // bounds unchanged. Needs at least two returns.
+    pub fn volatility_with_ci(&self, confidence: f64) -> Option<(f64, f64, f64)> {
+        if !(confidence > 0.0 && confidence < 1.0) || self.running.count < 2 {
+            return None;
+        }
+        let estimate = self.calculate_volatility().filter(|v| v.is_finite())?;
+        let df = (self.running.count - 1) as f64;
+        let chi_squared = ChiSquared::new(df).ok()?;
+        let alpha = 1.0 - confidence;
+        let lower = estimate * (df / chi_squared.inverse_cdf(1.0 - alpha / 2.0)).sqrt();
+        let upper = estimate * (df / chi_squared.inverse_cdf(alpha / 2.0)).sqrt();
+        Some((estimate, lower, upper))
+    }
+
+    // Recompute the running statistics from scratch, after the buffer or the
+    // return definition changes other than by push/evict
+    fn rebuild_running(&mut self) {
+        let mut running = RunningReturns::default();
+        for (previous, current) in self.price_history.iter().zip(self.price_history.iter().skip(1)) {
+            running.add(previous.price == current.price, self.pair_return(previous, current));
+        }
+        self.running = running;
+    }
+
+    // Annualized variance of returns, for consumers such as variance swaps
+    // that want it directly rather than re-squaring the volatility
+    pub fn calculate_realized_variance(&self) -> Option<f64> {
+        self.calculate_volatility().map(|vol| vol.powi(2))
+    }
+
+    // Raw realized variance: the sum of squared returns over the window, with
+    // no annualization or mean adjustment
+    pub fn realized_variance(&self) -> Option<f64> {
+        let running = &self.running;
+        if running.count < self.min_samples {
+            return None;
+        }
+        // Sum of squares from the running mean and squared deviations
+        Some(running.m2 + running.count as f64 * running.mean.powi(2))
+    }
+
+    // Square root of the realized variance, likewise not annualized
+    pub fn realized_volatility(&self) -> Option<f64> {
+        self.realized_variance().map(f64::sqrt)
+    }
+
+    // Volatility plus the context needed to report it, optionally carrying
+    // the annualized variance as well. Ok(None) while there isn't enough
+    // data; an error only for a non-finite value under NonFinitePolicy::Error.
+    pub fn calculate_result(&self, include_variance: bool) -> Result<Option<VolatilityResult>, VolatilityError> {
+        let (Some(annualized_volatility), Some(newest), Some(oldest)) =
+            (self.calculate_volatility(), self.price_history.back(), self.price_history.front())
+        else {
+            return Ok(None);
+        };
+        let timestamp = newest.timestamp;
+        let sample_count = self.price_history.len();
+
+        // Last line of defence: never hand out NaN/Inf as a volatility
+        if !annualized_volatility.is_finite() {
+            let (min, max) = self.price_history.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                (lo.min(p.price), hi.max(p.price))
+            });
+            let span = timestamp - oldest.timestamp;
+            log::error!(
+                "Non-finite volatility {} from {} samples over {}s (prices {}..{}, {} rejected inputs)",
+                annualized_volatility,
+                sample_count,
+                span.num_seconds(),
+                min,
+                max,
+                self.rejections.values().sum::<u64>()
+            );
+            return match self.non_finite_policy {
+                NonFinitePolicy::Suppress => Ok(None),
+                NonFinitePolicy::Error => Err(VolatilityError::NonFiniteVolatility(annualized_volatility)),
+            };
+        }
+
+        Ok(Some(VolatilityResult {
+            timestamp,
+            annualized_volatility,
+            annualized_variance: include_variance.then(|| annualized_volatility.powi(2)),
+            sample_count,
+            gap: false,
+            baseline_ratio: None,
+            baseline_percentile: None,
+        }))
+    }
+
+    // Volatility over the most recent `window` of history, measured back from
+    // the newest point and annualized with the given convention
+    pub fn calculate_volatility_for_window(&self, window: Duration, annualizer: &dyn Annualizer) -> Option<f64> {
+        let newest = self.price_history.back()?.timestamp;
+        let cutoff = newest - window;
+        let prices: Vec<_> = self.price_history.iter()
+            .filter(|p| p.timestamp >= cutoff)
+            .collect();
+        self.volatility_of(&prices, annualizer)
+    }
+
+    // Volatility for each of `windows`, all sliced from the one shared history
+    // and annualized with the calculator's own convention
+    pub fn calculate_windows(&self, windows: &[Duration]) -> Vec<(Duration, Option<f64>)> {
+        windows.iter()
+            .map(|window| (*window, self.calculate_volatility_for_window(*window, self.annualizer.as_ref())))
+            .collect()
+    }
+
+    // Realized volatility at each horizon the retained history fully spans,
+    // all windowed from the same buffer; longer horizons are left out
+    pub fn volatility_cone(&self, horizons: &[Duration]) -> Vec<(Duration, f64)> {
+        let (Some(oldest), Some(newest)) = (self.oldest_timestamp(), self.newest_timestamp()) else {
+            return Vec::new();
+        };
+        let covered = newest - oldest;
+        horizons.iter()
+            .filter(|horizon| **horizon <= covered)
+            .filter_map(|horizon| {
+                let vol = self.calculate_volatility_for_window(*horizon, self.annualizer.as_ref())?;
+                vol.is_finite().then_some((*horizon, vol))
+            })
+            .collect()
+    }
+
+    // Report every window of a preset in one shot, shortest first
+    pub fn calculate_preset(&self, preset: VolatilityPreset) -> Vec<(Duration, Option<f64>)> {
+        let annualizer = preset.annualizer();
+        preset.windows()
+            .into_iter()
+            .map(|window| (window, self.calculate_volatility_for_window(window, annualizer.as_ref())))
+            .collect()
+    }
+
+    // Exponentially weighted (RiskMetrics-style) volatility: each return's
+    // weight is `lambda` times that of the return after it, so recent moves
+    // dominate. Typical lambda is 0.94.
+    pub fn calculate_ewma_volatility(&self, lambda: f64) -> Option<f64> {
+        let prices: Vec<_> = self.price_history.iter().collect();
+        let (returns, interval) = self.returns_of(&prices, self.interval_estimate)?;
+
+        let mut weighted_sum = 0.0;
+        let mut weight_total = 0.0;
+        let mut weight = 1.0;
+        for r in returns.iter().rev() {
+            weighted_sum += weight * r.powi(2);
+            weight_total += weight;
+            weight *= lambda;
+        }
+
+        let stddev = (weighted_sum / weight_total).sqrt();
+        Some(self.annualizer.annualize(stddev, interval))
+    }
+
+    // Parkinson range estimator over the OHLC bars: per-bar variance is
+    // mean(ln(high/low)^2) / (4 ln 2). Needs two bars to infer the bar length.
+    pub fn calculate_parkinson_volatility(&self) -> Option<f64> {
+        let interval = self.ohlc_interval()?;
+        let n = self.ohlc_history.len() as f64;
+        let mean_sq_range = self.ohlc_history.iter()
+            .map(|bar| (bar.high / bar.low).ln().powi(2))
+            .sum::<f64>() / n;
+        let variance = mean_sq_range / (4.0 * std::f64::consts::LN_2);
+        Some(self.annualizer.annualize(variance.sqrt(), interval))
+    }
+
+    // Robust alternative to the sample standard deviation: the median
+    // absolute deviation of the returns, scaled by 1.4826 so it matches the
+    // standard deviation for normal returns. A single bad print barely moves it.
+    pub fn calculate_mad_volatility(&self) -> Option<f64> {
+        if self.running.count < self.min_samples {
+            return None;
+        }
+        let prices: Vec<_> = self.price_history.iter().collect();
+        let (returns, interval) = self.returns_of(&prices, self.interval_estimate)?;
+        let center = median(returns.clone());
+        let mad = median(returns.iter().map(|r| (r - center).abs()).collect());
+        Some(self.annualizer.annualize(1.4826 * mad, interval))
+    }
+
+    // Garman-Klass estimator over the OHLC bars: per-bar variance is
+    // mean(0.5 ln(H/L)^2 - (2 ln 2 - 1) ln(C/O)^2). Uses the open and close
+    // as well as the range, so it is more efficient than Parkinson.
+    pub fn calculate_garman_klass_volatility(&self) -> Option<f64> {
+        let interval = self.ohlc_interval()?;
+        let n = self.ohlc_history.len() as f64;
+        let variance = self.ohlc_history.iter()
+            .map(|bar| {
+                0.5 * (bar.high / bar.low).ln().powi(2)
+                    - (2.0 * std::f64::consts::LN_2 - 1.0) * (bar.close / bar.open).ln().powi(2)
+            })
+            .sum::<f64>() / n;
+        // Can dip below zero on bars with tiny ranges and large open-close moves
+        Some(self.annualizer.annualize(variance.max(0.0).sqrt(), interval))
+    }
+
+    // Wilder's Average True Range over the OHLC bars, in price units. True
+    // range is the largest of high - low and the distances from the previous
+    // close to the high and the low; the first `period` of them are averaged,
+    // then each later one is folded in as (atr * (period - 1) + tr) / period.
+    // Needs `period + 1` bars, since the first bar has no previous close.
+    pub fn calculate_atr(&self, period: usize) -> Option<f64> {
+        if period == 0 || self.ohlc_history.len() <= period {
+            return None;
+        }
+        let mut true_ranges = self.ohlc_history.iter()
+            .zip(self.ohlc_history.iter().skip(1))
+            .map(|(prev, bar)| {
+                (bar.high - bar.low)
+                    .max((bar.high - prev.close).abs())
+                    .max((bar.low - prev.close).abs())
+            });
+        let n = period as f64;
+        let seed = true_ranges.by_ref().take(period).sum::<f64>() / n;
+        Some(true_ranges.fold(seed, |atr, tr| (atr * (n - 1.0) + tr) / n))
+    }
+
+    // Average spacing in seconds between consecutive OHLC bars
+    fn ohlc_interval(&self) -> Option<f64> {
+        if self.ohlc_history.len() < 2 {
+            return None;
+        }
+        let first = self.ohlc_history.front()?.timestamp;
+        let last = self.ohlc_history.back()?.timestamp;
+        let interval = (last - first).num_milliseconds() as f64 / 1000.0 / (self.ohlc_history.len() - 1) as f64;
+        (interval > 0.0).then_some(interval)
+    }
+
+    fn volatility_of(&self, prices: &[&PricePoint], annualizer: &dyn Annualizer) -> Option<f64> {
+        let estimate = if annualizer.time_weighted() { IntervalEstimate::Mean } else { self.interval_estimate };
+        let (returns, interval) = self.returns_of(prices, estimate)?;
+        if annualizer.time_weighted() {
+            let sum_squares: f64 = returns.iter().map(|r| r * r).sum();
+            let per_second = (sum_squares / (interval * returns.len() as f64)).sqrt();
+            return Some(annualizer.annualize(per_second, 1.0));
+        }
+
+        // Calculate standard deviation
+        let stddev = if self.demean {
+            mean_and_stddev(&returns).1
+        } else {
+            (returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64).sqrt()
+        };
+        
+        // Calculate annualized volatility
+        let annualized_vol = annualizer.annualize(stddev, interval);
+        
+        Some(annualized_vol)
+    }
+
+    // Log returns of `prices` with the mean or median spacing in seconds
+    // between the samples, or None when the window is too thin (or
+    // suppressed as flat)
+    fn returns_of(&self, prices: &[&PricePoint], estimate: IntervalEstimate) -> Option<(Vec<f64>, f64)> {
+        if prices.len() < 2 {
+            return None;
+        }
+
+        if !self.allow_flat(prices.iter().all(|p| p.price == prices[0].price), prices.len()) {
+            return None;
+        }
+
+        // Calculate log returns, skipping any that land on an excluded weekday
+        let mut returns: Vec<f64> = Vec::new();
+        let mut intervals: Vec<f64> = Vec::new();
+        
+        for pair in prices.windows(2) {
+            if let Some((log_return, interval)) = self.pair_return(pair[0], pair[1]) {
+                returns.push(log_return);
+                intervals.push(interval);
+            }
+        }
+
+        if returns.is_empty() {
+            return None;
+        }
+
+        // Get the actual average time between samples. Backfilled or
+        // out-of-order points could still leave it at zero, which would
+        // annualize to infinity.
+        let actual_interval = match estimate {
+            IntervalEstimate::Mean => intervals.iter().sum::<f64>() / returns.len() as f64,
+            IntervalEstimate::Median => median(intervals),
+        };
+        if actual_interval <= 0.0 {
+            return None;
+        }
+        Some((returns, actual_interval))
+    }
+
+    // Apply the flat window policy; false means report nothing
+    fn allow_flat(&self, flat: bool, points: usize) -> bool {
+        if !flat {
+            return true;
+        }
+        match self.flat_window_policy {
+            FlatWindowPolicy::Report => true,
+            FlatWindowPolicy::Warn => {
+                log::warn!(
+                    "Only one distinct price across {} points in the window; feed may be stale",
+                    points
+                );
+                true
+            }
+            FlatWindowPolicy::Suppress => false,
+        }
+    }
+
+    // The (spread-adjusted) log return between two consecutive points and the
+    // seconds between them, or None if it lands on an excluded weekday
+    fn pair_return(&self, previous: &PricePoint, current: &PricePoint) -> Option<(f64, f64)> {
+        if self.excluded_weekdays.contains(&current.timestamp.weekday()) {
+            return None;
+        }
+        let value = self.return_type.of(previous.price, current.price);
+        let interval = (current.timestamp - previous.timestamp).num_milliseconds() as f64 / 1000.0;
+        Some((self.spread_adjustment.apply(value, previous, current), interval))
+    }
+
+    // How many standard deviations the most recent log return sits from the
+    // window's mean return. Large magnitudes flag unusual moves.
+    pub fn latest_return_zscore(&self) -> Option<f64> {
+        // Need a few returns besides the latest for the spread to mean anything
+        if self.price_history.len() < 4 {
+            return None;
+        }
+
+        let prices: Vec<_> = self.price_history.iter().collect();
+        let returns: Vec<f64> = prices.windows(2)
+            .map(|pair| self.return_type.of(pair[0].price, pair[1].price))
+            .collect();
+        let (mean, stddev) = mean_and_stddev(&returns);
+        if stddev == 0.0 || !stddev.is_finite() {
+            return None;
+        }
+
+        let latest = *returns.last()?;
+        Some((latest - mean) / stddev)
+    }
+}
+
+// Several look-back windows (e.g. 1h, 6h, 24h) over a single price buffer
+// sized to the largest of them, so memory doesn't grow per window
+pub struct MultiWindowCalculator {
+    calculator: VolatilityCalculator,
+    windows: Vec<Duration>,
+}
+
+impl MultiWindowCalculator {
+    pub fn new(mut windows: Vec<Duration>) -> Self {
+        windows.sort();
+        windows.dedup();
+        let largest = windows.last().copied().unwrap_or_else(Duration::zero);
+        Self {
+            calculator: VolatilityCalculator::new(largest),
+            windows,
+        }
+    }
+
+    pub fn add_price(&mut self, price: PricePoint) {
+        self.calculator.add_price(price);
+    }
+
+    pub fn windows(&self) -> &[Duration] {
+        &self.windows
+    }
+
+    // The shared calculator, for its builders/setters and single-window methods
+    pub fn calculator(&self) -> &VolatilityCalculator {
+        &self.calculator
+    }
+
+    pub fn calculator_mut(&mut self) -> &mut VolatilityCalculator {
+        &mut self.calculator
+    }
+
+    // Each window's estimate, shortest first
+    pub fn calculate_all(&self) -> Vec<(Duration, Option<f64>)> {
+        self.calculator.calculate_windows(&self.windows)
+    }
+}
+
+// One calculator per price source, keyed by PricePoint.source, so each
+// venue's volatility can be compared with the blended estimate. New sources
+// get a calculator from `make`.
+pub struct SourceCalculators {
+    make: Box<dyn Fn() -> VolatilityCalculator + Send + Sync>,
+    calculators: BTreeMap<String, VolatilityCalculator>,
+}
+
+impl SourceCalculators {
+    pub fn new(make: impl Fn() -> VolatilityCalculator + Send + Sync + 'static) -> Self {
+        Self {
+            make: Box::new(make),
+            calculators: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_price(&mut self, price: PricePoint) {
+        self.calculators
+            .entry(price.source.clone())
+            .or_insert_with(&self.make)
+            .add_price(price);
+    }
+
+    pub fn calculator(&self, source: &str) -> Option<&VolatilityCalculator> {
+        self.calculators.get(source)
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.calculators.keys().map(String::as_str)
+    }
+
+    // Each source's current estimate, in source order
+    pub fn calculate_all(&self) -> Vec<(&str, Option<f64>)> {
+        self.calculators.iter()
+            .map(|(source, calculator)| (source.as_str(), calculator.calculate_volatility()))
+            .collect()
+    }
+}
+
+// Mean and sample standard deviation (n-1) of a returns series
+fn mean_and_stddev(returns: &[f64]) -> (f64, f64) {
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter()
+        .map(|x| (x - mean).powi(2))
+        .sum::<f64>() / (returns.len() - 1) as f64;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annualization::AnnualizationMode;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn point(timestamp: DateTime<Utc>, price: f64) -> PricePoint {
+        PricePoint { timestamp, price, source: "test".to_string(), spread: None, volume: None }
+    }
+
+    #[test]
+    fn downsampled_outage_is_a_gap_not_flat_returns() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_gap_threshold(Duration::seconds(5))
+            .with_sample_interval(Duration::seconds(1));
+        calculator.add_price(point(start(), 100.0));
+        calculator.add_price(point(start() + Duration::seconds(1), 101.0));
+        assert!(!calculator.take_gap());
+
+        // The bucket after the outage closes on the next tick
+        calculator.add_price(point(start() + Duration::seconds(120), 102.0));
+        calculator.add_price(point(start() + Duration::seconds(121), 103.0));
+        assert!(calculator.take_gap());
+        // Three closes, no carried-forward fillers in between
+        assert_eq!(calculator.sample_count(), 3);
+    }
+
+    // Alternating 0.2% log returns every 30s through 00:00-01:00, then 0.1%
+    // until 02:00
+    fn busy_then_quiet_hour(calculator: &mut VolatilityCalculator) {
+        let mut log_price = 100f64.ln();
+        calculator.add_price(point(start(), log_price.exp()));
+        for i in 1..240 {
+            let timestamp = start() + Duration::seconds(30 * i);
+            let size = if timestamp.hour() == 0 { 0.002 } else { 0.001 };
+            log_price += if i % 2 == 0 { size } else { -size };
+            calculator.add_price(point(timestamp, log_price.exp()));
+        }
+    }
+
+    #[test]
+    fn seasonal_adjustment_uses_every_return_hour_not_just_the_newest() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(3)).with_seasonality();
+        busy_then_quiet_hour(&mut calculator);
+
+        let profile = calculator.seasonality().unwrap();
+        let (busy, quiet) = (profile.factor(0).unwrap(), profile.factor(1).unwrap());
+        assert!((busy - 1.6f64.sqrt()).abs() < 0.01, "busy factor {}", busy);
+        assert!((quiet - 0.4f64.sqrt()).abs() < 0.01, "quiet factor {}", quiet);
+
+        // Half the window in each hour averages out to about no adjustment,
+        // where dividing by the newest (quiet) hour's factor would inflate it
+        let raw = calculator.calculate_volatility().unwrap();
+        let adjusted = calculator.seasonally_adjusted_volatility().unwrap();
+        assert!((adjusted / raw - 1.0).abs() < 0.01, "raw {} adjusted {}", raw, adjusted);
+    }
+
+    #[test]
+    fn seasonal_adjustment_within_one_hour_divides_by_its_factor() {
+        let mut calculator = VolatilityCalculator::new(Duration::minutes(30)).with_seasonality();
+        busy_then_quiet_hour(&mut calculator);
+
+        // Only quiet-hour returns are left in the window
+        let factor = calculator.seasonality().unwrap().factor(1).unwrap();
+        let raw = calculator.calculate_volatility().unwrap();
+        let adjusted = calculator.seasonally_adjusted_volatility().unwrap();
+        assert!((adjusted - raw / factor).abs() < 1e-9 * raw);
+    }
+
+    // Samples 10s apart returning 2% over 30s
+    fn two_percent_in_thirty_seconds(annualizer: AnnualizationMode) -> VolatilityCalculator {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_annualizer(Box::new(annualizer))
+            .with_min_samples(2);
+        for (seconds, price) in [(0, 100.0), (10, 101.0), (20, 100.0), (30, 102.0)] {
+            calculator.add_price(point(start() + Duration::seconds(seconds), price));
+        }
+        calculator
+    }
+
+    #[test]
+    fn return_to_vol_ratio_scales_the_return_to_each_modes_year() {
+        let calendar_year = 365.0 * 24.0 * 3600.0;
+        let cases = [
+            (AnnualizationMode::ObservedInterval, calendar_year),
+            (AnnualizationMode::TimeWeighted, calendar_year),
+            (AnnualizationMode::CalendarDays(252.0), 252.0 * 24.0 * 3600.0),
+            // 1000 samples of 10s
+            (AnnualizationMode::FixedPeriodsPerYear(1000.0), 10_000.0),
+            // Not annualized: the return per 10s sample against per-sample volatility
+            (AnnualizationMode::Raw, 10.0),
+        ];
+        for (mode, year) in cases {
+            let calculator = two_percent_in_thirty_seconds(mode);
+            let volatility = calculator.calculate_volatility().unwrap();
+            let expected = 0.02 * year / 30.0 / volatility;
+            let ratio = calculator.return_to_vol_ratio().unwrap();
+            assert!((ratio / expected - 1.0).abs() < 1e-9, "{:?}: {} != {}", mode, ratio, expected);
+        }
+    }
+
+    #[test]
+    fn drift_scales_the_mean_return_to_each_modes_year() {
+        let calendar_year = 365.0 * 24.0 * 3600.0;
+        let cases = [
+            (AnnualizationMode::ObservedInterval, calendar_year / 10.0),
+            (AnnualizationMode::TimeWeighted, calendar_year / 10.0),
+            (AnnualizationMode::CalendarDays(252.0), 252.0 * 24.0 * 3600.0 / 10.0),
+            (AnnualizationMode::FixedPeriodsPerYear(1000.0), 1000.0),
+            (AnnualizationMode::Raw, 1.0),
+        ];
+        for (mode, samples_per_year) in cases {
+            // Two 1% moves 10s apart
+            let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+                .with_annualizer(Box::new(mode))
+                .with_min_samples(2);
+            for (seconds, price) in [(0, 100.0), (10, 101.0), (20, 102.01)] {
+                calculator.add_price(point(start() + Duration::seconds(seconds), price));
+            }
+            let expected = 1.01f64.ln() * samples_per_year;
+            let drift = calculator.estimated_drift().unwrap();
+            assert!((drift / expected - 1.0).abs() < 1e-9, "{:?}: {} != {}", mode, drift, expected);
+        }
+    }
+
+    #[test]
+    fn trend_inflates_zero_mean_volatility_by_the_drift() {
+        // Rising about 1% a step, wobbling by 0.1% either side
+        let trending = |demean: bool| {
+            let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+                .with_annualizer(Box::new(AnnualizationMode::Raw))
+                .with_demean(demean);
+            let mut log_price = 100f64.ln();
+            calculator.add_price(point(start(), log_price.exp()));
+            for i in 1..=40 {
+                log_price += if i % 2 == 0 { 0.011 } else { 0.009 };
+                calculator.add_price(point(start() + Duration::seconds(10 * i), log_price.exp()));
+            }
+            calculator
+        };
+        let (demeaned, raw) = (trending(true), trending(false));
+        let (demeaned_vol, raw_vol) = (demeaned.calculate_volatility().unwrap(), raw.calculate_volatility().unwrap());
+        let drift = raw.estimated_drift().unwrap();
+        assert!((drift - 0.01).abs() < 1e-12);
+        assert!((demeaned_vol - 0.001 * (40.0f64 / 39.0).sqrt()).abs() < 1e-12);
+        // Mean square = variance about the mean + squared mean, per sample
+        let n = 40.0;
+        assert!((raw_vol.powi(2) - (demeaned_vol.powi(2) * (n - 1.0) / n + drift * drift)).abs() < 1e-12);
+        assert_eq!(demeaned.estimated_drift(), raw.estimated_drift());
+    }
+
+    #[test]
+    fn with_capacity_reserves_the_window_up_to_the_cap() {
+        // 6h of 5s ticks: 4320 points plus the slot for the incoming one
+        let calculator = VolatilityCalculator::with_capacity(Duration::hours(6), 4320);
+        assert!(calculator.history_capacity() >= 4321);
+        assert!(calculator.history_capacity() < MAX_PREALLOCATED_POINTS);
+
+        let calculator = VolatilityCalculator::with_capacity(Duration::days(365), usize::MAX);
+        assert!(calculator.history_capacity() > MAX_PREALLOCATED_POINTS);
+        assert!(calculator.history_capacity() < 2 * MAX_PREALLOCATED_POINTS);
+
+        // An hour of 5s ticks, both ends included, is 721 points; once that
+        // window is full and evicting, the buffer never grows again
+        let mut calculator = VolatilityCalculator::with_capacity(Duration::hours(1), 721)
+            .with_eviction_reference(EvictionReference::NewestPoint);
+        let mut filled = None;
+        for tick in 0..720 * 5 {
+            calculator.add_price(point(start() + Duration::seconds(tick * 5), 2500.0 + (tick % 7) as f64));
+            if tick >= 720 {
+                let capacity = *filled.get_or_insert(calculator.history_capacity());
+                assert_eq!(calculator.history_capacity(), capacity, "grew at tick {}", tick);
+            }
+        }
+        assert!(calculator.sample_count() <= 721, "{}", calculator.sample_count());
+    }
+
+    #[test]
+    fn newest_point_eviction_keeps_a_stalled_window_that_clock_eviction_drains() {
+        let clock = crate::clock::ManualClock::new(start());
+        let mut anchored = VolatilityCalculator::with_clock(Duration::hours(1), Box::new(clock.clone()))
+            .with_eviction_reference(EvictionReference::NewestPoint);
+        let mut by_clock = VolatilityCalculator::with_clock(Duration::hours(1), Box::new(clock.clone()));
+        for minute in 0..10 {
+            let p = point(start() + Duration::minutes(minute), 2500.0 + minute as f64);
+            anchored.add_price(p.clone());
+            by_clock.add_price(p);
+        }
+
+        // Two hours of silence, then one late tick stamped just after the last
+        clock.advance(Duration::hours(2));
+        let late = point(start() + Duration::minutes(10), 2510.0);
+        anchored.add_price(late.clone());
+        by_clock.add_price(late);
+
+        // Anchored at 00:10, the window still covers all eleven points
+        assert_eq!(anchored.sample_count(), 11);
+        // Measured back from 02:00, none of them are left
+        assert_eq!(by_clock.sample_count(), 0);
+    }
+
+    // Stands in for a bug that lets NaN past the upstream guards
+    struct NanAnnualizer;
+
+    impl Annualizer for NanAnnualizer {
+        fn annualize(&self, _period_stddev: f64, _interval_seconds: f64) -> f64 {
+            f64::NAN
+        }
+    }
+
+    fn nan_calculator(policy: NonFinitePolicy) -> VolatilityCalculator {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_min_samples(2)
+            .with_annualizer(Box::new(NanAnnualizer))
+            .with_non_finite_policy(policy);
+        for (minute, price) in [2500.0, 2510.0, 2505.0, 2515.0].into_iter().enumerate() {
+            calculator.add_price(point(start() + Duration::minutes(minute as i64), price));
+        }
+        calculator
+    }
+
+    #[test]
+    fn non_finite_volatility_is_dropped_or_an_error() {
+        assert!(nan_calculator(NonFinitePolicy::Suppress).calculate_result(false).unwrap().is_none());
+        assert!(matches!(
+            nan_calculator(NonFinitePolicy::Error).calculate_result(false),
+            Err(VolatilityError::NonFiniteVolatility(v)) if v.is_nan()
+        ));
+    }
+
+    fn raw_calculator(prices: &[f64]) -> VolatilityCalculator {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_min_samples(2)
+            .with_annualizer(Box::new(AnnualizationMode::Raw));
+        for (minute, price) in prices.iter().enumerate() {
+            calculator.add_price(point(start() + Duration::minutes(minute as i64), *price));
+        }
+        calculator
+    }
+
+    #[test]
+    fn ewma_weights_each_return_lambda_times_the_next() {
+        // Returns ln(1.1) then ln(0.9); with lambda 0.5 the newest weighs 1, the older 0.5
+        let calculator = raw_calculator(&[100.0, 110.0, 99.0]);
+        let (older, newer) = (1.1f64.ln(), 0.9f64.ln());
+        let expected = ((newer.powi(2) + 0.5 * older.powi(2)) / 1.5).sqrt();
+        assert!((calculator.calculate_ewma_volatility(0.5).unwrap() - expected).abs() < 1e-12);
+
+        // Lambda 1 weighs them equally: the root mean square, not demeaned
+        let rms = ((older.powi(2) + newer.powi(2)) / 2.0).sqrt();
+        assert!((calculator.calculate_ewma_volatility(1.0).unwrap() - rms).abs() < 1e-12);
+    }
+
+    fn bar(minute: i64, open: f64, high: f64, low: f64, close: f64) -> OhlcPoint {
+        OhlcPoint { timestamp: start() + Duration::minutes(minute), open, high, low, close, source: "test".to_string() }
+    }
+
+    #[test]
+    fn parkinson_averages_the_squared_log_ranges() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_annualizer(Box::new(AnnualizationMode::Raw));
+        // One bar can't tell the bar length
+        calculator.add_ohlc(bar(0, 100.0, 102.0, 99.0, 101.0));
+        assert!(calculator.calculate_parkinson_volatility().is_none());
+
+        calculator.add_ohlc(bar(1, 101.0, 101.5, 100.5, 101.0));
+        let ranges = [(102.0f64 / 99.0).ln(), (101.5f64 / 100.5).ln()];
+        let variance = (ranges[0].powi(2) + ranges[1].powi(2)) / 2.0 / (4.0 * 2f64.ln());
+        let parkinson = calculator.calculate_parkinson_volatility().unwrap();
+        assert!((parkinson - variance.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn garman_klass_weighs_the_range_against_the_open_to_close_move() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_annualizer(Box::new(AnnualizationMode::Raw));
+        calculator.add_ohlc(bar(0, 100.0, 102.0, 99.0, 101.0));
+        assert!(calculator.calculate_garman_klass_volatility().is_none());
+
+        calculator.add_ohlc(bar(1, 101.0, 101.5, 100.5, 101.0));
+        // 0.5 * ln(H/L)^2 - (2 ln 2 - 1) * ln(C/O)^2 per bar; the second
+        // closes where it opened, so only its range counts
+        let first = 0.5 * (102.0f64 / 99.0).ln().powi(2) - (2.0 * 2f64.ln() - 1.0) * (101.0f64 / 100.0).ln().powi(2);
+        let second = 0.5 * (101.5f64 / 100.5).ln().powi(2);
+        let garman_klass = calculator.calculate_garman_klass_volatility().unwrap();
+        assert!((garman_klass - ((first + second) / 2.0).sqrt()).abs() < 1e-12);
+    }
+
+    // Running statistics, updated as points arrive and leave, against the
+    // estimate recomputed from the window's points, over random walks with
+    // uneven spacing so eviction takes anything from none to several points
+    #[test]
+    fn running_volatility_matches_a_full_recompute() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        for (seed, demean, return_type) in [(1, true, ReturnType::Log), (2, false, ReturnType::Log), (3, true, ReturnType::Simple)] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+                .with_min_samples(2)
+                .with_demean(demean)
+                .with_return_type(return_type);
+            let (mut timestamp, mut price) = (start(), 2500.0);
+            for step in 0..400 {
+                timestamp += Duration::seconds(rng.gen_range(1..=180));
+                price *= 1.0 + rng.gen_range(-0.01..0.01);
+                calculator.add_price(point(timestamp, price));
+                // The window recompute doesn't apply min_samples
+                if step < 2 {
+                    continue;
+                }
+
+                let running = calculator.calculate_volatility();
+                let recomputed = calculator.calculate_volatility_for_window(Duration::hours(1), calculator.annualizer.as_ref());
+                match (running, recomputed) {
+                    (Some(running), Some(recomputed)) => assert!(
+                        (running - recomputed).abs() <= 1e-9 * recomputed.abs().max(1.0),
+                        "seed {} step {}: running {} vs recomputed {}", seed, step, running, recomputed
+                    ),
+                    other => panic!("seed {} step {}: {:?}", seed, step, other),
+                }
+            }
+        }
+    }
+
+    // Trades sharing a millisecond used to leave a zero-length return that
+    // annualized to infinity; only the latest price at a timestamp is kept
+    #[test]
+    fn same_timestamp_prices_collapse_to_the_latest() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_min_samples(2)
+            .with_annualizer(Box::new(AnnualizationMode::Raw));
+        for (minute, price) in [(0, 100.0), (1, 101.0), (1, 102.0), (1, 101.5), (2, 103.0)] {
+            calculator.add_price(point(start() + Duration::minutes(minute), price));
+        }
+        let collapsed = raw_calculator(&[100.0, 101.5, 103.0]);
+        assert_eq!(calculator.price_history.len(), 3);
+        assert_eq!(calculator.price_history[1].price, 101.5);
+
+        let running = calculator.calculate_volatility().unwrap();
+        assert!(running.is_finite());
+        assert!((running - collapsed.calculate_volatility().unwrap()).abs() < 1e-12);
+        let recomputed = calculator.calculate_volatility_for_window(Duration::hours(1), calculator.annualizer.as_ref()).unwrap();
+        assert!((recomputed - running).abs() < 1e-12);
+    }
+
+    // A point exactly one window old stays; a millisecond older and it goes
+    #[test]
+    fn clock_eviction_drops_points_once_they_are_older_than_the_window() {
+        let clock = crate::clock::ManualClock::new(start());
+        let mut calculator = VolatilityCalculator::with_clock(Duration::hours(1), Box::new(clock.clone()));
+        for minute in 0..5 {
+            calculator.add_price(point(start() + Duration::minutes(minute), 2500.0 + minute as f64));
+        }
+        assert_eq!(calculator.sample_count(), 5);
+
+        clock.set(start() + Duration::hours(1));
+        calculator.evict_expired();
+        assert_eq!(calculator.sample_count(), 5);
+
+        clock.advance(Duration::milliseconds(1));
+        calculator.evict_expired();
+        assert_eq!(calculator.sample_count(), 4);
+
+        clock.advance(Duration::minutes(3));
+        calculator.evict_expired();
+        assert_eq!(calculator.sample_count(), 1);
+        assert_eq!(calculator.price_history[0].timestamp, start() + Duration::minutes(4));
+
+        // Adding a price evicts against the clock too, not the new point
+        clock.advance(Duration::hours(1));
+        calculator.add_price(point(start() + Duration::minutes(5), 2505.0));
+        assert_eq!(calculator.sample_count(), 0);
+    }
+
+    #[test]
+    fn volatility_waits_for_min_samples_returns() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1));
+        assert_eq!(calculator.min_samples(), DEFAULT_MIN_SAMPLES);
+        calculator.set_min_samples(5);
+
+        // Five returns take six prices; `minute` prices so far give one fewer return
+        for minute in 0..6 {
+            assert!(calculator.calculate_volatility().is_none());
+            assert_eq!(calculator.samples_needed(), 5 - (minute as usize).saturating_sub(1));
+            let price = if minute % 2 == 0 { 2500.0 } else { 2510.0 };
+            calculator.add_price(point(start() + Duration::minutes(minute), price));
+        }
+        assert_eq!(calculator.samples_needed(), 0);
+        assert_eq!(calculator.warmup_progress(), 1.0);
+        assert!(calculator.calculate_volatility().is_some());
+
+        calculator.add_price(point(start() + Duration::minutes(6), 2505.0));
+        assert!(calculator.calculate_volatility().is_some());
+    }
+
+    #[test]
+    fn max_drawdown_is_the_deepest_fall_from_a_running_peak() {
+        assert!(raw_calculator(&[100.0]).max_drawdown().is_none());
+        assert_eq!(raw_calculator(&[100.0, 101.0, 102.0]).max_drawdown(), Some(0.0));
+
+        // 120 -> 90 is 25%; the later 130 -> 117 is only 10%
+        let drawdown = raw_calculator(&[100.0, 120.0, 90.0, 110.0, 130.0, 117.0]).max_drawdown().unwrap();
+        assert!((drawdown - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn atr_seeds_with_the_mean_true_range_then_wilder_smooths() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1));
+        calculator.add_ohlc(bar(0, 100.0, 102.0, 99.0, 101.0));
+        // True ranges: 3 (high - low), 6 (gap up from 102), 6 (gap down from 107)
+        calculator.add_ohlc(bar(1, 101.0, 103.0, 100.0, 102.0));
+        calculator.add_ohlc(bar(2, 106.0, 108.0, 105.0, 107.0));
+        calculator.add_ohlc(bar(3, 103.0, 104.0, 101.0, 103.0));
+
+        // Seed (3 + 6) / 2 = 4.5, then (4.5 * 1 + 6) / 2
+        assert_eq!(calculator.calculate_atr(2), Some(5.25));
+        assert_eq!(calculator.calculate_atr(3), Some(5.0));
+        assert!(calculator.calculate_atr(4).is_none());
+        assert!(calculator.calculate_atr(0).is_none());
+    }
+
+    #[test]
+    fn price_stddev_is_the_sample_stddev_of_the_prices() {
+        assert!(raw_calculator(&[2500.0]).price_stddev().is_none());
+        // Mean 2505, squared deviations 25 + 25 + 225 + 225 = 500, over n - 1 = 3
+        let stddev = raw_calculator(&[2500.0, 2510.0, 2490.0, 2520.0]).price_stddev().unwrap();
+        assert!((stddev - (500.0f64 / 3.0).sqrt()).abs() < 1e-9);
+    }
+
+    // Four returns, the third across an hour's gap. Assuming the typical
+    // (median) one-minute spacing counts that hour's move as one minute's;
+    // time weighting spreads every squared return over the time it took.
+    #[test]
+    fn time_weighting_charges_a_long_gap_for_its_length() {
+        let prices = [(0, 100.0), (60, 101.0), (120, 100.5), (3720, 103.0), (3780, 102.5)];
+        let calculator = |mode: AnnualizationMode| {
+            let mut calculator = VolatilityCalculator::new(Duration::hours(2))
+                .with_min_samples(2)
+                .with_demean(false)
+                .with_interval_estimate(IntervalEstimate::Median)
+                .with_annualizer(Box::new(mode));
+            for (seconds, price) in prices {
+                calculator.add_price(point(start() + Duration::seconds(seconds), price));
+            }
+            calculator
+        };
+        let sum_squares: f64 = prices.windows(2).map(|w| (w[1].1 / w[0].1).ln().powi(2)).sum();
+        let year = 365.0 * 24.0 * 3600.0;
+
+        let uniform = calculator(AnnualizationMode::ObservedInterval).calculate_volatility().unwrap();
+        assert!((uniform - (sum_squares / 4.0 * year / 60.0).sqrt()).abs() < 1e-9);
+        let weighted = calculator(AnnualizationMode::TimeWeighted);
+        let expected = (sum_squares / 3780.0 * year).sqrt();
+        assert!((weighted.calculate_volatility().unwrap() - expected).abs() < 1e-9);
+        let recomputed = weighted.calculate_volatility_for_window(Duration::hours(2), weighted.annualizer.as_ref()).unwrap();
+        assert!((recomputed - expected).abs() < 1e-9);
+        assert!(expected < uniform / 3.0);
+    }
+
+    #[test]
+    fn warmup_progress_counts_returns_toward_min_samples() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1)).with_min_samples(4);
+        let mut progress = vec![calculator.warmup_progress()];
+        for minute in 0..6 {
+            calculator.add_price(point(start() + Duration::minutes(minute), 2500.0 + minute as f64));
+            progress.push(calculator.warmup_progress());
+        }
+        // The first price has no return yet; past four returns it stays at 1
+        assert_eq!(progress, [0.0, 0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn timeframes_rescale_one_estimate_by_the_square_root_of_time() {
+        let quoted = |mode: AnnualizationMode, prices: &[f64]| {
+            let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+                .with_min_samples(3)
+                .with_annualizer(Box::new(mode));
+            for (minute, price) in prices.iter().enumerate() {
+                calculator.add_price(point(start() + Duration::minutes(minute as i64), *price));
+            }
+            (calculator.as_annualized(), calculator.as_daily(), calculator.as_hourly())
+        };
+        let prices = [2500.0, 2510.0, 2495.0, 2505.0, 2520.0];
+
+        // Two returns are below the threshold for every timeframe
+        assert_eq!(quoted(AnnualizationMode::ObservedInterval, &prices[..3]), (None, None, None));
+
+        let (annual, daily, hourly) = quoted(AnnualizationMode::ObservedInterval, &prices);
+        let (annual, daily, hourly) = (annual.unwrap(), daily.unwrap(), hourly.unwrap());
+        assert!((daily * 365f64.sqrt() - annual).abs() < 1e-12);
+        assert!((hourly * 24f64.sqrt() - daily).abs() < 1e-12);
+
+        let (annual, daily, _) = quoted(AnnualizationMode::CalendarDays(252.0), &prices);
+        assert!((daily.unwrap() * 252f64.sqrt() - annual.unwrap()).abs() < 1e-12);
+
+        // A per-interval figure has no year to divide down
+        let (raw, daily, hourly) = quoted(AnnualizationMode::Raw, &prices);
+        assert!(raw.is_some() && daily.is_none() && hourly.is_none());
+    }
+
+    // Bursts of one-second trades a minute apart: gaps 1, 1, 1, 60, ...
+    // average 15.75s, but the typical spacing is one second
+    #[test]
+    fn median_interval_annualizes_bursts_by_their_typical_spacing() {
+        let seconds = [0, 1, 2, 3, 63, 64, 65, 66, 126];
+        let prices = [2500.0, 2501.0, 2499.5, 2502.0, 2498.0, 2500.5, 2499.0, 2503.0, 2501.0];
+        let estimate = |estimate: IntervalEstimate| {
+            let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+                .with_min_samples(2)
+                .with_interval_estimate(estimate);
+            for (second, price) in seconds.iter().zip(prices) {
+                calculator.add_price(point(start() + Duration::seconds(*second), price));
+            }
+            calculator.calculate_volatility().unwrap()
+        };
+        let returns: Vec<f64> = prices.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+        let stddev = mean_and_stddev(&returns).1;
+        let year: f64 = 365.0 * 24.0 * 3600.0;
+
+        let mean = estimate(IntervalEstimate::Mean);
+        let median = estimate(IntervalEstimate::Median);
+        assert!((mean - stddev * (year / 15.75).sqrt()).abs() < 1e-9);
+        assert!((median - stddev * year.sqrt()).abs() < 1e-9);
+        assert!((median / mean - 15.75f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn realized_variance_is_the_sum_of_squared_log_returns() {
+        // One return is below min_samples
+        assert!(raw_calculator(&[100.0, 104.0]).realized_variance().is_none());
+        assert!(raw_calculator(&[100.0, 104.0]).realized_volatility().is_none());
+
+        let calculator = raw_calculator(&[100.0, 104.0, 101.0]);
+        let expected = (104.0f64 / 100.0).ln().powi(2) + (101.0f64 / 104.0).ln().powi(2);
+        assert!((calculator.realized_variance().unwrap() - expected).abs() < 1e-12);
+        assert!((calculator.realized_volatility().unwrap() - expected.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn an_unreadable_history_file_fails_instead_of_being_overwritten() {
+        let path = std::env::temp_dir().join(format!("eth-volatility-{}-unreadable.json", std::process::id()));
+        std::fs::write(&path, "not a history file").unwrap();
+        let result = VolatilityCalculator::with_persistence(Duration::hours(1), path.clone());
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a history file");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn each_window_uses_only_the_points_inside_it() {
+        let prices = [2500.0, 2520.0, 2490.0, 2505.0, 2530.0, 2500.0];
+        let mut multi = MultiWindowCalculator::new(vec![Duration::minutes(5), Duration::minutes(2)]);
+        multi.calculator_mut().set_min_samples(2);
+        multi.calculator_mut().set_annualizer(Box::new(AnnualizationMode::Raw));
+        for (minute, price) in prices.iter().enumerate() {
+            multi.add_price(point(start() + Duration::minutes(minute as i64), *price));
+        }
+
+        // Shortest first; two minutes back from the newest point reaches minute 3
+        let all = multi.calculate_all();
+        assert_eq!(all.iter().map(|(window, _)| *window).collect::<Vec<_>>(), [Duration::minutes(2), Duration::minutes(5)]);
+        let short = raw_calculator(&prices[3..]).calculate_volatility().unwrap();
+        let long = raw_calculator(&prices).calculate_volatility().unwrap();
+        assert!((all[0].1.unwrap() - short).abs() < 1e-12);
+        assert!((all[1].1.unwrap() - long).abs() < 1e-12);
+        assert!((short - long).abs() > 1e-6);
+    }
+
+    #[test]
+    fn confidence_interval_brackets_the_estimate_and_narrows_with_more_returns() {
+        let pattern = [2500.0, 2510.0, 2495.0, 2505.0];
+        let width = |repeats: usize| {
+            let prices: Vec<f64> = pattern.iter().copied().cycle().take(pattern.len() * repeats + 1).collect();
+            let (estimate, lower, upper) = raw_calculator(&prices).volatility_with_ci(0.95).unwrap();
+            assert!(lower < estimate && estimate < upper, "{} not in ({}, {})", estimate, lower, upper);
+            (upper - lower) / estimate
+        };
+        let (few, some, many) = (width(1), width(4), width(15));
+        assert!(few > some && some > many, "{} {} {}", few, some, many);
+
+        // Needs a level strictly between 0 and 1
+        assert!(raw_calculator(&pattern).volatility_with_ci(1.0).is_none());
+        assert!(raw_calculator(&pattern).volatility_with_ci(0.0).is_none());
+    }
+
+    #[test]
+    fn max_points_caps_the_buffer_by_dropping_the_oldest() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1)).with_max_points(5);
+        for minute in 0..12 {
+            calculator.add_price(point(start() + Duration::minutes(minute), 2500.0 + minute as f64));
+            assert_eq!(calculator.sample_count(), (minute as usize + 1).min(5));
+        }
+        // All twelve are inside the hour; only the newest five are kept
+        assert_eq!(calculator.oldest_timestamp(), Some(start() + Duration::minutes(7)));
+        assert_eq!(calculator.prices().map(|p| p.price).collect::<Vec<_>>(), [2507.0, 2508.0, 2509.0, 2510.0, 2511.0]);
+    }
+
+    #[test]
+    fn vwap_weights_by_volume_and_skips_points_without_one() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1));
+        calculator.add_price(point(start(), 2500.0));
+        assert!(calculator.vwap().is_none());
+
+        for (minute, price, volume) in [(1, 2510.0, 3.0), (2, 2490.0, 1.0)] {
+            calculator.add_price(PricePoint { volume: Some(volume), ..point(start() + Duration::minutes(minute), price) });
+        }
+        // The volume-less 2500 doesn't count: (2510 * 3 + 2490 * 1) / 4
+        assert_eq!(calculator.vwap(), Some(2505.0));
+    }
+
+    #[test]
+    fn price_range_is_the_lowest_and_highest_price_in_the_window() {
+        assert!(VolatilityCalculator::new(Duration::hours(1)).price_range().is_none());
+        assert_eq!(raw_calculator(&[2500.0]).price_range(), Some((2500.0, 2500.0)));
+        assert_eq!(raw_calculator(&[2500.0, 2530.0, 2480.0, 2510.0]).price_range(), Some((2480.0, 2530.0)));
+    }
+
+    #[test]
+    fn one_outlier_moves_mad_volatility_less_than_the_stddev() {
+        let quiet: Vec<f64> = (0..21).map(|i| [2500.0, 2502.5, 2501.0][i % 3]).collect();
+        let mut spiked = quiet.clone();
+        // One bad print, and the return back from it
+        spiked[10] = 2750.0;
+
+        let (quiet, spiked) = (raw_calculator(&quiet), raw_calculator(&spiked));
+        let mad_change = spiked.calculate_mad_volatility().unwrap() / quiet.calculate_mad_volatility().unwrap();
+        let stddev_change = spiked.calculate_volatility().unwrap() / quiet.calculate_volatility().unwrap();
+        assert!(mad_change < 1.5, "MAD moved {}x", mad_change);
+        assert!(stddev_change > 10.0, "stddev moved {}x", stddev_change);
+    }
+
+    #[test]
+    fn each_source_calculator_tracks_only_its_own_prices() {
+        let mut sources = SourceCalculators::new(|| {
+            VolatilityCalculator::new(Duration::hours(1))
+                .with_min_samples(2)
+                .with_annualizer(Box::new(AnnualizationMode::Raw))
+        });
+        let (calm, wild) = ([2500.0, 2501.0, 2500.5, 2501.5], [2500.0, 2550.0, 2450.0, 2525.0]);
+        for (minute, (a, b)) in calm.iter().zip(wild).enumerate() {
+            let timestamp = start() + Duration::minutes(minute as i64);
+            sources.add_price(PricePoint { source: "Binance".to_string(), ..point(timestamp, *a) });
+            sources.add_price(PricePoint { source: "Kraken".to_string(), ..point(timestamp, b) });
+        }
+
+        assert_eq!(sources.sources().collect::<Vec<_>>(), ["Binance", "Kraken"]);
+        let binance = sources.calculator("Binance").unwrap();
+        assert_eq!(binance.prices().map(|p| p.price).collect::<Vec<_>>(), calm);
+        assert_eq!(binance.calculate_volatility(), raw_calculator(&calm).calculate_volatility());
+        assert_eq!(sources.calculate_all(), [
+            ("Binance", raw_calculator(&calm).calculate_volatility()),
+            ("Kraken", raw_calculator(&wild).calculate_volatility()),
+        ]);
+        assert!(sources.calculator("Coinbase").is_none());
+    }
+
+    #[test]
+    fn bollinger_bands_sit_k_sample_stddevs_either_side_of_the_mean() {
+        // Two prices give one return, short of min_samples
+        assert!(raw_calculator(&[2500.0, 2510.0]).bollinger_bands(2.0).is_none());
+
+        // Mean 2505, sample stddev sqrt(500 / 3)
+        let (mean, upper, lower) = raw_calculator(&[2500.0, 2510.0, 2490.0, 2520.0]).bollinger_bands(2.0).unwrap();
+        let stddev = (500.0f64 / 3.0).sqrt();
+        assert!((mean - 2505.0).abs() < 1e-9);
+        assert!((upper - (2505.0 + 2.0 * stddev)).abs() < 1e-9);
+        assert!((lower - (2505.0 - 2.0 * stddev)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn window_return_runs_from_the_oldest_to_the_newest_price() {
+        assert!(raw_calculator(&[2500.0]).window_return().is_none());
+        // The path in between doesn't matter
+        let window_return = raw_calculator(&[2500.0, 2400.0, 2700.0, 2550.0]).window_return().unwrap();
+        assert!((window_return - 0.02).abs() < 1e-12);
+        assert!((raw_calculator(&[2500.0, 2250.0]).window_return().unwrap() + 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn volatility_cone_covers_only_the_horizons_the_history_spans() {
+        // An hour of minute prices, calmer in the last ten minutes
+        let prices: Vec<f64> = (0..=60).map(|i| {
+            let step = if i > 50 { 1.0 } else { 10.0 };
+            2500.0 + if i % 2 == 0 { step } else { -step }
+        }).collect();
+        let calculator = raw_calculator(&prices);
+
+        let cone = calculator.volatility_cone(&[Duration::minutes(10), Duration::minutes(30), Duration::hours(2)]);
+        assert_eq!(cone.iter().map(|(horizon, _)| *horizon).collect::<Vec<_>>(), [Duration::minutes(10), Duration::minutes(30)]);
+        // Each horizon measured back from the newest point
+        assert!((cone[0].1 - raw_calculator(&prices[50..]).calculate_volatility().unwrap()).abs() < 1e-12);
+        assert!((cone[1].1 - raw_calculator(&prices[30..]).calculate_volatility().unwrap()).abs() < 1e-12);
+        assert!(cone[0].1 < cone[1].1);
+    }
+
+    #[test]
+    fn snapshot_serializes_the_window_state() {
+        let empty = serde_json::to_value(VolatilityCalculator::new(Duration::hours(1)).snapshot()).unwrap();
+        assert_eq!(empty["sample_count"], 0);
+        assert!(empty["oldest_timestamp"].is_null() && empty["annualized_volatility"].is_null());
+
+        let prices = [2500.0, 2520.0, 2490.0];
+        let calculator = raw_calculator(&prices);
+        let snapshot = serde_json::to_value(calculator.snapshot()).unwrap();
+        let realized = (2520.0f64 / 2500.0).ln().powi(2) + (2490.0f64 / 2520.0).ln().powi(2);
+        assert_eq!(snapshot, serde_json::json!({
+            "sample_count": 3,
+            "window_seconds": 3600,
+            "oldest_timestamp": "2026-01-01T00:00:00Z",
+            "newest_timestamp": "2026-01-01T00:02:00Z",
+            "annualized_volatility": calculator.calculate_volatility().unwrap(),
+            "realized_variance": realized,
+            "min_price": 2490.0,
+            "max_price": 2520.0,
+        }));
+    }
+
+    #[test]
+    fn a_suppressed_flat_window_is_told_apart_from_warmup() {
+        let mut calculator = VolatilityCalculator::new(Duration::hours(1))
+            .with_min_samples(2)
+            .with_flat_window_policy(FlatWindowPolicy::Suppress);
+        for minute in 0..3 {
+            calculator.add_price(point(start() + Duration::minutes(minute), 2500.0));
+        }
+        assert_eq!(calculator.samples_needed(), 0);
+        assert!(calculator.calculate_volatility().is_none());
+        assert!(calculator.flat_window_suppressed());
+
+        // One move is enough to report again
+        calculator.add_price(point(start() + Duration::minutes(3), 2501.0));
+        assert!(!calculator.flat_window_suppressed());
+        assert!(calculator.calculate_volatility().is_some());
+
+        let mut reporting = VolatilityCalculator::new(Duration::hours(1)).with_min_samples(2);
+        for minute in 0..3 {
+            reporting.add_price(point(start() + Duration::minutes(minute), 2500.0));
+        }
+        assert!(!reporting.flat_window_suppressed());
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+
+// Retained history of computed volatility readings, oldest first
+pub struct VolatilitySeries {
+    readings: VecDeque<(DateTime<Utc>, f64)>,
+    max_len: usize,
+    // When set, at most one reading is committed per interval, stamped with
+    // the start of its interval so the stored series has a regular step
+    interval: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendDirection {
+    Rising,
+    Falling,
+    Flat,
+}
+
+impl fmt::Display for TrendDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Rising => write!(f, "rising"),
+            Self::Falling => write!(f, "falling"),
+            Self::Flat => write!(f, "flat"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VolatilityTrend {
+    // Change in annualized volatility per hour
+    pub slope_per_hour: f64,
+    pub direction: TrendDirection,
+}
+
+// Exponential moving average over successive estimates, to steady the
+// reported series. `alpha` is the weight of each new value, in (0, 1].
+#[derive(Debug, Clone, Copy)]
+pub struct EmaFilter {
+    alpha: f64,
+    state: Option<f64>,
+}
+
+impl EmaFilter {
+    pub fn new(alpha: f64) -> Self {
+        Self { alpha, state: None }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    // The first value passes through unchanged
+    pub fn update(&mut self, value: f64) -> f64 {
+        let smoothed = match self.state {
+            Some(previous) => self.alpha * value + (1.0 - self.alpha) * previous,
+            None => value,
+        };
+        self.state = Some(smoothed);
+        smoothed
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.state
+    }
+
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+// Fewer readings than this don't make a meaningful regression
+const MIN_TREND_READINGS: usize = 3;
+// Below this a percentile says more about the sample size than the level
+const MIN_PERCENTILE_READINGS: usize = 10;
+
+impl VolatilitySeries {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            readings: VecDeque::with_capacity(max_len),
+            max_len,
+            interval: None,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval).filter(|i| *i > Duration::zero());
+        self
+    }
+
+    pub fn record(&mut self, timestamp: DateTime<Utc>, volatility: f64) {
+        let timestamp = match self.interval {
+            Some(interval) => {
+                let step = interval.num_milliseconds();
+                let slot = timestamp.timestamp_millis().div_euclid(step) * step;
+                let slot_start = DateTime::from_timestamp_millis(slot).unwrap_or(timestamp);
+                // Already have a reading for this interval
+                if self.readings.back().is_some_and(|(last, _)| *last >= slot_start) {
+                    return;
+                }
+                slot_start
+            }
+            None => timestamp,
+        };
+
+        self.readings.push_back((timestamp, volatility));
+        while self.readings.len() > self.max_len {
+            self.readings.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    // Where the latest reading sits among all retained ones, from 0 to 100.
+    // Ties count half, so a reading equal to every other one scores 50.
+    // None until there are MIN_PERCENTILE_READINGS readings.
+    pub fn current_percentile(&self) -> Option<f64> {
+        if self.readings.len() < MIN_PERCENTILE_READINGS {
+            return None;
+        }
+        let (_, latest) = *self.readings.back()?;
+        let (below, equal) = self.readings.iter().fold((0usize, 0usize), |(below, equal), (_, vol)| {
+            if *vol < latest {
+                (below + 1, equal)
+            } else if *vol == latest {
+                (below, equal + 1)
+            } else {
+                (below, equal)
+            }
+        });
+        Some((below as f64 + 0.5 * equal as f64) / self.readings.len() as f64 * 100.0)
+    }
+
+    // Least-squares slope of volatility against time. Slopes smaller in
+    // magnitude than `flat_threshold` (per hour) are labelled flat.
+    pub fn trend(&self, flat_threshold: f64) -> Option<VolatilityTrend> {
+        if self.readings.len() < MIN_TREND_READINGS {
+            return None;
+        }
+
+        let origin = self.readings.front()?.0;
+        let points: Vec<(f64, f64)> = self.readings.iter()
+            .map(|(ts, vol)| ((*ts - origin).num_milliseconds() as f64 / 3_600_000.0, *vol))
+            .collect();
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance = points.iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let variance_x = points.iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+
+        // All readings share a timestamp, so there is no time axis to regress on
+        if variance_x == 0.0 {
+            return None;
+        }
+
+        let slope_per_hour = covariance / variance_x;
+        let direction = if slope_per_hour.abs() < flat_threshold {
+            TrendDirection::Flat
+        } else if slope_per_hour > 0.0 {
+            TrendDirection::Rising
+        } else {
+            TrendDirection::Falling
+        };
+
+        Some(VolatilityTrend { slope_per_hour, direction })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn series_of(max_len: usize, readings: &[f64]) -> VolatilitySeries {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut series = VolatilitySeries::new(max_len);
+        for (minute, volatility) in readings.iter().enumerate() {
+            series.record(start + Duration::minutes(minute as i64), *volatility);
+        }
+        series
+    }
+
+    #[test]
+    fn percentile_ranks_the_latest_reading_among_the_retained_ones() {
+        let percentile = |series: VolatilitySeries| series.current_percentile().map(|p| (p * 1e9).round() / 1e9);
+        let deciles = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+        assert!(series_of(10, &deciles).current_percentile().is_none());
+
+        // Five of ten below it, plus half for itself
+        let mut readings = deciles.to_vec();
+        readings.push(0.55);
+        assert_eq!(percentile(series_of(10, &readings)), Some(55.0));
+
+        // 0.1 is pushed out, leaving nine below
+        readings.push(0.95);
+        let series = series_of(10, &readings);
+        assert_eq!(series.len(), 10);
+        assert_eq!(percentile(series), Some(95.0));
+
+        assert_eq!(percentile(series_of(10, &[0.4; 12])), Some(50.0));
+    }
+
+    #[test]
+    fn ema_follows_its_recurrence_from_the_first_value() {
+        let mut ema = EmaFilter::new(0.25);
+        assert_eq!(ema.value(), None);
+        // 0.8, then 0.25 * 0.4 + 0.75 * 0.8 = 0.7, then 0.25 * 1.0 + 0.75 * 0.7 = 0.775
+        let smoothed: Vec<f64> = [0.8, 0.4, 1.0].into_iter().map(|v| ema.update(v)).collect();
+        for (actual, expected) in smoothed.iter().zip([0.8, 0.7, 0.775]) {
+            assert!((actual - expected).abs() < 1e-12, "{:?}", smoothed);
+        }
+        assert_eq!(ema.value(), smoothed.last().copied());
+
+        ema.reset();
+        assert_eq!(ema.update(0.3), 0.3);
+    }
+}
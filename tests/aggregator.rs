@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use eth_volatility::error::VolatilityError;
+use eth_volatility::manual::{EmptyQueuePolicy, ManualCollector};
+use eth_volatility::price_collector::{
+    AggregationStrategy, AggregationTimestamp, BoxedCollector, CircuitBreaker, DivergenceAction,
+    PriceAggregator, PricePoint,
+};
+
+// 2023-11-14T22:13:20Z
+const T0: i64 = 1_700_000_000_000;
+
+fn point(source: &str, price: f64) -> PricePoint {
+    point_at(source, price, 0)
+}
+
+fn point_at(source: &str, price: f64, offset_ms: i64) -> PricePoint {
+    PricePoint {
+        timestamp: DateTime::<Utc>::from_timestamp_millis(T0 + offset_ms).unwrap(),
+        price,
+        source: source.to_string(),
+        spread: None,
+        volume: None,
+    }
+}
+
+// One manual source per price, each already holding its single tick
+fn sources(prices: &[(&str, f64)]) -> Vec<BoxedCollector> {
+    prices.iter()
+        .map(|&(name, price)| {
+            let collector = ManualCollector::new().with_name(name);
+            collector.push(point(name, price));
+            Box::new(collector) as BoxedCollector
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn dropping_a_diverging_source_still_honours_min_sources() {
+    let aggregator = PriceAggregator::new(
+        sources(&[("A", 2500.0), ("B", 2501.0), ("C", 2700.0)]),
+        AggregationStrategy::Mean,
+    )
+    .with_min_sources(3)
+    .with_max_divergence(Some(0.01), DivergenceAction::Drop);
+
+    assert!(matches!(
+        aggregator.get_aggregated_price().await,
+        Err(VolatilityError::InsufficientSources { got: 2, need: 3 })
+    ));
+}
+
+#[tokio::test]
+async fn two_diverging_sources_do_not_pass_one_off_as_the_aggregate() {
+    let aggregator = PriceAggregator::new(
+        sources(&[("A", 2500.0), ("B", 2700.0)]),
+        AggregationStrategy::Mean,
+    )
+    .with_min_sources(2)
+    .with_max_divergence(Some(0.01), DivergenceAction::Drop);
+
+    assert!(matches!(
+        aggregator.get_aggregated_price().await,
+        Err(VolatilityError::InsufficientSources { got: 1, need: 2 })
+    ));
+}
+
+#[tokio::test]
+async fn diverging_source_is_dropped_when_enough_remain() {
+    let aggregator = PriceAggregator::new(
+        sources(&[("A", 2500.0), ("B", 2502.0), ("C", 2700.0)]),
+        AggregationStrategy::Mean,
+    )
+    .with_min_sources(2)
+    .with_max_divergence(Some(0.01), DivergenceAction::Drop);
+
+    let aggregate = aggregator.get_aggregated_price().await.unwrap();
+    assert_eq!(aggregate.price, 2501.0);
+    assert_eq!(aggregate.source, "Aggregated");
+}
+
+#[tokio::test]
+async fn blended_timestamps_never_go_backwards() {
+    let a = ManualCollector::new().with_name("A");
+    let b = ManualCollector::new().with_name("B");
+    let (feed_a, feed_b) = (a.feed(), b.feed());
+    let aggregator = PriceAggregator::new(vec![Box::new(a), Box::new(b)], AggregationStrategy::Mean)
+        .with_timestamp_mode(AggregationTimestamp::Oldest);
+
+    feed_a.push(point_at("A", 2500.0, 10_000));
+    feed_b.push(point_at("B", 2502.0, 20_000));
+    let first = aggregator.get_aggregated_price().await.unwrap();
+    assert_eq!(first.timestamp.timestamp_millis(), T0 + 10_000);
+
+    // A's next tick is older than its last one: the oldest would be T0+5s
+    feed_a.push(point_at("A", 2501.0, 5_000));
+    feed_b.push(point_at("B", 2503.0, 30_000));
+    let second = aggregator.get_aggregated_price().await.unwrap();
+    assert_eq!(second.timestamp.timestamp_millis(), T0 + 10_000);
+
+    feed_a.push(point_at("A", 2504.0, 40_000));
+    feed_b.push(point_at("B", 2506.0, 35_000));
+    let third = aggregator.get_aggregated_price().await.unwrap();
+    assert_eq!(third.timestamp.timestamp_millis(), T0 + 35_000);
+}
+
+#[tokio::test]
+async fn a_failing_source_is_skipped_for_the_cooldown_then_probed() {
+    let a = ManualCollector::new().with_name("A");
+    // Fails whenever nothing is queued for it
+    let b = ManualCollector::new().with_name("B").with_empty_policy(EmptyQueuePolicy::Error);
+    let (feed_a, feed_b) = (a.feed(), b.feed());
+    let aggregator = PriceAggregator::new(vec![Box::new(a), Box::new(b)], AggregationStrategy::Mean)
+        .with_circuit_breaker(Some(CircuitBreaker {
+            failures: 2,
+            cooldown: std::time::Duration::from_millis(200),
+            max_latency: None,
+        }));
+    let skipped = || aggregator.source_stats().iter().find(|stats| stats.source == "B").map_or(0, |stats| stats.skipped);
+
+    for _ in 0..2 {
+        feed_a.push(point("A", 2500.0));
+        assert_eq!(aggregator.get_aggregated_price().await.unwrap().price, 2500.0);
+    }
+
+    // Tripped: B's queued price is left alone and A alone sets the aggregate
+    feed_a.push(point("A", 2500.0));
+    feed_b.push(point("B", 2510.0));
+    assert_eq!(aggregator.get_aggregated_price().await.unwrap().price, 2500.0);
+    assert_eq!(feed_b.pending(), 1);
+    assert_eq!(skipped(), 1);
+
+    // After the cooldown B is probed, answers and rejoins the blend
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    feed_a.push(point("A", 2500.0));
+    assert_eq!(aggregator.get_aggregated_price().await.unwrap().price, 2505.0);
+    assert_eq!(feed_b.pending(), 0);
+
+    feed_a.push(point("A", 2500.0));
+    feed_b.push(point("B", 2520.0));
+    assert_eq!(aggregator.get_aggregated_price().await.unwrap().price, 2510.0);
+    assert_eq!(skipped(), 1);
+}
+
+#[tokio::test]
+async fn volume_weighting_gives_unknown_volumes_the_mean_known_weight() {
+    let collectors = [(2500.0, Some(3.0)), (2510.0, Some(1.0)), (2520.0, None)]
+        .into_iter()
+        .zip(["A", "B", "C"])
+        .map(|((price, volume), name)| {
+            let collector = ManualCollector::new().with_name(name);
+            collector.push(PricePoint { volume, ..point(name, price) });
+            Box::new(collector) as BoxedCollector
+        })
+        .collect();
+    let aggregator = PriceAggregator::new(collectors, AggregationStrategy::VolumeWeighted);
+
+    // C weighs the mean of 3 and 1: (2500 * 3 + 2510 * 1 + 2520 * 2) / 6
+    let aggregate = aggregator.get_aggregated_price().await.unwrap();
+    assert!((aggregate.price - 15_050.0 / 6.0).abs() < 1e-9, "got {}", aggregate.price);
+    assert_eq!(aggregate.volume, Some(4.0));
+}
@@ -0,0 +1,405 @@
+mod common;
+
+use std::time::Duration;
+use common::{MockBinanceServer, Step};
+use eth_volatility::error::VolatilityError;
+use eth_volatility::price_collector::{BinanceCollector, BinanceStream, CollectorError, PriceCollector};
+
+// 2023-11-14T22:13:20Z
+const T0: i64 = 1_700_000_000_000;
+
+fn book_ticker(bid: f64, ask: f64) -> Step {
+    Step::Text(serde_json::json!({"u": 1, "s": "ETHUSDT", "b": bid.to_string(), "B": "1", "a": ask.to_string(), "A": "1"}).to_string())
+}
+
+fn collector(server: &MockBinanceServer) -> BinanceCollector {
+    BinanceCollector::new(server.url(), "ETHUSDT")
+        .with_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50))
+}
+
+#[tokio::test]
+async fn streams_trades_after_subscribing() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Ack,
+        Step::Trade(2500.25, T0),
+        // Sent after the first fetch returns, so it isn't skipped as backlog
+        Step::Pause(Duration::from_millis(200)),
+        Step::Trade(2501.5, T0 + 1_000),
+    ]]).await;
+    let collector = collector(&server);
+
+    let first = collector.get_latest_price().await.unwrap();
+    let second = collector.get_latest_price().await.unwrap();
+    assert_eq!(first.price, 2500.25);
+    assert_eq!(first.timestamp.timestamp_millis(), T0);
+    assert_eq!(first.source, "Binance:ETHUSDT");
+    assert_eq!(second.price, 2501.5);
+
+    let sent: serde_json::Value = serde_json::from_str(&server.received_text()[0]).unwrap();
+    assert_eq!(sent, serde_json::json!({"method": "SUBSCRIBE", "params": ["ethusdt@trade"], "id": 1}));
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn subscribes_to_the_configured_symbol() {
+    let collector = BinanceCollector::new("wss://unused".to_string(), "BTCUSDC");
+    let sent: serde_json::Value = serde_json::from_str(&collector.subscription_message()).unwrap();
+    assert_eq!(sent, serde_json::json!({"method": "SUBSCRIBE", "params": ["btcusdc@trade"], "id": 1}));
+    assert_eq!(collector.source_label(), "Binance:BTCUSDC");
+}
+
+#[tokio::test]
+async fn keeps_trades_sent_before_the_ack() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Trade(2499.0, T0),
+        Step::Ack,
+        Step::Pause(Duration::from_millis(200)),
+        Step::Trade(2500.0, T0 + 1_000),
+    ]]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2499.0);
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+}
+
+#[tokio::test]
+async fn reconnects_when_the_subscription_is_never_confirmed() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Pause(Duration::from_secs(30))],
+        vec![Step::Ack, Step::Trade(2500.0, T0)],
+    ]).await;
+    let collector = collector(&server).with_subscription_timeout(Duration::from_millis(200));
+
+    let started = std::time::Instant::now();
+    assert!(collector.get_latest_price().await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn skips_malformed_messages() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Ack,
+        Step::Text(r#"{"e":"trade","p":"not a number","T":1700000000000}"#.to_string()),
+        Step::Text("not json".to_string()),
+        Step::Trade(2500.0, T0),
+    ]]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+}
+
+#[tokio::test]
+async fn reconnects_after_a_dropped_connection() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Trade(2500.0, T0), Step::Drop],
+        vec![Step::Ack, Step::Trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.consecutive_errors(), 1);
+
+    let resumed = collector.get_latest_price().await.unwrap();
+    assert_eq!(resumed.price, 2600.0);
+    assert_eq!(collector.consecutive_errors(), 0);
+    assert_eq!(server.connections(), 2);
+    assert_eq!(server.received_text().len(), 2, "one subscription per connection");
+}
+
+#[tokio::test]
+async fn stale_feed_times_out_and_reconnects() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Pause(Duration::from_secs(5))],
+        vec![Step::Ack, Step::Trade(2500.0, T0)],
+    ]).await;
+    let collector = collector(&server).with_stale_timeout(Duration::from_millis(200));
+
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn close_sends_a_close_frame() {
+    let server = MockBinanceServer::start(vec![vec![Step::Ack, Step::Trade(2500.0, T0)]]).await;
+    let collector = collector(&server);
+
+    collector.get_latest_price().await.unwrap();
+    collector.close().await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.received_close());
+}
+
+#[tokio::test]
+async fn reconnects_after_the_server_closes() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Trade(2500.0, T0), Step::Close],
+        vec![Step::Ack, Step::Trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2600.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn book_ticker_returns_the_newest_queued_quote() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Ack,
+        book_ticker(2500.0, 2500.2),
+        Step::Pause(Duration::from_millis(200)),
+        book_ticker(2501.0, 2501.2),
+        book_ticker(2502.0, 2502.2),
+        book_ticker(2503.0, 2503.2),
+    ]]).await;
+    let collector = collector(&server).with_stream(BinanceStream::BookTicker);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.1);
+    // All three later quotes are waiting by now; only the last one counts
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let newest = collector.get_latest_price().await.unwrap();
+    assert!((newest.price - 2503.1).abs() < 1e-9, "got {}", newest.price);
+}
+
+#[tokio::test]
+async fn trades_that_queue_up_between_fetches_collapse_to_the_newest() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Ack,
+        Step::Trade(2500.0, T0),
+        Step::Pause(Duration::from_millis(200)),
+        Step::Trade(2501.0, T0 + 1_000),
+        Step::Trade(2502.0, T0 + 2_000),
+        Step::Trade(2503.0, T0 + 3_000),
+        Step::Pause(Duration::from_millis(200)),
+        Step::Trade(2504.0, T0 + 4_000),
+    ]]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2503.0);
+    // The backlog is gone, so the next fetch waits for the next trade
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2504.0);
+}
+
+#[tokio::test]
+async fn backlogged_trades_do_not_read_as_clock_skew() {
+    let now = chrono::Utc::now().timestamp_millis();
+    // Both arrive before the ack, so they're queued together
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Trade(2500.0, now - 60_000),
+        Step::Trade(2501.0, now),
+        Step::Ack,
+    ]]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2501.0);
+    assert!(collector.clock_skew().num_seconds().abs() < 5, "skew {:?}", collector.clock_skew());
+}
+
+#[tokio::test]
+async fn measures_skew_on_the_newest_trade() {
+    let now = chrono::Utc::now().timestamp_millis();
+    let server = MockBinanceServer::start(vec![vec![Step::Ack, Step::Trade(2500.0, now + 120_000)]]).await;
+    let collector = collector(&server);
+
+    collector.get_latest_price().await.unwrap();
+    assert!((115..=125).contains(&collector.clock_skew().num_seconds()), "skew {:?}", collector.clock_skew());
+}
+
+#[tokio::test]
+async fn reconnects_when_a_ping_goes_unanswered() {
+    let server = MockBinanceServer::start(vec![
+        // Not reading, so the ping is never answered
+        vec![Step::Ack, Step::Pause(Duration::from_secs(30))],
+        vec![Step::Ack, Step::Trade(2500.0, T0)],
+    ]).await;
+    let collector = collector(&server).with_ping_interval(Duration::from_millis(100));
+
+    let started = std::time::Instant::now();
+    assert!(collector.get_latest_price().await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn answered_pings_keep_a_quiet_connection() {
+    // After its script the server keeps reading, which answers each ping
+    let server = MockBinanceServer::start(vec![vec![Step::Ack]]).await;
+    let collector = collector(&server).with_ping_interval(Duration::from_millis(50));
+
+    let quiet = tokio::time::timeout(Duration::from_millis(500), collector.get_latest_price()).await;
+    assert!(quiet.is_err(), "the fetch should still be waiting for a trade");
+    assert_eq!(server.connections(), 1);
+}
+
+#[tokio::test]
+async fn a_stalled_read_does_not_block_the_runtime() {
+    let server = MockBinanceServer::start(vec![vec![Step::Ack, Step::Pause(Duration::from_secs(30))]]).await;
+    let collector = collector(&server);
+
+    // Single-threaded runtime: a blocking read would starve the timer below
+    let fetch = tokio::spawn(async move { collector.get_latest_price().await });
+    let started = std::time::Instant::now();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(!fetch.is_finished());
+    fetch.abort();
+}
+
+const TRADE_EVENT: &str = r#"{"e":"trade","E":1700000000005,"s":"ETHUSDT","t":12345,"p":"2500.25000000","q":"0.10000000","T":1700000000000,"m":true,"M":true}"#;
+const AGG_TRADE_EVENT: &str = r#"{"e":"aggTrade","E":1700000000105,"s":"ETHUSDT","a":54321,"p":"2500.50000000","q":"1.25000000","f":100,"l":105,"T":1700000000100,"m":false,"M":true}"#;
+
+#[test]
+fn parses_trade_and_agg_trade_events_for_the_chosen_stream() {
+    let trades = BinanceCollector::new("wss://unused".to_string(), "ETHUSDT");
+    let point = trades.parse_trade(TRADE_EVENT).unwrap().unwrap();
+    assert_eq!((point.price, point.volume), (2500.25, Some(0.1)));
+    assert_eq!(point.timestamp.timestamp_millis(), T0);
+
+    let agg_trades = BinanceCollector::new("wss://unused".to_string(), "ETHUSDT").with_stream(BinanceStream::AggTrade);
+    let point = agg_trades.parse_trade(AGG_TRADE_EVENT).unwrap().unwrap();
+    assert_eq!((point.price, point.volume), (2500.5, Some(1.25)));
+    assert_eq!(point.timestamp.timestamp_millis(), T0 + 100);
+    let sent: serde_json::Value = serde_json::from_str(&agg_trades.subscription_message()).unwrap();
+    assert_eq!(sent["params"], serde_json::json!(["ethusdt@aggTrade"]));
+
+    // Each stream ignores the other's events
+    assert!(trades.parse_trade(AGG_TRADE_EVENT).unwrap().is_none());
+    assert!(agg_trades.parse_trade(TRADE_EVENT).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn a_hung_read_times_out_and_keeps_the_socket_by_default() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Ack,
+        Step::Pause(Duration::from_millis(600)),
+        Step::Trade(2500.0, T0),
+    ]]).await;
+    let collector = collector(&server).with_read_timeout(Duration::from_millis(200), false);
+
+    let error = collector.get_latest_price().await.unwrap_err();
+    assert!(matches!(error, VolatilityError::Collector(CollectorError::Timeout(_))), "{}", error);
+    assert!(error.is_transient());
+    // The delayed trade still arrives on the same connection
+    let price = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(price) = collector.get_latest_price().await {
+                return price;
+            }
+        }
+    }).await.unwrap();
+    assert_eq!(price.price, 2500.0);
+    assert_eq!(server.connections(), 1);
+}
+
+#[tokio::test]
+async fn a_hung_read_can_reconnect_instead() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Pause(Duration::from_secs(30))],
+        vec![Step::Ack, Step::Trade(2500.0, T0)],
+    ]).await;
+    let collector = collector(&server).with_read_timeout(Duration::from_millis(200), true);
+
+    let error = collector.get_latest_price().await.unwrap_err();
+    assert!(matches!(error, VolatilityError::Collector(CollectorError::Timeout(_))), "{}", error);
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn backfill_requests_klines_from_the_configured_rest_base() {
+    use std::sync::{Arc, Mutex};
+    use axum::{extract::RawQuery, routing::get, Json, Router};
+
+    let queries = Arc::new(Mutex::new(Vec::new()));
+    let seen = queries.clone();
+    let app = Router::new().route("/api/v3/klines", get(move |RawQuery(query): RawQuery| async move {
+        seen.lock().unwrap().push(query.unwrap_or_default());
+        // [open time, open, high, low, close, volume, close time, ...]
+        Json(serde_json::json!([
+            [T0 - 60_000, "2490.0", "2505.0", "2485.0", "2500.0", "12.5", T0 - 1, "0", 10, "0", "0", "0"],
+            [T0, "2500.0", "2512.0", "2498.0", "2510.0", "8.0", T0 + 59_999, "0", 7, "0", "0", "0"],
+        ]))
+    }));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+    let collector = BinanceCollector::new("wss://unused".to_string(), "ETHUSDT").with_rest_base(&base);
+    assert_eq!(collector.klines_url(), format!("{}api/v3/klines", base));
+    let history = collector.fetch_historical("ethusdt", "1m", 5000).await.unwrap();
+
+    assert_eq!(queries.lock().unwrap().as_slice(), ["symbol=ETHUSDT&interval=1m&limit=1000"]);
+    let closes: Vec<_> = history.iter().map(|p| (p.timestamp.timestamp_millis(), p.price, p.volume)).collect();
+    assert_eq!(closes, [(T0 - 1, 2500.0, Some(12.5)), (T0 + 59_999, 2510.0, Some(8.0))]);
+}
+
+#[tokio::test]
+async fn the_price_stream_yields_each_fetch_and_carries_on_past_an_error() {
+    use futures_util::StreamExt;
+
+    let server = MockBinanceServer::start(vec![
+        vec![
+            Step::Ack,
+            Step::Trade(2500.0, T0),
+            Step::Pause(Duration::from_millis(200)),
+            Step::Trade(2501.0, T0 + 1_000),
+            Step::Drop,
+        ],
+        vec![Step::Ack, Step::Trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = collector(&server);
+
+    let items: Vec<_> = collector.stream().take(4).collect().await;
+    let prices: Vec<_> = items.iter().map(|item| item.as_ref().ok().map(|p| p.price)).collect();
+    assert_eq!(prices, [Some(2500.0), Some(2501.0), None, Some(2600.0)]);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn reconnect_count_counts_each_reconnect_after_the_first_connection() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Trade(2500.0, T0), Step::Drop],
+        vec![Step::Ack, Step::Trade(2600.0, T0 + 5_000), Step::Close],
+        vec![Step::Ack, Step::Trade(2700.0, T0 + 10_000)],
+    ]).await;
+    let collector = collector(&server);
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert_eq!(collector.reconnect_count(), 0);
+    for (price, reconnects) in [(2600.0, 1), (2700.0, 2)] {
+        assert!(collector.get_latest_price().await.is_err());
+        assert_eq!(collector.get_latest_price().await.unwrap().price, price);
+        assert_eq!(collector.reconnect_count(), reconnects);
+    }
+    assert_eq!(server.connections(), 3);
+}
+
+#[tokio::test]
+async fn reconnect_attempts_are_spaced_by_the_min_interval() {
+    let server = MockBinanceServer::start(vec![
+        vec![Step::Ack, Step::Trade(2500.0, T0), Step::Drop],
+        vec![Step::Ack, Step::Trade(2600.0, T0 + 5_000), Step::Drop],
+        vec![Step::Ack, Step::Trade(2700.0, T0 + 10_000)],
+    ]).await;
+    let interval = Duration::from_millis(300);
+    let collector = collector(&server).with_min_reconnect_interval(interval);
+
+    let started = std::time::Instant::now();
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert!(started.elapsed() < interval, "first connect took {:?}", started.elapsed());
+    // Each drop fails one fetch and the next reconnects at once, but waits
+    // out the interval from the attempt before it
+    for (n, price) in [(1, 2600.0), (2, 2700.0)] {
+        assert!(collector.get_latest_price().await.is_err());
+        assert_eq!(collector.get_latest_price().await.unwrap().price, price);
+        assert!(started.elapsed() >= n * interval, "connection {} after {:?}", n + 1, started.elapsed());
+    }
+}
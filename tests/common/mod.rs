@@ -0,0 +1,135 @@
+// A local stand-in for Binance's WebSocket API. Each accepted connection
+// reads the client's subscription, then plays the next script in order;
+// connections beyond the last script are closed straight away. Everything
+// the client sends is recorded for the test to inspect.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+pub enum Step {
+    // The success reply to SUBSCRIBE id 1
+    Ack,
+    // A trade event at this price and exchange time in milliseconds
+    Trade(f64, i64),
+    // Any raw text frame
+    Text(String),
+    // Any raw binary frame
+    Binary(Vec<u8>),
+    Pause(Duration),
+    // A Close frame, then keep reading until the client closes too
+    Close,
+    // Drop the TCP connection without a closing handshake
+    Drop,
+}
+
+pub struct MockBinanceServer {
+    url: String,
+    received: Arc<Mutex<Vec<Message>>>,
+    connections: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+}
+
+impl MockBinanceServer {
+    pub async fn start(scripts: Vec<Vec<Step>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let url = format!("ws://{}", listener.local_addr().expect("mock server address"));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn(serve(listener, scripts, received.clone(), connections.clone()));
+        Self { url, received, connections, task }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    // Connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    // Text frames the client sent, across every connection
+    pub fn received_text(&self) -> Vec<String> {
+        self.received.lock().unwrap().iter()
+            .filter_map(|msg| match msg {
+                Message::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Whether the client sent a Close frame on any connection
+    pub fn received_close(&self) -> bool {
+        self.received.lock().unwrap().iter().any(|msg| matches!(msg, Message::Close(_)))
+    }
+}
+
+impl Drop for MockBinanceServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub fn trade_message(price: f64, timestamp_ms: i64) -> String {
+    serde_json::json!({
+        "e": "trade",
+        "s": "ETHUSDT",
+        "p": price.to_string(),
+        "q": "1.0",
+        "T": timestamp_ms,
+    }).to_string()
+}
+
+async fn serve(
+    listener: TcpListener,
+    scripts: Vec<Vec<Step>>,
+    received: Arc<Mutex<Vec<Message>>>,
+    connections: Arc<AtomicUsize>,
+) {
+    let mut scripts = scripts.into_iter();
+    while let Ok((tcp, _)) = listener.accept().await {
+        connections.fetch_add(1, Ordering::SeqCst);
+        let Ok(mut ws) = tokio_tungstenite::accept_async(tcp).await else {
+            continue;
+        };
+        let Some(script) = scripts.next() else {
+            let _ = ws.close(None).await;
+            continue;
+        };
+        let received = received.clone();
+        tokio::spawn(async move {
+            // The subscription comes first
+            match ws.next().await {
+                Some(Ok(msg)) => received.lock().unwrap().push(msg),
+                _ => return,
+            }
+            for step in script {
+                let sent = match step {
+                    Step::Ack => ws.send(Message::Text(r#"{"result":null,"id":1}"#.to_string())).await,
+                    Step::Trade(price, timestamp_ms) => ws.send(Message::Text(trade_message(price, timestamp_ms))).await,
+                    Step::Text(text) => ws.send(Message::Text(text)).await,
+                    Step::Binary(data) => ws.send(Message::Binary(data)).await,
+                    Step::Pause(duration) => {
+                        tokio::time::sleep(duration).await;
+                        Ok(())
+                    }
+                    Step::Close => ws.close(None).await,
+                    Step::Drop => return,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            // Record whatever else the client sends until it goes away
+            while let Some(Ok(msg)) = ws.next().await {
+                received.lock().unwrap().push(msg);
+            }
+        });
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+use eth_volatility::config::{Config, ConfigBuilder};
+
+// A setting's name and how to change it
+type Change = (&'static str, fn(&mut Config));
+
+fn config() -> Config {
+    ConfigBuilder::new().binance_ws_url("wss://stream.example.com/ws").build().unwrap()
+}
+
+#[test]
+fn identical_configs_need_no_reconnect_or_restart() {
+    assert!(!config().sources_differ(&config()));
+    assert!(config().restart_required(&config()).is_empty());
+}
+
+#[test]
+fn connection_settings_rebuild_the_collectors() {
+    let changes: Vec<Change> = vec![
+        ("PING_INTERVAL_SECONDS", |c| c.ping_interval += Duration::from_secs(1)),
+        ("STALE_TIMEOUT_SECONDS", |c| c.stale_timeout = Some(Duration::from_secs(7))),
+        ("READ_TIMEOUT_SECONDS", |c| c.read_timeout = Some(Duration::from_secs(7))),
+        ("READ_TIMEOUT_RECONNECT", |c| c.read_timeout_reconnect = !c.read_timeout_reconnect),
+        ("MAX_CLOCK_SKEW_SECONDS", |c| c.max_clock_skew += chrono::Duration::seconds(1)),
+        ("MIN_RECONNECT_INTERVAL_MS", |c| c.min_reconnect_interval += Duration::from_millis(1)),
+        ("RECONNECT_BASE_MS", |c| c.reconnect_base += Duration::from_millis(1)),
+        ("RECONNECT_MAX_MS", |c| c.reconnect_max += Duration::from_millis(1)),
+        ("WARMUP_TICKS", |c| c.warmup_ticks += 1),
+        ("RECONNECT_LOG_EVERY", |c| c.reconnect_log_every += 1),
+    ];
+    for (var, change) in changes {
+        let mut reloaded = config();
+        change(&mut reloaded);
+        assert!(reloaded.sources_differ(&config()), "{} change did not reconnect", var);
+    }
+}
+
+#[test]
+fn startup_only_settings_are_reported_as_needing_a_restart() {
+    let mut reloaded = config();
+    reloaded.binance_rest_base = "https://api.example.com".to_string();
+    reloaded.ws_compression = !reloaded.ws_compression;
+    assert_eq!(reloaded.restart_required(&config()), ["BINANCE_REST_BASE", "WS_COMPRESSION"]);
+    assert!(!reloaded.sources_differ(&config()));
+}
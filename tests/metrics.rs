@@ -0,0 +1,34 @@
+#![cfg(feature = "metrics")]
+
+use std::sync::Arc;
+use eth_volatility::metrics::{self, Metrics};
+
+#[tokio::test]
+async fn metrics_endpoint_serves_the_exposition_format() {
+    let metrics = Arc::new(Metrics::new().unwrap());
+    metrics.current_volatility.set(0.42);
+    metrics.price_history_len.set(17);
+    Metrics::sync_counter(&metrics.binance_reconnects_total, 3);
+
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+        .serve(metrics::router(metrics).into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    let response = reqwest::get(format!("http://{}/metrics", addr)).await.unwrap();
+    assert!(response.status().is_success());
+    let body = response.text().await.unwrap();
+    for line in [
+        "# TYPE current_volatility gauge",
+        "current_volatility 0.42",
+        "last_price 0",
+        "price_history_len 17",
+        "# TYPE binance_reconnects_total counter",
+        "binance_reconnects_total 3",
+        "binance_consecutive_errors 0",
+        "price_fetch_errors_total 0",
+        "return_anomalies_total 0",
+    ] {
+        assert!(body.lines().any(|l| l == line), "missing {:?} in:\n{}", line, body);
+    }
+}
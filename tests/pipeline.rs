@@ -0,0 +1,175 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use common::{MockBinanceServer, Step};
+use eth_volatility::hooks::HookRegistry;
+use eth_volatility::manual::ManualCollector;
+use eth_volatility::pipeline::MultiSymbolPipeline;
+use eth_volatility::price_collector::{BinanceCollector, BoxedCollector, PricePoint};
+use eth_volatility::volatility::VolatilityCalculator;
+
+// 2023-11-14T22:13:20Z
+const T0: i64 = 1_700_000_000_000;
+
+fn point(seconds: i64, price: f64) -> PricePoint {
+    PricePoint {
+        timestamp: DateTime::<Utc>::from_timestamp_millis(T0 + seconds * 1_000).unwrap(),
+        price,
+        source: "Manual".to_string(),
+        spread: None,
+        volume: None,
+    }
+}
+
+#[tokio::test]
+async fn hooks_see_every_price_and_estimate_and_survive_a_panicking_one() {
+    let collector = ManualCollector::new();
+    let feed = collector.feed();
+    for (i, price) in [2500.0, 2510.0, 2505.0, 2515.0].into_iter().enumerate() {
+        feed.push(point(i as i64 * 60, price));
+    }
+    let collector = Mutex::new(Some(collector));
+
+    let prices = Arc::new(AtomicUsize::new(0));
+    let estimates = Arc::new(AtomicUsize::new(0));
+    let (seen_prices, seen_estimates) = (prices.clone(), estimates.clone());
+    let pipeline = MultiSymbolPipeline::new(
+        vec!["ETHUSDT".to_string()],
+        Duration::from_millis(1),
+        move |_| Box::new(collector.lock().unwrap().take().unwrap()) as BoxedCollector,
+        || VolatilityCalculator::new(chrono::Duration::hours(1)).with_min_samples(2),
+    )
+    .with_hooks(move |_| {
+        let mut hooks = HookRegistry::new();
+        hooks.on_price(|_| panic!("a broken hook"));
+        let prices = seen_prices.clone();
+        hooks.on_price(move |_| {
+            prices.fetch_add(1, Ordering::SeqCst);
+        });
+        let estimates = seen_estimates.clone();
+        hooks.on_volatility(move |result| {
+            assert!(result.annualized_volatility > 0.0);
+            estimates.fetch_add(1, Ordering::SeqCst);
+        });
+        hooks
+    });
+
+    let tasks = pipeline.spawn();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while feed.pending() > 0 || prices.load(Ordering::SeqCst) < 4 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.unwrap();
+    tasks.iter().for_each(|task| task.abort());
+
+    assert_eq!(prices.load(Ordering::SeqCst), 4);
+    // Two returns (three prices) are the first that can report
+    assert_eq!(estimates.load(Ordering::SeqCst), 2);
+    assert!(pipeline.latest().read().unwrap()["ETHUSDT"].is_some());
+}
+
+#[tokio::test]
+async fn each_symbol_gets_its_own_feed_and_window() {
+    let feeds = [("ETHUSDT", [2500.0, 2510.0, 2505.0, 2515.0]), ("BTCUSDT", [40000.0, 40400.0, 39800.0, 40100.0])];
+    let mut servers = HashMap::new();
+    for (symbol, prices) in feeds {
+        // Spaced out so each trade is fetched before the next supersedes it
+        let mut script = vec![Step::Ack];
+        for (i, price) in prices.iter().enumerate() {
+            script.push(Step::Trade(*price, T0 + i as i64 * 60_000));
+            script.push(Step::Pause(Duration::from_millis(100)));
+        }
+        servers.insert(symbol, MockBinanceServer::start(vec![script]).await);
+    }
+    let urls: HashMap<_, _> = servers.iter().map(|(symbol, server)| (symbol.to_string(), server.url())).collect();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorded = seen.clone();
+    let pipeline = MultiSymbolPipeline::new(
+        vec!["ETHUSDT".to_string(), "BTCUSDT".to_string()],
+        Duration::from_millis(1),
+        move |symbol| Box::new(BinanceCollector::new(urls[symbol].clone(), symbol)) as BoxedCollector,
+        || VolatilityCalculator::new(chrono::Duration::hours(1)).with_min_samples(2),
+    )
+    .with_hooks(move |symbol| {
+        let mut hooks = HookRegistry::new();
+        let (symbol, seen) = (symbol.to_string(), recorded.clone());
+        hooks.on_price(move |price| seen.lock().unwrap().push((symbol.clone(), price.source.clone(), price.price)));
+        hooks
+    });
+
+    // The same prices through calculators of their own
+    let expected: HashMap<_, _> = feeds.iter()
+        .map(|(symbol, prices)| {
+            let mut alone = VolatilityCalculator::new(chrono::Duration::hours(1)).with_min_samples(2);
+            for (i, price) in prices.iter().enumerate() {
+                alone.add_price(point(i as i64 * 60, *price));
+            }
+            (symbol.to_string(), alone.calculate_volatility())
+        })
+        .collect();
+
+    let tasks = pipeline.spawn();
+    let latest = pipeline.latest();
+    // The estimate is published just after the hooks run
+    let settled = tokio::time::timeout(Duration::from_secs(5), async {
+        while seen.lock().unwrap().len() < 8 || latest.read().unwrap().iter().any(|(symbol, v)| *v != expected[symbol]) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await;
+    tasks.iter().for_each(|task| task.abort());
+    assert!(settled.is_ok(), "estimates {:?}, expected {:?}", latest.read().unwrap(), expected);
+
+    for (symbol, prices) in feeds {
+        let received: Vec<_> = seen.lock().unwrap().iter()
+            .filter(|(task, _, _)| task == symbol)
+            .map(|(_, source, price)| (source.clone(), *price))
+            .collect();
+        let labelled: Vec<_> = prices.iter().map(|price| (format!("Binance:{}", symbol), *price)).collect();
+        assert_eq!(received, labelled);
+
+        let sent: serde_json::Value = serde_json::from_str(&servers[symbol].received_text()[0]).unwrap();
+        assert_eq!(sent["params"], serde_json::json!([format!("{}@trade", symbol.to_lowercase())]));
+        assert!(expected[symbol].is_some());
+    }
+}
+
+#[tokio::test]
+async fn a_symbol_added_while_running_flags_its_first_estimate_as_a_gap() {
+    let collector = ManualCollector::new();
+    let feed = collector.feed();
+    for (i, price) in [2500.0, 2510.0, 2505.0, 2515.0].into_iter().enumerate() {
+        feed.push(point(i as i64 * 60, price));
+    }
+    let collector = Mutex::new(Some(collector));
+
+    let gaps = Arc::new(Mutex::new(Vec::new()));
+    let seen = gaps.clone();
+    let pipeline = MultiSymbolPipeline::new(
+        Vec::new(),
+        Duration::from_millis(1),
+        move |_| Box::new(collector.lock().unwrap().take().unwrap()) as BoxedCollector,
+        || VolatilityCalculator::new(chrono::Duration::hours(1)).with_min_samples(2),
+    )
+    .with_hooks(move |_| {
+        let mut hooks = HookRegistry::new();
+        let gaps = seen.clone();
+        hooks.on_volatility(move |result| gaps.lock().unwrap().push(result.gap));
+        hooks
+    });
+
+    let task = pipeline.spawn_added("BTCUSDT");
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while gaps.lock().unwrap().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.unwrap();
+    task.abort();
+
+    assert_eq!(*gaps.lock().unwrap(), [true, false]);
+    assert!(pipeline.latest().read().unwrap().contains_key("BTCUSDT"));
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use eth_volatility::annualization::AnnualizationMode;
+use eth_volatility::csv_export::CsvSink;
+use eth_volatility::error::VolatilityError;
+use eth_volatility::price_collector::{PriceCollector, PricePoint};
+use eth_volatility::replay::ReplayCollector;
+use eth_volatility::volatility::VolatilityCalculator;
+
+// 2023-11-14T22:13:20Z
+const T0: i64 = 1_700_000_000_000;
+
+fn point(seconds: i64, price: f64) -> PricePoint {
+    PricePoint {
+        timestamp: DateTime::<Utc>::from_timestamp_millis(T0 + seconds * 1_000).unwrap(),
+        price,
+        source: "Binance".to_string(),
+        spread: None,
+        volume: None,
+    }
+}
+
+#[tokio::test]
+async fn replayed_export_reproduces_a_hand_computed_volatility() {
+    let path = std::env::temp_dir().join(format!("eth-volatility-{}-replay.csv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut sink = CsvSink::open(&path).unwrap();
+    // Written out of order; the replay sorts by timestamp
+    for p in [point(0, 100.0), point(120, 99.0), point(60, 102.0), point(180, 101.0)] {
+        sink.write(&p).unwrap();
+    }
+    drop(sink);
+
+    let replay = ReplayCollector::open(&path).unwrap();
+    let mut calculator = VolatilityCalculator::new(chrono::Duration::hours(1))
+        .with_min_samples(2)
+        .with_annualizer(Box::new(AnnualizationMode::Raw));
+    let mut prices = Vec::new();
+    loop {
+        match replay.get_latest_price().await {
+            Ok(p) => {
+                prices.push(p.price);
+                calculator.add_price(p);
+            }
+            Err(VolatilityError::ReplayFinished(_)) => break,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(prices, [100.0, 102.0, 99.0, 101.0]);
+
+    // Sample standard deviation of the three log returns
+    let returns = [(102.0f64 / 100.0).ln(), (99.0f64 / 102.0).ln(), (101.0f64 / 99.0).ln()];
+    let mean = returns.iter().sum::<f64>() / 3.0;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 2.0;
+    let volatility = calculator.calculate_volatility().unwrap();
+    assert!((volatility - variance.sqrt()).abs() < 1e-12, "{} vs {}", volatility, variance.sqrt());
+}
@@ -0,0 +1,21 @@
+use chrono::Duration;
+use eth_volatility::price_collector::PriceCollector;
+use eth_volatility::synthetic::SyntheticCollector;
+use eth_volatility::volatility::VolatilityCalculator;
+
+#[tokio::test]
+async fn long_synthetic_run_recovers_the_input_volatility() {
+    let collector = SyntheticCollector::new(2500.0, 0.0, 0.8, 7).with_step(Duration::minutes(1));
+    // 20,000 one-minute points fit inside the window, so nothing is evicted
+    let mut calculator = VolatilityCalculator::new(Duration::days(15)).with_min_samples(2);
+    for _ in 0..20_000 {
+        let point = collector.get_latest_price().await.unwrap();
+        assert_eq!(point.source, "Synthetic");
+        calculator.add_price(point);
+    }
+    assert_eq!(calculator.sample_count(), 20_000);
+
+    // The estimate's standard error is about 0.8 / sqrt(2 * 20,000) = 0.004
+    let volatility = calculator.calculate_volatility().unwrap();
+    assert!((volatility - 0.8).abs() < 0.03, "estimated {}", volatility);
+}
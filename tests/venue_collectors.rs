@@ -0,0 +1,252 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use common::{MockBinanceServer, Step};
+use eth_volatility::price_collector::{
+    AggregationStrategy, GeminiCollector, HtxCollector, OkxCollector, PriceAggregator, PriceCollector,
+};
+#[cfg(feature = "coinbase")]
+use eth_volatility::price_collector::CoinbaseCollector;
+#[cfg(feature = "kraken")]
+use eth_volatility::price_collector::KrakenCollector;
+
+// 2023-11-14T22:13:20Z
+const T0: i64 = 1_700_000_000_000;
+
+fn gemini_trade(price: f64, timestamp_ms: i64) -> Step {
+    Step::Text(serde_json::json!({
+        "type": "trade", "symbol": "ETHUSD", "event_id": 1,
+        "timestamp": timestamp_ms, "price": price.to_string(), "quantity": "1", "side": "buy",
+    }).to_string())
+}
+
+// HTX gzips every frame
+fn htx_trade(price: f64, timestamp_ms: i64) -> Step {
+    use std::io::Write;
+    let json = serde_json::json!({
+        "ch": "market.ethusdt.trade.detail", "ts": timestamp_ms,
+        "tick": {"id": 1, "ts": timestamp_ms, "data": [
+            {"id": 1, "ts": timestamp_ms, "price": price, "amount": 0.5, "direction": "buy"},
+        ]},
+    }).to_string();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(json.as_bytes()).unwrap();
+    Step::Binary(encoder.finish().unwrap())
+}
+
+fn okx_trade(price: f64, timestamp_ms: i64) -> Step {
+    Step::Text(serde_json::json!({
+        "arg": {"channel": "trades", "instId": "ETH-USDT"},
+        "data": [{"instId": "ETH-USDT", "px": price.to_string(), "sz": "1", "side": "buy", "ts": timestamp_ms.to_string()}],
+    }).to_string())
+}
+
+#[tokio::test]
+async fn gemini_read_can_be_timed_out_mid_stream() {
+    let server = MockBinanceServer::start(vec![vec![
+        gemini_trade(2500.0, T0),
+        Step::Pause(Duration::from_secs(30)),
+    ]]).await;
+    let collector = GeminiCollector::new(server.url());
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    let started = Instant::now();
+    let stalled = tokio::time::timeout(Duration::from_millis(200), collector.get_latest_price()).await;
+    assert!(stalled.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn okx_read_can_be_timed_out_mid_stream() {
+    let server = MockBinanceServer::start(vec![vec![
+        okx_trade(2500.0, T0),
+        Step::Pause(Duration::from_secs(30)),
+    ]]).await;
+    let collector = OkxCollector::new(server.url());
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    let started = Instant::now();
+    let stalled = tokio::time::timeout(Duration::from_millis(200), collector.get_latest_price()).await;
+    assert!(stalled.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn okx_pings_during_a_quiet_read_and_reports_trade_size() {
+    let server = MockBinanceServer::start(vec![vec![
+        okx_trade(2500.0, T0),
+        Step::Pause(Duration::from_millis(400)),
+        okx_trade(2501.0, T0 + 1_000),
+    ]]).await;
+    let collector = OkxCollector::new(server.url()).with_ping_interval(Duration::from_millis(100));
+
+    let first = collector.get_latest_price().await.unwrap();
+    assert_eq!((first.price, first.volume), (2500.0, Some(1.0)));
+    // The wait for this one outlasts the ping interval several times over
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2501.0);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.received_text().iter().any(|text| text == "ping"), "{:?}", server.received_text());
+    assert_eq!(server.connections(), 1);
+}
+
+#[tokio::test]
+async fn okx_reconnects_after_a_dropped_connection() {
+    let server = MockBinanceServer::start(vec![
+        vec![okx_trade(2500.0, T0), Step::Drop],
+        vec![okx_trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = OkxCollector::new(server.url())
+        .with_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2500.0);
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2600.0);
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+async fn gemini_reports_trade_size_and_reconnects_after_a_dropped_connection() {
+    let server = MockBinanceServer::start(vec![
+        vec![gemini_trade(2500.0, T0), Step::Drop],
+        vec![gemini_trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = GeminiCollector::new(server.url())
+        .with_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+    let first = collector.get_latest_price().await.unwrap();
+    assert_eq!((first.price, first.volume), (2500.0, Some(1.0)));
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2600.0);
+    assert_eq!(server.connections(), 2);
+    assert_eq!(collector.reconnects(), 1);
+}
+
+#[tokio::test]
+async fn gemini_close_sends_a_close_frame() {
+    let server = MockBinanceServer::start(vec![vec![gemini_trade(2500.0, T0)]]).await;
+    let collector = GeminiCollector::new(server.url());
+
+    collector.get_latest_price().await.unwrap();
+    collector.close().await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.received_close());
+}
+
+#[tokio::test]
+async fn htx_reports_trade_size_and_reconnects_after_a_dropped_connection() {
+    let server = MockBinanceServer::start(vec![
+        vec![htx_trade(2500.0, T0), Step::Drop],
+        vec![htx_trade(2600.0, T0 + 5_000)],
+    ]).await;
+    let collector = HtxCollector::new(server.url())
+        .with_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+    let first = collector.get_latest_price().await.unwrap();
+    assert_eq!((first.price, first.volume), (2500.0, Some(0.5)));
+    assert!(collector.get_latest_price().await.is_err());
+    assert_eq!(collector.get_latest_price().await.unwrap().price, 2600.0);
+    assert_eq!(server.connections(), 2);
+    assert_eq!(collector.reconnects(), 1);
+}
+
+#[tokio::test]
+async fn htx_close_sends_a_close_frame() {
+    let server = MockBinanceServer::start(vec![vec![htx_trade(2500.0, T0)]]).await;
+    let collector = HtxCollector::new(server.url());
+
+    collector.get_latest_price().await.unwrap();
+    collector.close().await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.received_close());
+}
+
+#[tokio::test]
+async fn aggregator_queries_slow_sources_concurrently_and_drops_failures() {
+    let gemini = MockBinanceServer::start(vec![vec![
+        Step::Pause(Duration::from_millis(400)),
+        gemini_trade(2500.0, T0),
+    ]]).await;
+    let okx = MockBinanceServer::start(vec![vec![
+        Step::Pause(Duration::from_millis(400)),
+        okx_trade(2510.0, T0),
+    ]]).await;
+    // Nothing listens here once the listener is dropped
+    let refused = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("ws://{}", listener.local_addr().unwrap())
+    };
+    let aggregator = PriceAggregator::new(
+        vec![
+            Box::new(GeminiCollector::new(gemini.url())),
+            Box::new(OkxCollector::new(okx.url())),
+            Box::new(GeminiCollector::new(refused)),
+        ],
+        AggregationStrategy::Mean,
+    );
+
+    let started = Instant::now();
+    let aggregate = aggregator.get_aggregated_price().await.unwrap();
+    // Two 400ms waits one after the other would take at least 800ms
+    assert!(started.elapsed() < Duration::from_millis(750), "took {:?}", started.elapsed());
+    assert_eq!(aggregate.price, 2505.0);
+
+    let attempts: u64 = aggregator.source_stats().iter().map(|stats| stats.attempts).sum();
+    let successes: u64 = aggregator.source_stats().iter().map(|stats| stats.successes).sum();
+    assert_eq!((attempts, successes), (3, 2));
+}
+
+// Trades are nested arrays: [channelID, [[price, volume, time, side, type, misc], ...], "trade", pair]
+#[cfg(feature = "kraken")]
+const KRAKEN_TRADE: &str = r#"[337,[["2500.10000","0.25000000","1700000000.123456","b","m",""],["2500.20000","1.00000000","1700000000.456789","s","l",""]],"trade","ETH/USD"]"#;
+
+#[cfg(feature = "kraken")]
+#[test]
+fn kraken_trade_arrays_parse_to_their_first_trade() {
+    let collector = KrakenCollector::new("ws://unused".to_string(), "eth/usd");
+    let point = collector.parse_trade(KRAKEN_TRADE).unwrap();
+    assert_eq!(point.price, 2500.1);
+    assert_eq!(point.volume, Some(0.25));
+    assert_eq!(point.timestamp.timestamp_millis(), T0 + 123);
+    assert_eq!(point.source, "Kraken:ETH/USD");
+
+    assert!(collector.parse_trade(r#"{"event":"heartbeat"}"#).is_none());
+    assert!(collector.parse_trade(r#"{"event":"subscriptionStatus","status":"subscribed","pair":"ETH/USD"}"#).is_none());
+    assert!(collector.parse_trade(r#"[337,{"a":["2500.1"]},"ticker","ETH/USD"]"#).is_none());
+}
+
+#[cfg(feature = "kraken")]
+#[tokio::test]
+async fn kraken_skips_event_objects_to_the_next_trade() {
+    let server = MockBinanceServer::start(vec![vec![
+        Step::Text(r#"{"event":"systemStatus","status":"online","version":"1.9.0"}"#.to_string()),
+        Step::Text(r#"{"event":"subscriptionStatus","status":"subscribed","pair":"ETH/USD"}"#.to_string()),
+        Step::Text(r#"{"event":"heartbeat"}"#.to_string()),
+        Step::Text(KRAKEN_TRADE.to_string()),
+    ]]).await;
+    let collector = KrakenCollector::new(server.url(), "ETH/USD");
+
+    let point = tokio::time::timeout(Duration::from_secs(5), collector.get_latest_price()).await.unwrap().unwrap();
+    assert_eq!(point.price, 2500.1);
+    assert_eq!(collector.reconnects(), 0);
+}
+
+#[cfg(feature = "coinbase")]
+#[test]
+fn coinbase_tickers_parse_price_spread_and_size() {
+    let collector = CoinbaseCollector::new("ws://unused".to_string(), "eth-usd");
+    let ticker = serde_json::json!({
+        "type": "ticker", "product_id": "ETH-USD", "price": "2500.25",
+        "best_bid": "2500.20", "best_ask": "2500.30", "last_size": "0.75",
+        "time": "2023-11-14T22:13:20.123Z",
+    }).to_string();
+    let point = collector.parse_ticker(&ticker).unwrap();
+    assert_eq!(point.price, 2500.25);
+    assert!((point.spread.unwrap() - 0.1).abs() < 1e-9);
+    assert_eq!(point.volume, Some(0.75));
+    assert_eq!(point.timestamp.timestamp_millis(), T0 + 123);
+    assert_eq!(point.source, "Coinbase:ETH-USD");
+
+    // Subscription acks and heartbeats carry no price
+    assert!(collector.parse_ticker(r#"{"type":"subscriptions","channels":[]}"#).is_none());
+    assert!(collector.parse_ticker(r#"{"type":"heartbeat","sequence":1}"#).is_none());
+}